@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::fmt;
+use super::circuit::Circuit;
+use super::circuit::Gate;
+use super::stabilizer;
+
+// Calibrated on a release build of the statevector engine: time spent per amplitude updated by a gate kernel
+const NANOSECONDS_PER_AMPLITUDE_UPDATE: f64 = 5.0;
+// Sampling scans on average half of the statevector for every shot
+const NANOSECONDS_PER_SAMPLED_AMPLITUDE: f64 = 0.5;
+
+pub struct CircuitAnalysis {
+    pub qubit_count: u8,
+    pub depth: usize,
+    pub gate_count: usize,
+    pub gate_counts: BTreeMap<String, usize>,
+    pub two_qubit_gate_count: usize,
+    pub multi_qubit_gate_count: usize,
+    pub is_clifford: bool,
+    pub statevector_memory_f32: f64,
+    pub statevector_memory_f64: f64,
+    pub shots: u32,
+    pub estimated_runtime: f64,
+}
+
+// Barriers do not act on the state, so they are not counted as gates and do not add to the depth
+pub fn analyze(circuit:&Circuit, qubit_count:u8, shots:u32) -> CircuitAnalysis {
+    let mut indexes = HashSet::new();
+    let mut gate_counts = BTreeMap::new();
+    let mut gate_count = 0;
+    let mut two_qubit_gate_count = 0;
+    let mut multi_qubit_gate_count = 0;
+    let mut is_clifford = true;
+    let mut amplitude_updates = 0f64;
+
+    let amplitude_count = 2f64.powi(qubit_count as i32);
+
+    for step in &circuit.steps {
+        for gate in &step.gates {
+            if matches!(gate.name.as_str(), "barrier" | "snapshot") {
+                continue;
+            }
+
+            indexes.insert(step.index);
+            gate_count += 1;
+            *gate_counts.entry(gate.name.clone()).or_insert(0) += 1;
+
+            match get_qubit_count(gate) {
+                0 | 1 => {},
+                2 => two_qubit_gate_count += 1,
+                _ => multi_qubit_gate_count += 1
+            }
+
+            if !stabilizer::is_clifford_gate(gate) {
+                is_clifford = false;
+            }
+
+            amplitude_updates += get_kernel_count(gate) * amplitude_count / 2f64.powi(gate.controls.len() as i32);
+        }
+    }
+
+    let estimated_runtime = (amplitude_updates * NANOSECONDS_PER_AMPLITUDE_UPDATE
+                            + shots as f64 * amplitude_count * NANOSECONDS_PER_SAMPLED_AMPLITUDE) * 1e-9;
+
+    CircuitAnalysis {
+        qubit_count,
+        depth: indexes.len(),
+        gate_count,
+        gate_counts,
+        two_qubit_gate_count,
+        multi_qubit_gate_count,
+        is_clifford,
+        statevector_memory_f32: 8.0 * amplitude_count,
+        statevector_memory_f64: 16.0 * amplitude_count,
+        shots,
+        estimated_runtime,
+    }
+}
+
+fn get_qubit_count(gate:&Gate) -> usize {
+    let mut qubits:HashSet<u8> = gate.targets.iter().cloned().collect();
+    for control in &gate.controls {
+        qubits.insert(control.target);
+    }
+    for aggregated_gate in &gate.gates {
+        qubits.extend(aggregated_gate.targets.iter());
+    }
+    qubits.len()
+}
+
+// Number of full sweeps over the statevector needed by the engine to apply the gate
+fn get_kernel_count(gate:&Gate) -> f64 {
+    match gate.name.as_ref() {
+        "measure-z" => 0.0,
+        "aggregate" => gate.gates.len() as f64,
+        "qft" | "qft-dagger" => {
+            let targets = gate.targets.len() as f64;
+            targets * (targets + 1.0) / 2.0
+        },
+        _ => 1.0
+    }
+}
+
+impl fmt::Display for CircuitAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "qubits: {}", self.qubit_count)?;
+        writeln!(f, "depth: {}", self.depth)?;
+        writeln!(f, "gates: {}", self.gate_count)?;
+        for (name, count) in &self.gate_counts {
+            writeln!(f, "  {}: {}", name, count)?;
+        }
+        writeln!(f, "two-qubit gates: {}", self.two_qubit_gate_count)?;
+        writeln!(f, "gates on more than two qubits: {}", self.multi_qubit_gate_count)?;
+        writeln!(f, "clifford only: {}", if self.is_clifford { "yes" } else { "no" })?;
+        writeln!(f, "statevector memory (f32): {}", format_bytes(self.statevector_memory_f32))?;
+        writeln!(f, "statevector memory (f64): {}", format_bytes(self.statevector_memory_f64))?;
+        write!(f, "estimated runtime ({} shots): {}", self.shots, format_seconds(self.estimated_runtime))
+    }
+}
+
+fn format_bytes(bytes:f64) -> String {
+    const UNITS: [&str; 9] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB", "ZiB", "YiB"];
+
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", value, UNITS[0])
+    } else if value < 1e6 {
+        format!("{:.1} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1e} {}", value, UNITS[unit])
+    }
+}
+
+fn format_seconds(seconds:f64) -> String {
+    if seconds < 1e-3 {
+        format!("{:.1} µs", seconds * 1e6)
+    } else if seconds < 1.0 {
+        format!("{:.1} ms", seconds * 1e3)
+    } else if seconds < 3600.0 {
+        format!("{:.1} s", seconds)
+    } else if seconds < 1e6 * 3600.0 {
+        format!("{:.1} h", seconds / 3600.0)
+    } else {
+        format!("{:.1e} h", seconds / 3600.0)
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::{BufRead, BufWriter, ErrorKind, Read, Result, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use num_complex::Complex32;
+
+// A checkpoint holds the state of a statevector simulation after a step, so the simulation can continue after a crash.
+// The file is little endian: the magic MOARACKP, the format version (u16), the qubit count (u8), the index of the last step applied (u16),
+// the measurements (u8 count, then qubit and bit pairs), the circuit and the metadata (u32 length, then UTF-8 text),
+// the amplitudes (u64 count, then re and im as f32) and the CRC-32 of everything before it (u32).
+
+const MAGIC: &[u8] = b"MOARACKP";
+const VERSION: u16 = 1;
+const DEFAULT_INTERVAL_MINUTES: f64 = 10.0;
+
+pub struct Checkpoint {
+    pub qubit_count:u8,
+    pub step_index:u16,
+    pub measurements:HashMap<u8,u8>,
+    pub circuit:String,
+    pub metadata:String,
+    pub statevector:Vec<Complex32>,
+}
+
+impl Checkpoint {
+    pub fn write(&self, writer:&mut dyn Write) -> Result<()> {
+        write_checkpoint(writer, self.qubit_count, self.step_index, &self.measurements, &self.circuit, &self.metadata, &self.statevector)
+    }
+
+    // Reads the checkpoint as a stream and checks the CRC as it goes, so only the statevector is held in memory
+    pub fn read(reader:&mut dyn BufRead) -> Checkpoint {
+        let mut reader = CrcReader { inner: reader, crc: !0 };
+
+        let mut magic = [0u8; MAGIC.len()];
+        if reader.inner.read_exact(&mut magic).is_err() || magic != MAGIC {
+            panic!("The file is not a moara checkpoint");
+        }
+        reader.crc = get_crc(reader.crc, &magic);
+
+        let version = u16::from_le_bytes(reader.take::<2>());
+        if version != VERSION {
+            panic!("Unsupported checkpoint version {}. This version of moara reads version {}", version, VERSION);
+        }
+
+        let qubit_count = reader.take::<1>()[0];
+        let step_index = u16::from_le_bytes(reader.take::<2>());
+
+        let measurement_count = reader.take::<1>()[0];
+        let measurements = (0..measurement_count).map(|_| {
+            let [qubit, bit] = reader.take::<2>();
+            (qubit, bit)
+        }).collect();
+
+        let circuit = reader.take_text();
+        let metadata = reader.take_text();
+
+        let amplitude_count = u64::from_le_bytes(reader.take::<8>());
+        if qubit_count >= 64 || amplitude_count != 1 << qubit_count {
+            panic!("The checkpoint has {} amplitudes for {} qubits", amplitude_count, qubit_count);
+        }
+        let mut statevector = Vec::with_capacity(amplitude_count as usize);
+        for _ in 0..amplitude_count {
+            let re = f32::from_le_bytes(reader.take::<4>());
+            let im = f32::from_le_bytes(reader.take::<4>());
+            statevector.push(Complex32::new(re, im));
+        }
+
+        let crc = !reader.crc;
+        let checksum = u32::from_le_bytes(reader.take::<4>());
+        if crc != checksum || !reader.inner.fill_buf().map_or(true, |rest| rest.is_empty()) {
+            panic!("The checkpoint is corrupted: its checksum does not match");
+        }
+
+        Checkpoint { qubit_count, step_index, measurements, circuit, metadata, statevector }
+    }
+}
+
+// Writes the checkpoints of a simulation every given number of steps or minutes, whichever comes first
+pub struct Checkpointer {
+    path:PathBuf,
+    every_steps:Option<u32>,
+    interval:Option<Duration>,
+    metadata:String,
+    circuit:String,
+    steps:u32,
+    last_write:Instant,
+}
+
+impl Checkpointer {
+    // Without a number of steps or minutes the checkpoints are written every 10 minutes.
+    // The metadata is stored as is, for the program that resumes the simulation.
+    pub fn new(path:PathBuf, every_steps:Option<u32>, every_minutes:Option<f64>, metadata:String) -> Checkpointer {
+        if every_steps == Some(0) {
+            panic!("The number of steps between checkpoints must be at least 1");
+        }
+        let every_minutes = match (every_steps, every_minutes) {
+            (None, None) => Some(DEFAULT_INTERVAL_MINUTES),
+            (_, every_minutes) => every_minutes
+        };
+
+        Checkpointer {
+            path,
+            every_steps,
+            interval: every_minutes.map(|minutes| Duration::from_secs_f64(minutes*60.0)),
+            metadata,
+            circuit: String::new(),
+            steps: 0,
+            last_write: Instant::now(),
+        }
+    }
+
+    pub(crate) fn set_circuit(&mut self, circuit:String) {
+        self.circuit = circuit;
+    }
+
+    pub(crate) fn after_step(&mut self, statevector:&[Complex32], measurements:&HashMap<u8,u8>, qubit_count:u8, step_index:u16) {
+        self.steps += 1;
+        let steps_due = self.every_steps.is_some_and(|every_steps| self.steps >= every_steps);
+        let time_due = self.interval.is_some_and(|interval| self.last_write.elapsed() >= interval);
+        if !steps_due && !time_due {
+            return;
+        }
+
+        // A checkpoint that cannot be written should not stop the simulation, the next one is tried at the next interval
+        if let Err(err) = self.write(statevector, measurements, qubit_count, step_index) {
+            eprintln!("Warning: cannot write the checkpoint {}: {}", self.path.display(), err);
+        }
+
+        self.steps = 0;
+        self.last_write = Instant::now();
+    }
+
+    // The checkpoint is written next to the previous one and then renamed, so a crash while writing keeps the previous checkpoint
+    fn write(&self, statevector:&[Complex32], measurements:&HashMap<u8,u8>, qubit_count:u8, step_index:u16) -> Result<()> {
+        let mut temporary_path = self.path.clone().into_os_string();
+        temporary_path.push(".tmp");
+
+        let mut writer = BufWriter::new(File::create(&temporary_path)?);
+        write_checkpoint(&mut writer, qubit_count, step_index, measurements, &self.circuit, &self.metadata, statevector)?;
+        // The contents must be on disk before the rename, or a crash can leave an empty checkpoint in place of the previous one
+        writer.into_inner()?.sync_all()?;
+
+        fs::rename(&temporary_path, &self.path)
+    }
+}
+
+// Writes straight from the simulation state, so the statevector is not copied
+fn write_checkpoint(writer:&mut dyn Write, qubit_count:u8, step_index:u16, measurements:&HashMap<u8,u8>, circuit:&str, metadata:&str, statevector:&[Complex32]) -> Result<()> {
+    let mut writer = CrcWriter { inner: writer, crc: !0 };
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&[qubit_count])?;
+    writer.write_all(&step_index.to_le_bytes())?;
+
+    let mut measurements:Vec<_> = measurements.iter().collect();
+    measurements.sort();
+    writer.write_all(&[measurements.len() as u8])?;
+    for (qubit, bit) in measurements {
+        writer.write_all(&[*qubit, *bit])?;
+    }
+
+    for text in [circuit, metadata].iter() {
+        writer.write_all(&(text.len() as u32).to_le_bytes())?;
+        writer.write_all(text.as_bytes())?;
+    }
+
+    writer.write_all(&(statevector.len() as u64).to_le_bytes())?;
+    for amplitude in statevector {
+        writer.write_all(&amplitude.re.to_le_bytes())?;
+        writer.write_all(&amplitude.im.to_le_bytes())?;
+    }
+
+    let crc = !writer.crc;
+    writer.inner.write_all(&crc.to_le_bytes())?;
+    writer.inner.flush()
+}
+
+struct CrcWriter<'a> {
+    inner:&'a mut dyn Write,
+    crc:u32,
+}
+
+impl Write for CrcWriter<'_> {
+    fn write(&mut self, buffer:&[u8]) -> Result<usize> {
+        let written = self.inner.write(buffer)?;
+        self.crc = get_crc(self.crc, &buffer[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+struct CrcReader<'a> {
+    inner:&'a mut dyn BufRead,
+    crc:u32,
+}
+
+impl CrcReader<'_> {
+    fn take<const N:usize>(&mut self) -> [u8; N] {
+        let mut value = [0u8; N];
+        self.read_into(&mut value);
+        value
+    }
+
+    fn take_text(&mut self) -> String {
+        let length = u32::from_le_bytes(self.take::<4>()) as usize;
+        let mut text = Vec::new();
+        // The length is not trusted for the allocation, a corrupted one should not reserve gigabytes
+        let read = (&mut *self.inner).take(length as u64).read_to_end(&mut text).unwrap_or_else(|err| panic!("Cannot read the checkpoint: {}", err));
+        if read < length {
+            panic!("The checkpoint is truncated");
+        }
+        self.crc = get_crc(self.crc, &text);
+        String::from_utf8_lossy(&text).to_string()
+    }
+
+    fn read_into(&mut self, buffer:&mut [u8]) {
+        if let Err(err) = self.inner.read_exact(buffer) {
+            match err.kind() {
+                ErrorKind::UnexpectedEof => panic!("The checkpoint is truncated"),
+                _ => panic!("Cannot read the checkpoint: {}", err)
+            }
+        }
+        self.crc = get_crc(self.crc, buffer);
+    }
+}
+
+// CRC-32 as used by zip and png, continued from the given value
+fn get_crc(mut crc:u32, bytes:&[u8]) -> u32 {
+    for byte in bytes {
+        crc = CRC_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+const CRC_TABLE: [u32; 256] = get_crc_table();
+
+const fn get_crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut value = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            value = if value & 1 == 1 { 0xedb88320 ^ (value >> 1) } else { value >> 1 };
+            bit += 1;
+        }
+        table[index] = value;
+        index += 1;
+    }
+    table
+}
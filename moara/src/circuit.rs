@@ -1,19 +1,24 @@
 extern crate serde;
 extern crate serde_with;
 
+use std::collections::HashMap;
 use serde::Deserialize;
+use serde::Serialize;
 use serde_with::serde_as;
 use serde_with::DisplayFromStr;
 use serde_with::PickFirst;
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Circuit
 {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub definitions:Vec<Definition>,
+
     #[serde(default)]
     pub steps:Vec<Step>
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Step
 {
     #[serde(default)]
@@ -24,7 +29,7 @@ pub struct Step
 }
 
 #[serde_as]
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Gate
 {
     pub name:String,
@@ -32,35 +37,45 @@ pub struct Gate
     #[serde(default)]
     pub targets:Vec<u8>,
 
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub controls:Vec<Control>,
 
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub gates:Vec<AggregatedGate>,
 
     #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub bit:Option<u8>,
 
     #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub phi:Option<f32>,
 
     #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub theta:Option<f32>,
 
     #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub lambda:Option<f32>,
 
     #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub root:Option<String>,
+
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dagger:bool,
+
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub arguments:HashMap<String, Angle>,
+
+    // The name of the state recorded by a snapshot pseudo-gate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label:Option<String>,
 }
 
 #[serde_as]
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 #[derive(Clone)]
 pub struct Control 
 {
@@ -69,7 +84,7 @@ pub struct Control
 }
 
 #[serde_as]
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 #[derive(Clone)]
 pub struct AggregatedGate 
 {
@@ -79,24 +94,150 @@ pub struct AggregatedGate
     pub targets:Vec<u8>,
 
     #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub phi:Option<f32>,
 
     #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub theta:Option<f32>,
 
     #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub lambda:Option<f32>,
 
     #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root:Option<String>,
+
+}
+
+// A named subcircuit acting on the qubits 0..qubits-1 of the definition. Steps invoke it like a gate, with the arguments of its parameters
+#[derive(Serialize, Deserialize)]
+pub struct Definition
+{
+    pub name:String,
+
+    pub qubits:u8,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parameters:Vec<String>,
+
+    #[serde(default)]
+    pub steps:Vec<DefinitionStep>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DefinitionStep
+{
+    #[serde(default)]
+    pub index:u16,
+
+    #[serde(default)]
+    pub gates:Vec<DefinitionGate>
+}
+
+// A gate of a definition. Its angles and arguments can be expressions of the parameters of the definition
+#[serde_as]
+#[derive(Serialize, Deserialize)]
+pub struct DefinitionGate
+{
+    pub name:String,
+
     #[serde(default)]
+    pub targets:Vec<u8>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub controls:Vec<Control>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phi:Option<Angle>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theta:Option<Angle>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lambda:Option<Angle>,
+
+    #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub root:Option<String>,
 
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dagger:bool,
+
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub arguments:HashMap<String, Angle>,
+
+    // The name of the state recorded by a snapshot pseudo-gate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label:Option<String>,
+}
+
+// A number or an expression such as "theta/2" or "pi/4"
+#[derive(Serialize, Deserialize)]
+#[derive(Clone)]
+#[serde(untagged)]
+pub enum Angle
+{
+    Value(f64),
+    Expression(String),
+}
+
+fn is_false(value:&bool) -> bool {
+    !value
+}
+
+impl Circuit {
+    // Places every gate in the earliest step after the previous gates acting on any of its qubits
+    pub fn from_gates(gates:Vec<Gate>) -> Circuit {
+        let mut steps:Vec<Step> = Vec::new();
+        let mut next_free_steps:HashMap<u8, usize> = HashMap::new();
+
+        for gate in gates {
+            let qubits = gate.get_qubits();
+            let index = qubits.iter().map(|qubit| *next_free_steps.get(qubit).unwrap_or(&0)).max().unwrap_or(0);
+
+            if index == steps.len() {
+                steps.push(Step { index: index as u16, gates: Vec::new() });
+            }
+            steps[index].gates.push(gate);
+
+            for qubit in qubits {
+                next_free_steps.insert(qubit, index + 1);
+            }
+        }
+
+        Circuit { definitions: Vec::new(), steps }
+    }
 }
 
 impl Gate {
+    pub fn new(name:&str, targets:Vec<u8>, controls:Vec<Control>) -> Gate {
+        Gate {
+            name: name.to_string(),
+            targets,
+            controls,
+            gates: Vec::new(),
+            bit: None,
+            phi: None,
+            theta: None,
+            lambda: None,
+            root: None,
+            dagger: false,
+            arguments: HashMap::new(),
+            label: None,
+        }
+    }
+
+    pub fn get_qubits(&self) -> Vec<u8> {
+        let mut qubits = self.targets.to_vec();
+        qubits.extend(self.controls.iter().map(|control| control.target));
+        for gate in &self.gates {
+            qubits.extend(gate.targets.iter());
+        }
+        qubits
+    }
+
     pub fn get_min_qubit_index(&self) -> u8 {
         let mut min_index = u8::MAX;
 
@@ -0,0 +1,453 @@
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use num_complex::Complex64;
+use serde_json::Value;
+use super::circuit::Circuit;
+use super::circuit::Control;
+use super::circuit::Gate;
+use super::decomposition;
+
+const TOLERANCE: f64 = 1e-10;
+
+// Parses a circuit serialized with cirq.to_json. Returns the circuit and the number of qubits.
+// The qubits are numbered in Cirq's sorted order, so LineQubit(x) becomes qubit x when the line starts at 0 without gaps.
+// Every measurement key gets as many classical bits as it measures qubits, with the keys in order of first appearance.
+pub fn parse(source:&str) -> (Circuit, u8) {
+    let circuit:Value = serde_json::from_str(source).unwrap_or_else(|err| panic!("Invalid Cirq JSON: {}", err));
+    match get_type(&circuit) {
+        "Circuit" | "FrozenCircuit" => {},
+        unknown_type => panic!("Expected a Circuit. Received {}", unknown_type)
+    }
+
+    let moments = match circuit["moments"].as_array() {
+        Some(moments) => moments,
+        None => panic!("The circuit has no moments")
+    };
+    let operations:Vec<&Value> = moments.iter().flat_map(|moment| match moment["operations"].as_array() {
+        Some(operations) => operations.iter().collect::<Vec<&Value>>(),
+        None => panic!("Every moment needs a list of operations")
+    }).collect();
+
+    let mut qubits = BTreeSet::new();
+    for operation in &operations {
+        collect_qubits(operation, &mut qubits);
+    }
+    if qubits.len() > u8::MAX as usize {
+        panic!("At most {} qubits are supported. The circuit uses {}", u8::MAX, qubits.len());
+    }
+
+    let mut parser = Parser {
+        qubits: qubits.into_iter().enumerate().map(|(index, qubit)| (qubit, index as u8)).collect(),
+        keys: HashMap::new(),
+        bit_count: 0,
+        used_qubits: HashSet::new(),
+        gates: Vec::new(),
+    };
+    for operation in operations {
+        parser.apply_operation(operation, Vec::new());
+    }
+
+    (Circuit::from_gates(parser.gates), parser.qubits.len() as u8)
+}
+
+// Sorts like Cirq: line qubits by position, grid qubits by row then column, named qubits by name
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Qubit {
+    Line(i64),
+    Grid(i64, i64),
+    Named(String),
+}
+
+struct Parser {
+    qubits: HashMap<Qubit, u8>,
+    keys: HashMap<String, usize>,
+    bit_count: usize,
+    used_qubits: HashSet<u8>,
+    gates: Vec<Gate>,
+}
+
+impl Parser {
+    // Applies an operation with the controls collected from the enclosing controlled operations
+    fn apply_operation(&mut self, operation:&Value, controls:Vec<Control>) {
+        match get_type(operation) {
+            "GateOperation" => {
+                let qubits:Vec<u8> = get_qubits(operation).iter().map(|qubit| self.qubits[&to_qubit(qubit)]).collect();
+                self.apply_gate(&operation["gate"], &qubits, controls);
+            },
+            "ControlledOperation" => {
+                let control_qubits = get_qubits_of(&operation["controls"]);
+                let values = get_control_values(&operation["control_values"], control_qubits.len());
+
+                let mut all_controls = controls;
+                all_controls.extend(control_qubits.iter().zip(values).map(|(qubit, state)| Control { target: self.qubits[&to_qubit(qubit)], state }));
+                self.apply_operation(&operation["sub_operation"], all_controls);
+            },
+            "TaggedOperation" => self.apply_operation(&operation["sub_operation"], controls),
+            unknown_type => panic!("Operations of type {} are not supported", unknown_type)
+        }
+    }
+
+    fn apply_gate(&mut self, gate:&Value, qubits:&[u8], controls:Vec<Control>) {
+        let gate_type = get_type(gate);
+        match gate_type {
+            "MeasurementGate" => {
+                if !controls.is_empty() {
+                    panic!("Controlled measurements are not supported");
+                }
+                let key = get_key(gate);
+                if self.keys.contains_key(&key) {
+                    panic!("The measurement key {} is used more than once", key);
+                }
+                if let Some(invert_mask) = gate["invert_mask"].as_array() {
+                    if invert_mask.iter().any(|inverted| inverted.as_bool() == Some(true)) {
+                        panic!("Measurements with an invert mask are not supported. Found one with key {}", key);
+                    }
+                }
+
+                self.keys.insert(key, self.bit_count);
+                for qubit in qubits {
+                    let mut gate = Gate::new("measure-z", vec![*qubit], Vec::new());
+                    gate.bit = Some(self.bit_count as u8);
+                    self.add_gate(gate);
+                    self.bit_count += 1;
+                }
+            },
+            "ResetChannel" => {
+                if self.used_qubits.contains(&qubits[0]) {
+                    panic!("reset is supported only on qubits that have not been used yet");
+                }
+            },
+            // a global phase is observable only when it is controlled
+            "GlobalPhaseGate" if controls.is_empty() => {},
+            "IdentityGate" => {
+                for qubit in qubits {
+                    self.add_gate(Gate::new("identity", vec![*qubit], controls.clone()));
+                }
+            },
+            "ControlledGate" => {
+                let control_count = get_number(gate, "num_controls") as usize;
+                let values = get_control_values(&gate["control_values"], control_count);
+
+                let mut all_controls = controls;
+                all_controls.extend(qubits[..control_count].iter().zip(values).map(|(qubit, state)| Control { target: *qubit, state }));
+                self.apply_gate(&gate["sub_gate"], &qubits[control_count..], all_controls);
+            },
+            _ => {
+                let gates = match get_moara_gate(gate, qubits) {
+                    Some(mut moara_gate) => {
+                        moara_gate.controls.extend(controls);
+                        vec![moara_gate]
+                    },
+                    None => {
+                        let matrix = get_matrix(gate);
+                        if matrix.len() != 1 << (2*qubits.len()) {
+                            panic!("The gate {} is applied on {} qubits, which does not match its matrix", gate_type, qubits.len());
+                        }
+                        decomposition::get_unitary_gates(&matrix, qubits, &controls)
+                    }
+                };
+                for gate in gates {
+                    self.add_gate(gate);
+                }
+            }
+        }
+    }
+
+    fn add_gate(&mut self, gate:Gate) {
+        self.used_qubits.extend(gate.get_qubits());
+        self.gates.push(gate);
+    }
+}
+
+// Maps the gates that have a moara equivalent with the same matrix, global phase included
+fn get_moara_gate(gate:&Value, qubits:&[u8]) -> Option<Gate> {
+    let on_one = |qubit:u8| Control { target: qubit, state: String::from("1") };
+    let with_theta = |mut gate:Gate, theta:f64| { gate.theta = Some(theta as f32); gate };
+
+    let single = |moara_name:&str| Gate::new(moara_name, vec![qubits[0]], Vec::new());
+    let controlled = |moara_name:&str| Gate::new(moara_name, vec![qubits[1]], vec![on_one(qubits[0])]);
+    let double = |moara_name:&str| Gate::new(moara_name, vec![qubits[0], qubits[1]], Vec::new());
+
+    let gate_type = get_type(gate);
+    if gate_type == "CSwapGate" {
+        return Some(Gate::new("swap", vec![qubits[1], qubits[2]], vec![on_one(qubits[0])]));
+    }
+    if !is_eigen_gate(gate_type) {
+        return None;
+    }
+
+    let (exponent, global_shift) = get_exponent(gate);
+    let is_one = (exponent - 1.0).abs() < TOLERANCE;
+    // shift -1/2 turns a power of a Pauli product into a rotation exp(-i pi t P / 2)
+    let is_rotation = (global_shift + 0.5).abs() < TOLERANCE;
+    if global_shift.abs() > TOLERANCE && !is_rotation {
+        return None;
+    }
+
+    let moara_gate = match (gate_type, is_rotation) {
+        ("XPowGate", false) | ("_PauliX", false) if is_one => single("pauli-x"),
+        ("YPowGate", false) | ("_PauliY", false) if is_one => single("pauli-y"),
+        ("ZPowGate", false) | ("_PauliZ", false) => with_theta(single("p"), PI*exponent),
+        ("HPowGate", false) if is_one => single("hadamard"),
+        ("XPowGate", true) | ("_PauliX", true) => with_theta(single("rx-theta"), PI*exponent),
+        ("YPowGate", true) | ("_PauliY", true) => with_theta(single("ry-theta"), PI*exponent),
+        ("ZPowGate", true) | ("_PauliZ", true) => with_theta(single("rz-theta"), PI*exponent),
+        ("CXPowGate", false) | ("CNotPowGate", false) if is_one => controlled("pauli-x"),
+        ("CZPowGate", false) => with_theta(controlled("p"), PI*exponent),
+        ("SwapPowGate", false) if is_one => double("swap"),
+        ("ISwapPowGate", false) if is_one => double("iswap"),
+        ("XXPowGate", true) => with_theta(double("xx"), PI*exponent),
+        ("YYPowGate", true) => with_theta(double("yy"), PI*exponent),
+        ("ZZPowGate", true) => with_theta(double("zz"), PI*exponent),
+        ("CCXPowGate", false) if is_one => Gate::new("pauli-x", vec![qubits[2]], vec![on_one(qubits[0]), on_one(qubits[1])]),
+        ("CCZPowGate", false) => with_theta(Gate::new("p", vec![qubits[2]], vec![on_one(qubits[0]), on_one(qubits[1])]), PI*exponent),
+        _ => return None
+    };
+    Some(moara_gate)
+}
+
+fn is_eigen_gate(gate_type:&str) -> bool {
+    matches!(gate_type, "XPowGate" | "YPowGate" | "ZPowGate" | "_PauliX" | "_PauliY" | "_PauliZ" | "HPowGate"
+        | "CXPowGate" | "CNotPowGate" | "CZPowGate" | "SwapPowGate" | "ISwapPowGate"
+        | "XXPowGate" | "YYPowGate" | "ZZPowGate" | "CCXPowGate" | "CCZPowGate")
+}
+
+// Unitary of the gate, with the first qubit as the most significant bit of the row index
+fn get_matrix(gate:&Value) -> Vec<Complex64> {
+    let gate_type = get_type(gate);
+    let one = Complex64::new(1.0, 0.0);
+    let zero = Complex64::new(0.0, 0.0);
+    let i = Complex64::new(0.0, 1.0);
+
+    let pauli_x = vec![zero, one, one, zero];
+    let pauli_y = vec![zero, -i, i, zero];
+    let pauli_z = vec![one, zero, zero, -one];
+    let hadamard:Vec<Complex64> = [one, one, one, -one].iter().map(|element| element/2.0_f64.sqrt()).collect();
+
+    match gate_type {
+        "XPowGate" | "_PauliX" => get_power(&pauli_x, gate),
+        "YPowGate" | "_PauliY" => get_power(&pauli_y, gate),
+        "ZPowGate" | "_PauliZ" => get_power(&pauli_z, gate),
+        "HPowGate" => get_power(&hadamard, gate),
+        "CXPowGate" | "CNotPowGate" => get_power(&get_controlled(&pauli_x, 1), gate),
+        "CZPowGate" => get_power(&get_controlled(&pauli_z, 1), gate),
+        "CCXPowGate" => get_power(&get_controlled(&pauli_x, 2), gate),
+        "CCZPowGate" => get_power(&get_controlled(&pauli_z, 2), gate),
+        "SwapPowGate" => get_power(&get_swap(), gate),
+        "XXPowGate" => get_power(&get_kronecker(&pauli_x, &pauli_x), gate),
+        "YYPowGate" => get_power(&get_kronecker(&pauli_y, &pauli_y), gate),
+        "ZZPowGate" => get_power(&get_kronecker(&pauli_z, &pauli_z), gate),
+        "CSwapGate" => get_controlled(&get_swap(), 1),
+        "Rx" => get_rotation(&pauli_x, get_number(gate, "rads")),
+        "Ry" => get_rotation(&pauli_y, get_number(gate, "rads")),
+        "Rz" => get_rotation(&pauli_z, get_number(gate, "rads")),
+        "MSGate" => get_rotation(&get_kronecker(&pauli_x, &pauli_x), 2.0*get_number(gate, "rads")),
+        "ISwapPowGate" => {
+            // eigenvalues 1 on |00> and |11>, e^(+-i pi t/2) on (|01> +- |10>)/sqrt(2)
+            let (exponent, global_shift) = get_exponent(gate);
+            let phase = Complex64::from_polar(1.0, PI*exponent*global_shift);
+            let (cos, sin) = ((PI*exponent/2.0).cos(), (PI*exponent/2.0).sin());
+            vec![one, zero, zero, zero,
+                 zero, cos*one, sin*i, zero,
+                 zero, sin*i, cos*one, zero,
+                 zero, zero, zero, one].iter().map(|element| element*phase).collect()
+        },
+        "PhasedXPowGate" => {
+            // Z^p X^t Z^-p
+            let phase_exponent = get_number(gate, "phase_exponent");
+            let matrix = get_power(&pauli_x, gate);
+            vec![matrix[0], matrix[1]*Complex64::from_polar(1.0, -PI*phase_exponent), matrix[2]*Complex64::from_polar(1.0, PI*phase_exponent), matrix[3]]
+        },
+        "FSimGate" => {
+            let theta = get_number(gate, "theta");
+            let phi = get_number(gate, "phi");
+            vec![one, zero, zero, zero,
+                 zero, theta.cos()*one, -theta.sin()*i, zero,
+                 zero, -theta.sin()*i, theta.cos()*one, zero,
+                 zero, zero, zero, Complex64::from_polar(1.0, -phi)]
+        },
+        "MatrixGate" => {
+            let mut elements = Vec::new();
+            flatten_matrix(&gate["matrix"], &mut elements);
+            elements
+        },
+        "GlobalPhaseGate" => panic!("Controlled global phases are not supported"),
+        unknown_gate => panic!("Unknown gate {}", unknown_gate)
+    }
+}
+
+// P^t with global shift s for an involution P: e^(i pi t s) ((I + P)/2 + e^(i pi t) (I - P)/2)
+fn get_power(involution:&[Complex64], gate:&Value) -> Vec<Complex64> {
+    let (exponent, global_shift) = get_exponent(gate);
+    let phase = Complex64::from_polar(1.0, PI*exponent*global_shift);
+    let eigenvalue = Complex64::from_polar(1.0, PI*exponent);
+
+    let size = (involution.len() as f64).sqrt() as usize;
+    involution.iter().enumerate().map(|(index, element)| {
+        let identity = if index % (size + 1) == 0 { 1.0 } else { 0.0 };
+        phase*((1.0 + eigenvalue)*identity + (1.0 - eigenvalue)*element)/2.0
+    }).collect()
+}
+
+// exp(-i rads P / 2) for an involution P
+fn get_rotation(involution:&[Complex64], rads:f64) -> Vec<Complex64> {
+    let size = (involution.len() as f64).sqrt() as usize;
+    involution.iter().enumerate().map(|(index, element)| {
+        let identity = if index % (size + 1) == 0 { 1.0 } else { 0.0 };
+        Complex64::new(identity*(rads/2.0).cos(), 0.0) - Complex64::new(0.0, (rads/2.0).sin())*element
+    }).collect()
+}
+
+// The matrix applied when all the control qubits, which come first, are 1
+fn get_controlled(matrix:&[Complex64], control_count:usize) -> Vec<Complex64> {
+    let size = (matrix.len() as f64).sqrt() as usize;
+    let full_size = size << control_count;
+    let offset = full_size - size;
+
+    let mut controlled = vec![Complex64::new(0.0, 0.0); full_size*full_size];
+    for index in 0..offset {
+        controlled[index*full_size + index] = Complex64::new(1.0, 0.0);
+    }
+    for row in 0..size {
+        for column in 0..size {
+            controlled[(offset + row)*full_size + offset + column] = matrix[row*size + column];
+        }
+    }
+    controlled
+}
+
+fn get_kronecker(first:&[Complex64], second:&[Complex64]) -> Vec<Complex64> {
+    let mut product = vec![Complex64::new(0.0, 0.0); 16];
+    for row in 0..4 {
+        for column in 0..4 {
+            product[row*4 + column] = first[(row/2)*2 + column/2]*second[(row%2)*2 + column%2];
+        }
+    }
+    product
+}
+
+fn get_swap() -> Vec<Complex64> {
+    let mut swap = vec![Complex64::new(0.0, 0.0); 16];
+    for (row, column) in [(0, 0), (1, 2), (2, 1), (3, 3)].iter() {
+        swap[row*4 + column] = Complex64::new(1.0, 0.0);
+    }
+    swap
+}
+
+// Matrices are nested lists of numbers or of {"cirq_type": "complex", "real": ..., "imag": ...}
+fn flatten_matrix(value:&Value, elements:&mut Vec<Complex64>) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                flatten_matrix(item, elements);
+            }
+        },
+        Value::Number(number) => elements.push(Complex64::new(number.as_f64().unwrap(), 0.0)),
+        Value::Object(_) if get_type(value) == "complex" => elements.push(Complex64::new(get_number(value, "real"), get_number(value, "imag"))),
+        _ => panic!("Invalid matrix element {}", value)
+    }
+}
+
+fn get_exponent(gate:&Value) -> (f64, f64) {
+    let exponent = if gate["exponent"].is_null() { 1.0 } else { get_number(gate, "exponent") };
+    let global_shift = if gate["global_shift"].is_null() { 0.0 } else { get_number(gate, "global_shift") };
+    (exponent, global_shift)
+}
+
+fn get_number(value:&Value, field:&str) -> f64 {
+    match value[field].as_f64() {
+        Some(number) => number,
+        None if value[field].is_object() => panic!("Symbolic values are not supported. The {} of {} is {}", field, get_type(value), value[field]),
+        None => panic!("{} has no value for {}", get_type(value), field)
+    }
+}
+
+fn get_type(value:&Value) -> &str {
+    match value["cirq_type"].as_str() {
+        Some(cirq_type) => cirq_type,
+        None => panic!("Expected an object with a cirq_type. Received {}", value)
+    }
+}
+
+fn get_key(gate:&Value) -> String {
+    match &gate["key"] {
+        Value::String(key) => key.clone(),
+        // newer versions serialize the key as a MeasurementKey
+        key => match key["name"].as_str() {
+            Some(name) => name.to_string(),
+            None => panic!("Invalid measurement key {}", key)
+        }
+    }
+}
+
+// Control values are a list with one list of allowed values per control, optionally wrapped in a ProductOfSums
+fn get_control_values(value:&Value, control_count:usize) -> Vec<String> {
+    let values = match value {
+        Value::Null => return vec![String::from("1"); control_count],
+        Value::Object(_) => &value["data"],
+        _ => value
+    };
+
+    let values = match values.as_array() {
+        Some(values) if values.len() == control_count => values,
+        _ => panic!("Invalid control values {}", value)
+    };
+    values.iter().map(|allowed| {
+        let allowed = match allowed {
+            Value::Array(allowed) if allowed.len() == 1 => &allowed[0],
+            Value::Array(_) => panic!("Controls with more than one allowed value are not supported"),
+            allowed => allowed
+        };
+        match allowed.as_u64() {
+            Some(0) => String::from("0"),
+            Some(1) => String::from("1"),
+            _ => panic!("Invalid control value {}", allowed)
+        }
+    }).collect()
+}
+
+fn collect_qubits(operation:&Value, qubits:&mut BTreeSet<Qubit>) {
+    match get_type(operation) {
+        "ControlledOperation" => {
+            for qubit in get_qubits_of(&operation["controls"]) {
+                qubits.insert(to_qubit(qubit));
+            }
+            collect_qubits(&operation["sub_operation"], qubits);
+        },
+        "TaggedOperation" => collect_qubits(&operation["sub_operation"], qubits),
+        _ => {
+            for qubit in get_qubits(operation) {
+                qubits.insert(to_qubit(qubit));
+            }
+        }
+    }
+}
+
+fn get_qubits(operation:&Value) -> &Vec<Value> {
+    get_qubits_of(&operation["qubits"])
+}
+
+fn get_qubits_of(value:&Value) -> &Vec<Value> {
+    match value.as_array() {
+        Some(qubits) => qubits,
+        None => panic!("Expected a list of qubits. Received {}", value)
+    }
+}
+
+fn to_qubit(value:&Value) -> Qubit {
+    if !value["dimension"].is_null() && value["dimension"].as_u64() != Some(2) {
+        panic!("Only qubits are supported. Received a qudit of dimension {}", value["dimension"]);
+    }
+
+    match get_type(value) {
+        "LineQubit" | "LineQid" => Qubit::Line(value["x"].as_i64().unwrap_or_else(|| panic!("Invalid line qubit {}", value))),
+        "GridQubit" | "GridQid" => match (value["row"].as_i64(), value["col"].as_i64()) {
+            (Some(row), Some(column)) => Qubit::Grid(row, column),
+            _ => panic!("Invalid grid qubit {}", value)
+        },
+        "NamedQubit" | "NamedQid" => Qubit::Named(value["name"].as_str().unwrap_or_else(|| panic!("Invalid named qubit {}", value)).to_string()),
+        unknown_type => panic!("Unknown qubit type {}", unknown_type)
+    }
+}
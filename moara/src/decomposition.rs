@@ -0,0 +1,223 @@
+use num_complex::Complex64;
+use super::circuit::Control;
+use super::circuit::Gate;
+
+const TOLERANCE: f64 = 1e-10;
+
+// Elementary operations that every exported circuit is reduced to.
+// u3 uses the matrix of gates::u3, so there is no global phase hidden in it.
+#[derive(Clone, Debug)]
+pub enum Operation {
+    GlobalPhase(f64),
+    X(u8),
+    Phase { lambda:f64, target:u8 },
+    U3 { theta:f64, phi:f64, lambda:f64, target:u8 },
+    Cx { control:u8, target:u8 },
+    Cu3 { theta:f64, phi:f64, lambda:f64, control:u8, target:u8 },
+}
+
+// Single qubit unitary applied when every control qubit has the given value. The global phase is kept exactly,
+// so the result can be used as the body of gates that are controlled again.
+pub fn decompose_controlled(matrix:[Complex64; 4], target:u8, controls:&[(u8, bool)]) -> Vec<Operation> {
+    let mut operations = Vec::new();
+
+    let flipped:Vec<u8> = controls.iter().filter(|(_, value)| !value).map(|(qubit, _)| *qubit).collect();
+    operations.extend(flipped.iter().map(|qubit| Operation::X(*qubit)));
+
+    let qubits:Vec<u8> = controls.iter().map(|(qubit, _)| *qubit).collect();
+    add_controlled(&mut operations, matrix, target, &qubits);
+
+    operations.extend(flipped.iter().map(|qubit| Operation::X(*qubit)));
+    operations
+}
+
+// Two qubit unitary in the engine convention: the row index is 2*bit(first) + bit(last).
+// Every two-level unitary is a single qubit gate controlled by the value of the other qubit.
+pub fn decompose_two_qubit(matrix:[Complex64; 16], first:u8, last:u8, controls:&[(u8, bool)]) -> Vec<Operation> {
+    let qubits = [first, last];
+
+    let mut operations = Vec::new();
+    for (target, unitary, two_level_controls) in get_two_level_unitaries(&matrix, 2) {
+        let mut full_controls = controls.to_vec();
+        full_controls.extend(two_level_controls.iter().map(|(position, value)| (qubits[*position], *value)));
+        operations.extend(decompose_controlled(unitary, qubits[target], &full_controls));
+    }
+    operations
+}
+
+// (target position, single qubit unitary, controls as (position, value))
+pub type TwoLevelUnitary = (usize, [Complex64; 4], Vec<(usize, bool)>);
+
+// Reduces a unitary on qubit_count qubits (the first qubit is the most significant bit of the row index) to two-level
+// unitaries between basis states that differ in one bit, taken in Gray code order. Returns them in the order they are
+// applied, with every other qubit as a control.
+pub fn get_two_level_unitaries(matrix:&[Complex64], qubit_count:usize) -> Vec<TwoLevelUnitary> {
+    let size = 1 << qubit_count;
+    if matrix.len() != size*size {
+        panic!("A unitary on {} qubits needs {} elements. Received {}", qubit_count, size*size, matrix.len());
+    }
+
+    let gray_code:Vec<usize> = (0..size).map(|index| index ^ (index >> 1)).collect();
+    let mut a:Vec<Vec<Complex64>> = gray_code.iter().map(|row| gray_code.iter().map(|column| matrix[row*size + column]).collect()).collect();
+
+    // two-level unitaries g (acting on rows (i-1, i) of a) with g_m ... g_1 matrix = identity
+    let mut two_levels:Vec<(usize, [Complex64; 4])> = Vec::new();
+    for column in 0..size-1 {
+        for row in (column+1..size).rev() {
+            let x = a[row-1][column];
+            let y = a[row][column];
+            let norm = (x.norm_sqr() + y.norm_sqr()).sqrt();
+            if norm < TOLERANCE {
+                continue;
+            }
+            let g = [x.conj()/norm, y.conj()/norm, -y/norm, x/norm];
+            if is_identity(&g) {
+                continue;
+            }
+            apply_two_level(&mut a, row, &g);
+            two_levels.push((row, g));
+        }
+    }
+    let last_phase = a[size-1][size-1];
+    let g = [Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0), Complex64::new(0.0, 0.0), last_phase.conj()];
+    if !is_identity(&g) {
+        two_levels.push((size-1, g));
+    }
+
+    // matrix = g_1^† ... g_m^†, so g_m^† is applied first
+    let mut unitaries = Vec::new();
+    for (row, g) in two_levels.iter().rev() {
+        let lower = gray_code[row-1];
+        let upper = gray_code[*row];
+        let changed_bit = lower ^ upper;
+        let target = qubit_count - 1 - changed_bit.trailing_zeros() as usize;
+
+        let mut dagger = [g[0].conj(), g[2].conj(), g[1].conj(), g[3].conj()];
+        if lower & changed_bit != 0 {
+            // the lower row has the target in state 1
+            dagger = [dagger[3], dagger[2], dagger[1], dagger[0]];
+        }
+
+        let controls = (0..qubit_count)
+            .filter(|position| *position != target)
+            .map(|position| (position, lower & (1 << (qubit_count - 1 - position)) != 0))
+            .collect();
+        unitaries.push((target, dagger, controls));
+    }
+    unitaries
+}
+
+// Moara gates for a unitary on the targets (the first target is the most significant bit of the row index).
+// Each two-level unitary becomes a u3 controlled by the other targets. When there are controls the global phase of
+// the u3 is kept with p(2 alpha) rz(-2 alpha), whose product is e^(i alpha) times the identity.
+pub(crate) fn get_unitary_gates(matrix:&[Complex64], targets:&[u8], controls:&[Control]) -> Vec<Gate> {
+    let mut gates = Vec::new();
+    for (target, unitary, two_level_controls) in get_two_level_unitaries(matrix, targets.len()) {
+        let mut gate_controls = controls.to_vec();
+        gate_controls.extend(two_level_controls.iter().map(|(position, value)| Control {
+            target: targets[*position],
+            state: String::from(if *value { "1" } else { "0" })
+        }));
+        let new_gate = |name:&str| Gate::new(name, vec![targets[target]], gate_controls.clone());
+
+        let (alpha, theta, phi, lambda) = get_u3_angles(&unitary);
+        let mut u3 = new_gate("u3");
+        u3.theta = Some(theta as f32);
+        u3.phi = Some(phi as f32);
+        u3.lambda = Some(lambda as f32);
+        gates.push(u3);
+
+        if !gate_controls.is_empty() && alpha.abs() > TOLERANCE {
+            let mut phase = new_gate("p");
+            phase.theta = Some((2.0*alpha) as f32);
+            let mut rotation = new_gate("rz-theta");
+            rotation.theta = Some((-2.0*alpha) as f32);
+            gates.push(phase);
+            gates.push(rotation);
+        }
+    }
+    gates
+}
+
+// Returns (alpha, theta, phi, lambda) with matrix = e^(i alpha) u3(theta, phi, lambda)
+pub fn get_u3_angles(matrix:&[Complex64; 4]) -> (f64, f64, f64, f64) {
+    let cos = matrix[0].norm();
+    let sin = matrix[2].norm();
+    let theta = 2.0*sin.atan2(cos);
+
+    if cos > TOLERANCE {
+        let alpha = matrix[0].arg();
+        let phi_plus_lambda = matrix[3].arg() - alpha;
+        if sin > TOLERANCE {
+            let phi = matrix[2].arg() - alpha;
+            (alpha, theta, phi, phi_plus_lambda - phi)
+        } else {
+            (alpha, theta, 0.0, phi_plus_lambda)
+        }
+    } else {
+        let alpha = matrix[2].arg();
+        (alpha, theta, 0.0, (-matrix[1]).arg() - alpha)
+    }
+}
+
+// C^k(w) = C(v)[c_k] C^(k-1)(X)[c_k] C(v^†)[c_k] C^(k-1)(X)[c_k] C^(k-1)(v), with v^2 = w (Barenco et al., lemma 7.5)
+fn add_controlled(operations:&mut Vec<Operation>, matrix:[Complex64; 4], target:u8, controls:&[u8]) {
+    if is_identity(&matrix) {
+        return;
+    }
+
+    match controls.len() {
+        0 => {
+            let (alpha, theta, phi, lambda) = get_u3_angles(&matrix);
+            if alpha.abs() > TOLERANCE {
+                operations.push(Operation::GlobalPhase(alpha));
+            }
+            operations.push(Operation::U3 { theta, phi, lambda, target });
+        },
+        1 => {
+            let (alpha, theta, phi, lambda) = get_u3_angles(&matrix);
+            operations.push(Operation::Cu3 { theta, phi, lambda, control: controls[0], target });
+            if alpha.abs() > TOLERANCE {
+                operations.push(Operation::Phase { lambda: alpha, target: controls[0] });
+            }
+        },
+        count => {
+            let root = get_square_root(&matrix);
+            let root_dagger = [root[0].conj(), root[2].conj(), root[1].conj(), root[3].conj()];
+            let pauli_x = [Complex64::new(0.0, 0.0), Complex64::new(1.0, 0.0), Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0)];
+            let last = controls[count-1];
+            let rest = &controls[..count-1];
+
+            add_controlled(operations, root, target, &[last]);
+            add_controlled(operations, pauli_x, last, rest);
+            add_controlled(operations, root_dagger, target, &[last]);
+            add_controlled(operations, pauli_x, last, rest);
+            add_controlled(operations, root, target, rest);
+        }
+    }
+}
+
+// Square root of a 2x2 unitary from the Cayley-Hamilton theorem: (m + s I) / t with s^2 = det(m), t^2 = tr(m) + 2s
+fn get_square_root(matrix:&[Complex64; 4]) -> [Complex64; 4] {
+    let trace = matrix[0] + matrix[3];
+    let mut s = (matrix[0]*matrix[3] - matrix[1]*matrix[2]).sqrt();
+    let mut t = (trace + 2.0*s).sqrt();
+    if t.norm() < TOLERANCE {
+        s = -s;
+        t = (trace + 2.0*s).sqrt();
+    }
+    [(matrix[0] + s)/t, matrix[1]/t, matrix[2]/t, (matrix[3] + s)/t]
+}
+
+fn apply_two_level(a:&mut [Vec<Complex64>], row:usize, g:&[Complex64; 4]) {
+    let (upper, lower) = a.split_at_mut(row);
+    for (x, y) in upper[row-1].iter_mut().zip(lower[0].iter_mut()) {
+        let (old_x, old_y) = (*x, *y);
+        *x = g[0]*old_x + g[1]*old_y;
+        *y = g[2]*old_x + g[3]*old_y;
+    }
+}
+
+fn is_identity(matrix:&[Complex64; 4]) -> bool {
+    (matrix[0] - 1.0).norm() < TOLERANCE && matrix[1].norm() < TOLERANCE && matrix[2].norm() < TOLERANCE && (matrix[3] - 1.0).norm() < TOLERANCE
+}
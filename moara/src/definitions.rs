@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use super::circuit::Angle;
+use super::circuit::Circuit;
+use super::circuit::Control;
+use super::circuit::Definition;
+use super::circuit::Gate;
+use super::circuit::Step;
+use super::expression;
+use super::expression::Expression;
+
+// Replaces the gates that invoke a definition by the steps of the definition, and the gates with the dagger flag by their inverse.
+// Every step becomes as many steps as its longest invocation, so the steps are renumbered from 0.
+// Errors name the path of steps and gates through the nested definitions where they occur.
+pub fn expand(circuit:Circuit) -> Circuit {
+    let uses_dagger = circuit.steps.iter().any(|step| step.gates.iter().any(|gate| gate.dagger));
+    if circuit.definitions.is_empty() && !uses_dagger {
+        return circuit;
+    }
+
+    let Circuit { definitions, mut steps } = circuit;
+    let mut expander = Expander { definitions: HashMap::new() };
+    for definition in &definitions {
+        if expander.definitions.insert(definition.name.as_str(), definition).is_some() {
+            panic!("The definition {} is declared more than once", definition.name);
+        }
+    }
+
+    steps.sort_by_key(|step| step.index);
+
+    let mut layers = Vec::new();
+    for step in steps {
+        let mut step_layers = vec![Vec::new()];
+        for mut gate in step.gates {
+            let path = format!("step {} gate {}", step.index, gate.name);
+            if !expander.definitions.contains_key(gate.name.as_str()) {
+                if !gate.arguments.is_empty() {
+                    panic!("{}: arguments are supported only by defined gates", path);
+                }
+                if gate.dagger {
+                    invert(&mut gate, &path);
+                    gate.dagger = false;
+                }
+                step_layers[0].push(gate);
+                continue;
+            }
+
+            let call = Call {
+                name: gate.name,
+                targets: gate.targets,
+                controls: gate.controls,
+                dagger: gate.dagger,
+                arguments: gate.arguments.iter().map(|(name, angle)| (name.clone(), evaluate(angle, &HashMap::new(), &path))).collect(),
+            };
+            merge(&mut step_layers, expander.expand_call(&call, &[], &path));
+        }
+        layers.extend(step_layers);
+    }
+
+    let steps = layers.into_iter().enumerate().map(|(index, gates)| Step { index: index as u16, gates }).collect();
+    Circuit { definitions: Vec::new(), steps }
+}
+
+struct Expander<'a> {
+    definitions: HashMap<&'a str, &'a Definition>,
+}
+
+// A gate or an invocation of a definition, with its qubits, angles and arguments resolved
+struct Call {
+    name: String,
+    targets: Vec<u8>,
+    controls: Vec<Control>,
+    dagger: bool,
+    arguments: HashMap<String, f64>,
+}
+
+impl Expander<'_> {
+    // The steps of the definition applied on the qubits of the call, one list of gates per step
+    fn expand_call(&self, call:&Call, callers:&[&str], path:&str) -> Vec<Vec<Gate>> {
+        let definition = self.definitions[call.name.as_str()];
+        if callers.contains(&definition.name.as_str()) {
+            panic!("{}: the definition {} invokes itself", path, definition.name);
+        }
+        if call.targets.len() != definition.qubits as usize {
+            panic!("{}: the definition {} acts on {} qubits. Received {}", path, definition.name, definition.qubits, call.targets.len());
+        }
+        for parameter in &definition.parameters {
+            if !call.arguments.contains_key(parameter) {
+                panic!("{}: no argument for the parameter {} of {}", path, parameter, definition.name);
+            }
+        }
+        for argument in call.arguments.keys() {
+            if !definition.parameters.contains(argument) {
+                panic!("{}: {} has no parameter {}", path, definition.name, argument);
+            }
+        }
+
+        let mut callers = callers.to_vec();
+        callers.push(&definition.name);
+
+        let mut ordered_steps:Vec<_> = definition.steps.iter().collect();
+        ordered_steps.sort_by_key(|step| step.index);
+
+        let mut layers = Vec::new();
+        for step in ordered_steps {
+            let mut step_layers = vec![Vec::new()];
+            for gate in &step.gates {
+                let gate_path = format!("{} > step {} gate {}", path, step.index, gate.name);
+                let get_qubit = |qubit:u8| match call.targets.get(qubit as usize) {
+                    Some(target) => *target,
+                    None => panic!("{}: the qubit {} is not one of the {} qubits of {}", gate_path, qubit, definition.qubits, definition.name)
+                };
+                let get_angle = |angle:&Option<Angle>| angle.as_ref().map(|angle| evaluate(angle, &call.arguments, &gate_path) as f32);
+
+                let mut controls:Vec<Control> = gate.controls.iter().map(|control| Control { target: get_qubit(control.target), state: control.state.clone() }).collect();
+                if gate.name != "barrier" && gate.name != "snapshot" {
+                    controls.extend(call.controls.iter().cloned());
+                }
+                let inner_call = Call {
+                    name: gate.name.clone(),
+                    targets: gate.targets.iter().map(|target| get_qubit(*target)).collect(),
+                    controls,
+                    dagger: gate.dagger,
+                    arguments: gate.arguments.iter().map(|(name, angle)| (name.clone(), evaluate(angle, &call.arguments, &gate_path))).collect(),
+                };
+
+                let gate_layers = if self.definitions.contains_key(gate.name.as_str()) {
+                    self.expand_call(&inner_call, &callers, &gate_path)
+                } else {
+                    if gate.name.starts_with("measure") {
+                        panic!("{}: measurements are not supported in definitions", gate_path);
+                    }
+                    vec![vec![get_gate(&inner_call, get_angle(&gate.phi), get_angle(&gate.theta), get_angle(&gate.lambda), &gate.root, &gate.label, &gate_path)]]
+                };
+                merge(&mut step_layers, gate_layers);
+            }
+            layers.extend(step_layers);
+        }
+
+        if call.dagger {
+            layers.reverse();
+            for gate in layers.iter_mut().flatten() {
+                invert(gate, path);
+            }
+        }
+        layers
+    }
+}
+
+fn get_gate(call:&Call, phi:Option<f32>, theta:Option<f32>, lambda:Option<f32>, root:&Option<String>, label:&Option<String>, path:&str) -> Gate {
+    if !call.arguments.is_empty() {
+        panic!("{}: arguments are supported only by defined gates", path);
+    }
+
+    let mut gate = Gate::new(&call.name, call.targets.to_vec(), call.controls.to_vec());
+    gate.phi = phi;
+    gate.theta = theta;
+    gate.lambda = lambda;
+    gate.root = root.clone();
+    gate.label = label.clone();
+    if call.dagger {
+        invert(&mut gate, path);
+    }
+    gate
+}
+
+// Gates that run at the same time are merged step by step, so the step that follows starts after the longest of them
+fn merge(layers:&mut Vec<Vec<Gate>>, gate_layers:Vec<Vec<Gate>>) {
+    for (index, gates) in gate_layers.into_iter().enumerate() {
+        if index == layers.len() {
+            layers.push(Vec::new());
+        }
+        layers[index].extend(gates);
+    }
+}
+
+fn evaluate(angle:&Angle, variables:&HashMap<String, f64>, path:&str) -> f64 {
+    match angle {
+        Angle::Value(value) => *value,
+        Angle::Expression(text) => {
+            let expression = expression::parse(text);
+            if let Some(name) = get_unknown_variable(&expression, variables) {
+                panic!("{}: unknown parameter {} in {}", path, name, text);
+            }
+            expression.evaluate(variables)
+        }
+    }
+}
+
+fn get_unknown_variable(expression:&Expression, variables:&HashMap<String, f64>) -> Option<String> {
+    match expression {
+        Expression::Variable(name) if name != "i" && !variables.contains_key(name) => Some(name.clone()),
+        Expression::Negate(operand) | Expression::Function(_, operand) => get_unknown_variable(operand, variables),
+        Expression::Binary(_, left, right) => get_unknown_variable(left, variables).or_else(|| get_unknown_variable(right, variables)),
+        _ => None
+    }
+}
+
+// Turns the gate into its inverse, keeping its qubits and controls
+fn invert(gate:&mut Gate, path:&str) {
+    let negate = |angle:Option<f32>| angle.map(|angle| -angle);
+    let name = gate.name.as_str();
+
+    match name {
+        "identity" | "pauli-x" | "pauli-y" | "pauli-z" | "hadamard" | "hadamard-xy" | "hadamard-yz" | "hadamard-zx"
+            | "swap" | "fswap" | "w" | "a" | "barrier" | "snapshot" => {},
+        "p" | "rx-theta" | "ry-theta" | "rz-theta" | "xx" | "yy" | "zz" | "xy" | "swap-theta" | "givens" => gate.theta = negate(gate.theta),
+        "u1" => gate.lambda = negate(gate.lambda),
+        "u2" | "u3" => {
+            let phi = gate.phi;
+            gate.theta = if name == "u2" { Some(-PI/2.0) } else { negate(gate.theta) };
+            gate.phi = negate(gate.lambda);
+            gate.lambda = negate(phi);
+            gate.name = String::from("u3");
+        },
+        "iswap" => {
+            gate.name = String::from("xy");
+            gate.theta = Some(PI/2.0);
+        },
+        "aggregate" => {
+            gate.gates.reverse();
+            for aggregated_gate in gate.gates.iter_mut() {
+                let mut single_gate = Gate::new(&aggregated_gate.name, aggregated_gate.targets.to_vec(), Vec::new());
+                single_gate.phi = aggregated_gate.phi;
+                single_gate.theta = aggregated_gate.theta;
+                single_gate.lambda = aggregated_gate.lambda;
+                invert(&mut single_gate, path);
+
+                aggregated_gate.name = single_gate.name;
+                aggregated_gate.phi = single_gate.phi;
+                aggregated_gate.theta = single_gate.theta;
+                aggregated_gate.lambda = single_gate.lambda;
+            }
+        },
+        _ => match name.strip_suffix("-dagger") {
+            Some(inverse) => gate.name = inverse.to_string(),
+            None if HAS_DAGGER.contains(&name) => gate.name = format!("{}-dagger", name),
+            None => panic!("{}: the gate {} cannot be inverted", path, name)
+        }
+    }
+}
+
+// Gates whose inverse is the gate with the -dagger suffix
+const HAS_DAGGER: [&str; 16] = ["c", "t", "s", "v", "h", "sqrt-swap", "berkeley", "ecp", "magic", "molmer-sorensen", "cross-resonance",
+    "swap-root", "pauli-x-root", "pauli-y-root", "pauli-z-root", "qft"];
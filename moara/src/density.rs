@@ -0,0 +1,73 @@
+use num_complex::Complex32;
+use num_complex::Complex64;
+
+// Largest number of kept qubits, as the matrix has 4^k entries
+const MAX_KEPT_QUBITS: usize = 14;
+
+// The reduced density matrix of the kept qubits, tracing out the other qubits of the statevector.
+// The result is a row-major 2^k x 2^k matrix. Its row and column indices are made of the bits of the kept qubits
+// in the order they are given, the first qubit being the most significant bit.
+pub fn get_reduced_density_matrix(statevector:&[Complex32], qubit_count:u8, qubits:&[u8]) -> Vec<Complex32> {
+    check_qubits(qubits, qubit_count);
+    if qubits.len() > MAX_KEPT_QUBITS {
+        panic!("The reduced density matrix can keep at most {} qubits. Received {}", MAX_KEPT_QUBITS, qubits.len());
+    }
+
+    get_reduced_density_matrix_f64(statevector, qubit_count, qubits).iter().map(|value| Complex32::new(value.re as f32, value.im as f32)).collect()
+}
+
+// The same matrix in double precision, for the computations that need its eigenvalues
+pub(crate) fn get_reduced_density_matrix_f64(statevector:&[Complex32], qubit_count:u8, qubits:&[u8]) -> Vec<Complex64> {
+    let kept_bits:Vec<usize> = qubits.iter().map(|qubit| 1 << (qubit_count - qubit - 1)).collect();
+    let traced_bits:Vec<usize> = (0..qubit_count).filter(|qubit| !qubits.contains(qubit)).map(|qubit| 1 << (qubit_count - qubit - 1)).collect();
+
+    // the statevector offset of every value of the kept bits
+    let size = 1 << qubits.len();
+    let kept_offsets:Vec<usize> = (0..size).map(|value| get_offset(value, &kept_bits)).collect();
+
+    let mut matrix = vec![Complex64::new(0.0, 0.0); size*size];
+    let mut amplitudes = vec![Complex64::new(0.0, 0.0); size];
+    for traced_value in 0..1usize << traced_bits.len() {
+        let base = get_offset(traced_value, &traced_bits);
+        for (amplitude, offset) in amplitudes.iter_mut().zip(&kept_offsets) {
+            let value = statevector[base | offset];
+            *amplitude = Complex64::new(value.re as f64, value.im as f64);
+        }
+        if amplitudes.iter().all(|amplitude| amplitude.norm_sqr() == 0.0) {
+            continue;
+        }
+
+        for row in 0..size {
+            if amplitudes[row].norm_sqr() == 0.0 {
+                continue;
+            }
+            // the matrix is Hermitian, so only the lower triangle is summed
+            for column in 0..=row {
+                matrix[row*size + column] += amplitudes[row]*amplitudes[column].conj();
+            }
+        }
+    }
+
+    for row in 0..size {
+        for column in row+1..size {
+            matrix[row*size + column] = matrix[column*size + row].conj();
+        }
+    }
+    matrix
+}
+
+pub(crate) fn check_qubits(qubits:&[u8], qubit_count:u8) {
+    for (position, qubit) in qubits.iter().enumerate() {
+        if *qubit >= qubit_count {
+            panic!("The qubit {} is not one of the {} qubits of the circuit", qubit, qubit_count);
+        }
+        if qubits[..position].contains(qubit) {
+            panic!("The qubit {} is given twice", qubit);
+        }
+    }
+}
+
+// Spreads the bits of the value over the given statevector bits, the first bit receiving the most significant bit of the value
+pub(crate) fn get_offset(value:usize, bits:&[usize]) -> usize {
+    bits.iter().enumerate().filter(|(position, _)| value & (1 << (bits.len() - position - 1)) != 0).fold(0, |offset, (_, bit)| offset | bit)
+}
@@ -0,0 +1,567 @@
+use std::f32::consts::PI;
+use super::circuit::Circuit;
+use super::circuit::Control;
+use super::circuit::Step;
+
+// Short names of the gates, the others are drawn with their full name
+const SYMBOLS: [(&str, &str); 31] = [
+    ("identity", "I"), ("pauli-x", "X"), ("pauli-y", "Y"), ("pauli-z", "Z"), ("hadamard", "H"),
+    ("t", "T"), ("t-dagger", "T†"), ("s", "S"), ("s-dagger", "S†"), ("v", "V"), ("v-dagger", "V†"), ("c", "C"), ("c-dagger", "C†"),
+    ("rx-theta", "RX"), ("ry-theta", "RY"), ("rz-theta", "RZ"), ("u1", "U1"), ("u2", "U2"), ("u3", "U3"), ("p", "P"),
+    ("pauli-x-root", "X"), ("pauli-y-root", "Y"), ("pauli-z-root", "Z"),
+    ("pauli-x-root-dagger", "X†"), ("pauli-y-root-dagger", "Y†"), ("pauli-z-root-dagger", "Z†"),
+    ("iswap", "iSWAP"), ("sqrt-swap", "√SWAP"), ("sqrt-swap-dagger", "√SWAP†"), ("qft", "QFT"), ("qft-dagger", "QFT†"),
+];
+
+// Sizes in the SVG diagrams, in pixels
+const SVG_CHARACTER_WIDTH: usize = 9;
+const SVG_QUBIT_SPACING: usize = 50;
+const SVG_MARGIN: usize = 20;
+const SVG_HEADER_HEIGHT: usize = 60;
+
+// Symbols of the control states
+const CONTROL_SYMBOLS: [(&str, &str); 6] = [("1", "●"), ("0", "○"), ("+", "⊕"), ("-", "⊖"), ("+i", "⊗"), ("-i", "⊘")];
+
+// What a column shows on the wire of a qubit
+#[derive(Clone, PartialEq)]
+pub(crate) enum Cell {
+    Wire,
+    Control(String),
+    Swap,
+    // A gate over one or more qubits. Its label is on its first qubit, and the targets of gates with several targets show their position
+    Box { label:Option<Label>, position:Option<usize>, first:bool, last:bool },
+    Measure { basis:char, bit:u8 },
+    Barrier,
+    Snapshot,
+}
+
+// The symbol of a gate with its root and its angles
+#[derive(Clone, PartialEq)]
+pub(crate) struct Label {
+    symbol:String,
+    root:Option<String>,
+    angles:Vec<f32>,
+}
+
+impl Label {
+    fn new(name:&str, angles:&[Option<f32>], root:&Option<String>) -> Label {
+        let symbol = SYMBOLS.iter().find(|(gate_name, _)| *gate_name == name).map_or(name, |(_, symbol)| symbol).to_string();
+        Label { symbol, root: root.clone(), angles: angles.iter().flatten().copied().collect() }
+    }
+
+    pub(crate) fn to_text(&self) -> String {
+        let mut text = self.symbol.clone();
+        if let Some(root) = &self.root {
+            text.push_str(&format!("^1/{}", root));
+        }
+        if !self.angles.is_empty() {
+            let angles:Vec<String> = self.angles.iter().map(|angle| format_angle(*angle)).collect();
+            text.push_str(&format!("({})", angles.join(",")));
+        }
+        text
+    }
+
+    pub(crate) fn to_latex(&self) -> String {
+        let is_symbol = SYMBOLS.iter().any(|(_, symbol)| *symbol == self.symbol);
+        let (symbol, dagger) = match self.symbol.strip_suffix('†') {
+            Some(symbol) => (symbol, true),
+            None => (self.symbol.as_str(), false)
+        };
+        let mut latex = match (is_symbol, symbol.strip_prefix('√')) {
+            (false, _) => format!("\\text{{{}}}", escape_latex(&self.symbol)),
+            (true, Some(radicand)) => format!("\\sqrt{{\\mathrm{{{}}}}}", radicand),
+            (true, None) => format!("\\mathrm{{{}}}", symbol)
+        };
+        if is_symbol && dagger {
+            latex.push_str("^\\dagger");
+        }
+        if let Some(root) = &self.root {
+            latex = format!("{{{}}}^{{1/{}}}", latex, escape_latex(root));
+        }
+        if !self.angles.is_empty() {
+            let angles:Vec<String> = self.angles.iter().map(|angle| format_angle(*angle).replace('π', "\\pi")).collect();
+            latex.push_str(&format!("({})", angles.join(", ")));
+        }
+        latex
+    }
+}
+
+// Gates of a step drawn side by side. The gates of a step that overlap vertically take several columns
+pub(crate) struct Column {
+    pub(crate) step_index:u16,
+    // the label of a snapshot
+    pub(crate) label:Option<String>,
+    pub(crate) cells:Vec<Cell>,
+    // the vertical lines joining the controls and the targets of the gates, from their first qubit to their last
+    pub(crate) links:Vec<(u8, u8)>,
+    // the qubits of the aggregates of several gates, drawn as a group without a line as their gates are independent
+    pub(crate) groups:Vec<(u8, u8)>,
+}
+
+impl Column {
+    pub(crate) fn is_linked_above(&self, qubit:u8) -> bool {
+        self.links.iter().any(|(first, last)| *first < qubit && qubit <= *last)
+    }
+
+    pub(crate) fn is_linked_below(&self, qubit:u8) -> bool {
+        self.links.iter().any(|(first, last)| *first <= qubit && qubit < *last)
+    }
+
+    fn is_free(&self, first:u8, last:u8) -> bool {
+        self.cells[first as usize..=last as usize].iter().all(|cell| *cell == Cell::Wire)
+            && self.links.iter().chain(&self.groups).all(|(other_first, other_last)| *other_last < first || last < *other_first)
+    }
+}
+
+// Draws the circuit as 'text', 'svg' or 'latex' (quantikz). Only the text is wrapped to the width
+pub fn draw(circuit:&Circuit, qubit_count:u8, format:&str, width:Option<usize>) -> String {
+    match format {
+        "text" => draw_text(circuit, qubit_count, width),
+        "svg" => draw_svg(circuit, qubit_count),
+        "latex" => draw_latex(circuit, qubit_count),
+        unknown_format => panic!("Unknown diagram format {}. Use 'text', 'svg' or 'latex'", unknown_format)
+    }
+}
+
+// Draws the circuit with one wire per qubit and the steps in the order of their index. With a width, the diagram is
+// wrapped into blocks of columns that fit in it.
+pub fn draw_text(circuit:&Circuit, qubit_count:u8, width:Option<usize>) -> String {
+    let columns = get_columns(circuit, qubit_count);
+    let names:Vec<String> = (0..qubit_count).map(|qubit| format!("q{}: ", qubit)).collect();
+    let prefix_width = names.iter().map(|name| name.chars().count()).max().unwrap_or(0);
+    let column_widths:Vec<usize> = columns.iter().map(get_text_width).collect();
+
+    // the columns of every block
+    let mut blocks:Vec<Vec<usize>> = vec![Vec::new()];
+    let mut block_width = prefix_width;
+    for (position, column_width) in column_widths.iter().enumerate() {
+        let block = blocks.last_mut().unwrap();
+        if !block.is_empty() && width.is_some_and(|width| block_width + column_width > width) {
+            blocks.push(vec![position]);
+            block_width = prefix_width + column_width;
+        } else {
+            block.push(position);
+            block_width += column_width;
+        }
+    }
+
+    let mut lines = Vec::new();
+    for (block_index, block) in blocks.iter().enumerate() {
+        if block_index > 0 {
+            lines.push(String::new());
+        }
+
+        let mut header = " ".repeat(prefix_width);
+        for position in block {
+            header.push_str(&center(&get_title(&columns, *position), ' ', column_widths[*position]));
+        }
+        lines.push(header.trim_end().to_string());
+
+        for qubit in 0..qubit_count {
+            let mut rows = [" ".repeat(prefix_width), format!("{:>width$}", names[qubit as usize], width = prefix_width), " ".repeat(prefix_width)];
+            for position in block {
+                let cell_rows = draw_text_cell(&columns[*position], qubit, column_widths[*position]);
+                for (row, cell_row) in rows.iter_mut().zip(cell_rows.iter()) {
+                    row.push_str(cell_row);
+                }
+            }
+            lines.extend(rows.iter().map(|row| row.trim_end().to_string()));
+        }
+    }
+
+    lines.join("\n")
+}
+
+// The index of a step is written over its first column, and the label of a snapshot over its column
+fn get_title(columns:&[Column], position:usize) -> String {
+    let column = &columns[position];
+    match &column.label {
+        Some(label) => label.clone(),
+        None if position > 0 && columns[position - 1].step_index == column.step_index && columns[position - 1].label.is_none() => String::new(),
+        None => column.step_index.to_string()
+    }
+}
+
+// The columns of the circuit, with the gates of every step in the first column of the step where they do not overlap other gates
+pub(crate) fn get_columns(circuit:&Circuit, qubit_count:u8) -> Vec<Column> {
+    let mut steps:Vec<&Step> = circuit.steps.iter().collect();
+    steps.sort_by_key(|step| step.index);
+
+    let mut columns:Vec<Column> = Vec::new();
+    for step in steps {
+        let first_column = columns.len();
+        for gate in &step.gates {
+            let mut cells:Vec<(u8, Cell)> = Vec::new();
+            let mut label = None;
+            match gate.name.as_str() {
+                "snapshot" => {
+                    cells.extend((0..qubit_count).map(|qubit| (qubit, Cell::Snapshot)));
+                    label = Some(gate.label.clone().unwrap_or_default());
+                },
+                "barrier" if gate.targets.is_empty() => cells.extend((0..qubit_count).map(|qubit| (qubit, Cell::Barrier))),
+                "barrier" => cells.extend(gate.targets.iter().map(|qubit| (*qubit, Cell::Barrier))),
+                "measure-x" | "measure-y" | "measure-z" => {
+                    let target = gate.targets[0];
+                    let basis = gate.name.chars().last().unwrap().to_ascii_uppercase();
+                    cells.push((target, Cell::Measure { basis, bit: gate.bit.unwrap_or(target) }));
+                },
+                "swap" => cells.extend(gate.targets.iter().map(|qubit| (*qubit, Cell::Swap))),
+                "aggregate" => {
+                    for aggregated_gate in &gate.gates {
+                        let label = Label::new(&aggregated_gate.name, &[aggregated_gate.theta, aggregated_gate.phi, aggregated_gate.lambda], &aggregated_gate.root);
+                        cells.extend(get_box_cells(label, &aggregated_gate.targets));
+                    }
+                },
+                _ => cells.extend(get_box_cells(Label::new(&gate.name, &[gate.theta, gate.phi, gate.lambda], &gate.root), &gate.targets))
+            }
+            cells.extend(gate.controls.iter().map(|Control { target, state }| (*target, Cell::Control(state.clone()))));
+            cells.retain(|(qubit, _)| *qubit < qubit_count);
+            if cells.is_empty() {
+                continue;
+            }
+
+            let first = cells.iter().map(|(qubit, _)| *qubit).min().unwrap();
+            let last = cells.iter().map(|(qubit, _)| *qubit).max().unwrap();
+            // the boxes of gates with several targets need no line between their targets
+            let is_linked = first < last && (!gate.controls.is_empty() || gate.name == "swap");
+            let aggregated_qubits = gate.gates.iter().flat_map(|aggregated_gate| aggregated_gate.targets.iter()).filter(|qubit| **qubit < qubit_count);
+            let group = match (aggregated_qubits.clone().min(), aggregated_qubits.max()) {
+                (Some(group_first), Some(group_last)) if gate.name == "aggregate" && gate.gates.len() > 1 => Some((*group_first, *group_last)),
+                _ => None
+            };
+
+            // snapshots take a column of their own
+            let free_column = columns[first_column..].iter().position(|column| label.is_none() && column.label.is_none() && column.is_free(first, last));
+            let column = match free_column {
+                Some(position) => &mut columns[first_column + position],
+                None => {
+                    columns.push(Column { step_index: step.index, label, cells: vec![Cell::Wire; qubit_count as usize], links: Vec::new(), groups: Vec::new() });
+                    columns.last_mut().unwrap()
+                }
+            };
+            for (qubit, cell) in cells {
+                column.cells[qubit as usize] = cell;
+            }
+            if is_linked {
+                column.links.push((first, last));
+            }
+            column.groups.extend(group);
+        }
+    }
+    columns
+}
+
+// A box from the first to the last target, also covering the qubits between them
+fn get_box_cells(label:Label, targets:&[u8]) -> Vec<(u8, Cell)> {
+    let first = match targets.iter().min() {
+        Some(first) => *first,
+        None => return Vec::new()
+    };
+    let last = *targets.iter().max().unwrap();
+
+    (first..=last).map(|qubit| {
+        let position = targets.iter().position(|target| *target == qubit).filter(|_| targets.len() > 1);
+        let label = if qubit == first { Some(label.clone()) } else { None };
+        (qubit, Cell::Box { label, position, first: qubit == first, last: qubit == last })
+    }).collect()
+}
+
+pub(crate) fn get_control_symbol(state:&str) -> &str {
+    CONTROL_SYMBOLS.iter().find(|(control_state, _)| *control_state == state).map_or(state, |(_, symbol)| symbol)
+}
+
+// Angles close to a simple fraction of π are written as one, the others with at most two decimals
+pub(crate) fn format_angle(angle:f32) -> String {
+    for denominator in [1, 2, 3, 4, 6, 8] {
+        let numerator = angle*denominator as f32/PI;
+        if numerator.round() != 0.0 && (numerator - numerator.round()).abs() < 1e-4 {
+            let coefficient = match numerator.round() as i32 {
+                1 => String::new(),
+                -1 => String::from("-"),
+                numerator => numerator.to_string()
+            };
+            return match denominator {
+                1 => format!("{}π", coefficient),
+                _ => format!("{}π/{}", coefficient, denominator)
+            };
+        }
+    }
+
+    let text = format!("{:.2}", angle);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { String::from("0") } else { text.to_string() }
+}
+
+// The text in a box, without its borders
+fn get_box_text(cell:&Cell) -> Option<String> {
+    match cell {
+        Cell::Box { label: Some(label), position: Some(position), .. } => Some(format!("{} {}", position, label.to_text())),
+        Cell::Box { label: None, position: Some(position), .. } => Some(position.to_string()),
+        Cell::Box { label, .. } => Some(label.as_ref().map_or(String::new(), Label::to_text)),
+        Cell::Measure { basis: 'Z', bit } => Some(format!("M→c{}", bit)),
+        Cell::Measure { basis, bit } => Some(format!("M{}→c{}", basis.to_ascii_lowercase(), bit)),
+        _ => None
+    }
+}
+
+// The width of the column with a wire on both sides of its widest cell, and the brackets of its groups
+fn get_text_width(column:&Column) -> usize {
+    let cell_width = column.cells.iter().map(|cell| get_box_text(cell).map_or(1, |text| text.chars().count() + 4)).max().unwrap_or(1);
+    let title_width = get_title_width(column);
+    let bracket_width = if column.groups.is_empty() { 0 } else { 2 };
+    cell_width.max(title_width) + 2 + bracket_width
+}
+
+fn get_title_width(column:&Column) -> usize {
+    column.label.as_ref().map_or(column.step_index.to_string().len(), |label| label.chars().count())
+}
+
+// The three rows of the cell of the qubit: above its wire, on its wire and below it.
+// The groups are bracketed on both sides, with the wires going through the brackets
+fn draw_text_cell(column:&Column, qubit:u8, width:usize) -> [String; 3] {
+    if column.groups.is_empty() {
+        return draw_text_cell_content(column, qubit, width);
+    }
+
+    let rows = draw_text_cell_content(column, qubit, width - 2);
+    let edges = match column.groups.iter().find(|(first, last)| *first <= qubit && qubit <= *last) {
+        Some((first, last)) => [
+            if qubit == *first { ('┌', '┐') } else { ('┆', '┆') },
+            ('─', '─'),
+            if qubit == *last { ('└', '┘') } else { ('┆', '┆') },
+        ],
+        None => [(' ', ' '), ('─', '─'), (' ', ' ')]
+    };
+    let mut edged_rows = rows.clone();
+    for ((edged_row, row), (left, right)) in edged_rows.iter_mut().zip(rows.iter()).zip(edges.iter()) {
+        *edged_row = format!("{}{}{}", left, row, right);
+    }
+    edged_rows
+}
+
+fn draw_text_cell_content(column:&Column, qubit:u8, width:usize) -> [String; 3] {
+    let above = column.is_linked_above(qubit);
+    let below = column.is_linked_below(qubit);
+    let link = |is_linked:bool| center(if is_linked { "│" } else { " " }, ' ', width);
+
+    let cell = &column.cells[qubit as usize];
+    match cell {
+        Cell::Wire => [link(above), center(if above && below { "┼" } else { "─" }, '─', width), link(below)],
+        Cell::Control(state) => [link(above), center(get_control_symbol(state), '─', width), link(below)],
+        Cell::Swap => [link(above), center("×", '─', width), link(below)],
+        Cell::Barrier => [center("░", ' ', width), center("░", '─', width), center("░", ' ', width)],
+        Cell::Snapshot => [center("┊", ' ', width), center("┊", '─', width), center("┊", ' ', width)],
+        Cell::Box { .. } | Cell::Measure { .. } => {
+            let (first, last) = match cell {
+                Cell::Box { first, last, .. } => (*first, *last),
+                _ => (true, true)
+            };
+            let inner_width = get_box_width(column, qubit);
+            let top = match first {
+                true => format!("┌{}┐", center(if above { "┴" } else { "─" }, '─', inner_width)),
+                false => format!("│{}│", " ".repeat(inner_width))
+            };
+            let bottom = match last {
+                true => format!("└{}┘", center(if below { "┬" } else { "─" }, '─', inner_width)),
+                false => format!("│{}│", " ".repeat(inner_width))
+            };
+            let middle = format!("┤{}├", center(&get_box_text(cell).unwrap(), ' ', inner_width));
+            [center(&top, ' ', width), center(&middle, '─', width), center(&bottom, ' ', width)]
+        }
+    }
+}
+
+// The width inside the borders of the box over the qubit, fitting the text of all its rows
+fn get_box_width(column:&Column, qubit:u8) -> usize {
+    let cells = &column.cells;
+    let mut first = qubit as usize;
+    while first > 0 && matches!(cells[first], Cell::Box { first: false, .. }) {
+        first -= 1;
+    }
+    let mut last = qubit as usize;
+    while last + 1 < cells.len() && matches!(cells[last], Cell::Box { last: false, .. }) {
+        last += 1;
+    }
+    cells[first..=last].iter().filter_map(get_box_text).map(|text| text.chars().count()).max().unwrap_or(0) + 2
+}
+
+// Writes the circuit as a quantikz environment, with the columns of the text diagram.
+// The first qubit of a gate draws the vertical line to its last qubit.
+pub fn draw_latex(circuit:&Circuit, qubit_count:u8) -> String {
+    let columns = get_columns(circuit, qubit_count);
+
+    let mut lines = vec![String::from("\\begin{quantikz}")];
+    for qubit in 0..qubit_count {
+        let mut cells = vec![format!("\\lstick{{$q_{{{}}}$}}", qubit)];
+        cells.extend(columns.iter().map(|column| get_latex_cell(column, qubit)));
+        cells.push(String::from("\\qw"));
+        let end = if qubit + 1 < qubit_count { " \\\\" } else { "" };
+        lines.push(format!("{}{}", cells.join(" & "), end));
+    }
+    lines.push(String::from("\\end{quantikz}"));
+    lines.join("\n")
+}
+
+// The first qubit of a group also draws its dashed box
+fn get_latex_cell(column:&Column, qubit:u8) -> String {
+    let cell = get_latex_gate(column, qubit);
+    match column.groups.iter().find(|(first, _)| *first == qubit) {
+        Some((first, last)) => format!("{} \\gategroup[wires={},steps=1,style={{dashed,rounded corners}}]{{}}", cell, last - first + 1),
+        None => cell
+    }
+}
+
+fn get_latex_gate(column:&Column, qubit:u8) -> String {
+    let link_length = column.links.iter().filter(|(first, _)| *first == qubit).map(|(first, last)| last - first).max();
+    let vertical_wire = link_length.map_or(String::new(), |length| format!("\\vqw{{{}}}", length));
+
+    match &column.cells[qubit as usize] {
+        Cell::Wire => String::from("\\qw"),
+        Cell::Control(state) => match (state.as_str(), link_length) {
+            ("1", Some(length)) => format!("\\ctrl{{{}}}", length),
+            ("1", None) => String::from("\\control{}"),
+            ("0", Some(length)) => format!("\\octrl{{{}}}", length),
+            ("0", None) => String::from("\\ocontrol{}"),
+            (state, _) => format!("\\gate[style={{circle,inner sep=1pt}}]{{{}}}{}", state, vertical_wire)
+        },
+        Cell::Swap => link_length.map_or(String::from("\\targX{}"), |length| format!("\\swap{{{}}}", length)),
+        Cell::Box { label: Some(label), first: true, .. } => {
+            let wires = (qubit as usize..column.cells.len()).find(|row| matches!(column.cells[*row], Cell::Box { last: true, .. })).map_or(1, |last| last - qubit as usize + 1);
+            match wires {
+                1 => format!("\\gate{{{}}}{}", label.to_latex(), vertical_wire),
+                wires => format!("\\gate[wires={}]{{{}}}{}", wires, label.to_latex(), vertical_wire)
+            }
+        },
+        Cell::Box { .. } => String::from("\\qw"),
+        Cell::Measure { basis: 'Z', bit } => format!("\\meter{{\\ensuremath{{c_{{{}}}}}}}{}", bit, vertical_wire),
+        Cell::Measure { basis, bit } => format!("\\meter{{\\ensuremath{{{} \\rightarrow c_{{{}}}}}}}{}", basis, bit, vertical_wire),
+        // slices cross all the wires, so only the first qubit of a barrier or of a snapshot draws one
+        Cell::Barrier if column.cells[..qubit as usize].contains(&Cell::Barrier) => String::from("\\qw"),
+        Cell::Barrier => String::from("\\qw \\slice{}"),
+        Cell::Snapshot if qubit > 0 => String::from("\\qw"),
+        Cell::Snapshot => format!("\\qw \\slice{{{}}}", escape_latex(column.label.as_deref().unwrap_or(""))),
+    }
+}
+
+fn escape_latex(text:&str) -> String {
+    text.chars().map(|character| match character {
+        '_' | '&' | '%' | '#' | '$' | '{' | '}' => format!("\\{}", character),
+        '\\' => String::from("\\textbackslash{}"),
+        '^' => String::from("\\textasciicircum{}"),
+        '~' => String::from("\\textasciitilde{}"),
+        character => character.to_string()
+    }).collect()
+}
+
+// Draws the circuit as an SVG image, with the columns and the symbols of the text diagram
+pub fn draw_svg(circuit:&Circuit, qubit_count:u8) -> String {
+    let columns = get_columns(circuit, qubit_count);
+    let name_width = format!("q{}", qubit_count.saturating_sub(1)).len()*SVG_CHARACTER_WIDTH + SVG_MARGIN;
+    let column_widths:Vec<usize> = columns.iter().map(get_svg_width).collect();
+    let width = 2*SVG_MARGIN + name_width + column_widths.iter().sum::<usize>();
+    let height = SVG_HEADER_HEIGHT + (qubit_count as usize).saturating_sub(1)*SVG_QUBIT_SPACING + 2*SVG_MARGIN;
+
+    let mut elements = vec![
+        format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"monospace\" font-size=\"14\">", width, height),
+        String::from("<rect width=\"100%\" height=\"100%\" fill=\"white\"/>"),
+    ];
+    for qubit in 0..qubit_count {
+        let y = get_svg_y(qubit);
+        elements.push(format!("<text x=\"{}\" y=\"{}\" dominant-baseline=\"middle\">q{}</text>", SVG_MARGIN, y, qubit));
+        elements.push(format!("<line x1=\"{}\" y1=\"{2}\" x2=\"{}\" y2=\"{2}\" stroke=\"black\"/>", SVG_MARGIN + name_width - SVG_MARGIN/2, width - SVG_MARGIN, y));
+    }
+
+    let mut x = SVG_MARGIN + name_width;
+    for (position, column) in columns.iter().enumerate() {
+        let center_x = x + column_widths[position]/2;
+        let title = get_title(&columns, position);
+        if !title.is_empty() {
+            elements.push(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>", center_x, SVG_MARGIN, escape_xml(&title)));
+        }
+        for (first, last) in &column.links {
+            elements.push(format!("<line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\" stroke=\"black\"/>", center_x, get_svg_y(*first), get_svg_y(*last)));
+        }
+        for qubit in 0..qubit_count {
+            elements.extend(draw_svg_cell(column, qubit, center_x));
+        }
+        for (first, last) in &column.groups {
+            elements.push(format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"gray\" stroke-dasharray=\"4 2\"/>",
+                x + SVG_MARGIN/4, get_svg_y(*first) - 22, column_widths[position] - SVG_MARGIN/2, get_svg_y(*last) - get_svg_y(*first) + 44));
+        }
+        x += column_widths[position];
+    }
+
+    elements.push(String::from("</svg>"));
+    elements.join("\n")
+}
+
+fn get_svg_y(qubit:u8) -> usize {
+    SVG_HEADER_HEIGHT + qubit as usize*SVG_QUBIT_SPACING
+}
+
+fn get_svg_width(column:&Column) -> usize {
+    let cell_width = (0..column.cells.len() as u8).map(|qubit| match &column.cells[qubit as usize] {
+        Cell::Box { .. } => get_svg_box_width(column, qubit),
+        Cell::Measure { .. } => 40,
+        _ => 20
+    }).max().unwrap_or(20);
+    cell_width.max(get_title_width(column)*SVG_CHARACTER_WIDTH) + SVG_MARGIN
+}
+
+fn get_svg_box_width(column:&Column, qubit:u8) -> usize {
+    (get_box_width(column, qubit) - 2)*SVG_CHARACTER_WIDTH + SVG_MARGIN
+}
+
+fn draw_svg_cell(column:&Column, qubit:u8, x:usize) -> Vec<String> {
+    let y = get_svg_y(qubit);
+    match &column.cells[qubit as usize] {
+        Cell::Wire => Vec::new(),
+        Cell::Control(state) if state == "1" => vec![format!("<circle cx=\"{}\" cy=\"{}\" r=\"5\" fill=\"black\"/>", x, y)],
+        Cell::Control(state) if state == "0" => vec![format!("<circle cx=\"{}\" cy=\"{}\" r=\"5\" fill=\"white\" stroke=\"black\"/>", x, y)],
+        Cell::Control(state) => vec![
+            format!("<circle cx=\"{}\" cy=\"{}\" r=\"10\" fill=\"white\" stroke=\"black\"/>", x, y),
+            format!("<text x=\"{}\" y=\"{}\" font-size=\"10\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>", x, y, escape_xml(state)),
+        ],
+        Cell::Swap => vec![format!("<path d=\"M {} {} L {} {} M {} {} L {} {}\" stroke=\"black\" stroke-width=\"2\"/>", x - 6, y - 6, x + 6, y + 6, x - 6, y + 6, x + 6, y - 6)],
+        Cell::Box { label, first: true, .. } => {
+            let last = (qubit as usize..column.cells.len()).find(|row| matches!(column.cells[*row], Cell::Box { last: true, .. })).unwrap_or(qubit as usize) as u8;
+            let box_width = get_svg_box_width(column, qubit);
+            let left = x - box_width/2;
+            let mut elements = vec![
+                format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\" stroke=\"black\"/>", left, y - 16, box_width, get_svg_y(last) - y + 32),
+                format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>", x, (y + get_svg_y(last))/2, escape_xml(&label.as_ref().map_or(String::new(), Label::to_text))),
+            ];
+            for row in qubit..=last {
+                if let Cell::Box { position: Some(position), .. } = column.cells[row as usize] {
+                    elements.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"10\" dominant-baseline=\"middle\">{}</text>", left + 4, get_svg_y(row), position));
+                }
+            }
+            elements
+        },
+        Cell::Box { .. } => Vec::new(),
+        Cell::Measure { basis, bit } => {
+            let mut elements = vec![
+                format!("<rect x=\"{}\" y=\"{}\" width=\"40\" height=\"32\" fill=\"white\" stroke=\"black\"/>", x - 20, y - 16),
+                format!("<path d=\"M {} {} A 12 12 0 0 1 {} {}\" fill=\"none\" stroke=\"black\"/>", x - 12, y + 8, x + 12, y + 8),
+                format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\"/>", x, y + 8, x + 8, y - 8),
+                format!("<text x=\"{}\" y=\"{}\" font-size=\"10\" text-anchor=\"middle\">c{}</text>", x, y + 28, bit),
+            ];
+            if *basis != 'Z' {
+                elements.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"10\" dominant-baseline=\"middle\">{}</text>", x - 17, y - 9, basis));
+            }
+            elements
+        },
+        Cell::Barrier => vec![format!("<line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\" stroke=\"gray\" stroke-dasharray=\"4 2\"/>", x, y - SVG_QUBIT_SPACING/2, y + SVG_QUBIT_SPACING/2)],
+        Cell::Snapshot => vec![format!("<line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\" stroke=\"gray\" stroke-dasharray=\"2 2\"/>", x, y - SVG_QUBIT_SPACING/2, y + SVG_QUBIT_SPACING/2)],
+    }
+}
+
+fn escape_xml(text:&str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn center(text:&str, fill:char, width:usize) -> String {
+    let padding = width.saturating_sub(text.chars().count());
+    let left = padding/2;
+    format!("{}{}{}", fill.to_string().repeat(left), text, fill.to_string().repeat(padding - left))
+}
@@ -4,6 +4,7 @@ use std::collections::HashSet;
 use std::collections::HashMap;
 use num_complex::Complex32;
 use super::circuit::Circuit;
+use super::circuit::Step;
 use crate::circuit::Gate;
 use crate::circuit::Control;
 use super::gate_mapper;
@@ -15,225 +16,279 @@ const MEASUREMENT_Z: &str = "measure-z";
 const KNOWN_CONTROL_STATES: [&str; 6] = ["0", "1", "+", "-", "+i", "-i"];
 
 pub fn get_final_statevector(qubit_count:u8, circuit:Circuit) -> (Vec<Complex32>, HashMap<u8,u8>) {
+    let mut statevector = vec![C!(0); 1<<qubit_count];
+    statevector[0] = C!(1);
+
+    get_final_statevector_from(statevector, qubit_count, circuit)
+}
+
+// Runs the circuit on the given statevector instead of |0…0⟩
+pub fn get_final_statevector_from(mut statevector:Vec<Complex32>, qubit_count:u8, circuit:Circuit) -> (Vec<Complex32>, HashMap<u8,u8>) {
+    if statevector.len() != 1<<qubit_count {
+        panic!("The initial statevector has {} amplitudes. Expected {} for {} qubits", statevector.len(), 1usize<<qubit_count, qubit_count);
+    }
     let mut measurements = HashMap::new();
 
+    run_steps(&mut statevector, &mut measurements, qubit_count, circuit, None, &mut |_, _, _| {});
+
+    (statevector, measurements)
+}
+
+// Receives the statevector, the measurements and the index after each step
+pub type StepObserver<'a> = dyn FnMut(&[Complex32], &HashMap<u8,u8>, u16) + 'a;
+
+// Applies the steps that come after the given index in order, calling the observer with the state and the index after each step
+pub fn run_steps(statevector:&mut Vec<Complex32>, measurements:&mut HashMap<u8,u8>, qubit_count:u8, circuit:Circuit, after_index:Option<u16>,
+                 observer:&mut StepObserver) {
     let mut ordered_steps = circuit.steps;
     ordered_steps.sort_by(|a, b| a.index.cmp(&b.index));
 
-    let mut statevector = vec![C!(0); 1<<qubit_count];
-    statevector[0] = C!(1);
-
     for step in ordered_steps {
-        
-        let mut afected_qubits = HashSet::new();
-        
-        for gate in step.gates {
-
-            // barrier gate is useful only when circuit is converted to Qiskit/QASM format
-            // otherwise only provides visual separation betweeen different portions of a circuit 
-            if gate.name == "barrier" {
-              continue;
-            }
+        if matches!(after_index, Some(index) if step.index <= index) {
+            continue;
+        }
+        let index = step.index;
+        apply_step(statevector, measurements, step, qubit_count);
+        observer(statevector, measurements, index);
+    }
+}
 
-            for target in &gate.targets {
-                if measurements.contains_key(target) {
-                    panic!("The qubit {} has been measured. Cannot add gates at step {} after measurement", target, step.index);
-                }
+pub(crate) fn apply_step(statevector:&mut Vec<Complex32>, measurements:&mut HashMap<u8,u8>, step:Step, qubit_count:u8) {
+    
+    let mut afected_qubits = HashSet::new();
+    
+    for gate in step.gates {
+
+        // barrier gate is useful only when circuit is converted to Qiskit/QASM format
+        // otherwise only provides visual separation betweeen different portions of a circuit 
+        // snapshots are recorded by the stepper after the step
+        if matches!(gate.name.as_str(), "barrier" | "snapshot") {
+          continue;
+        }
 
-                if afected_qubits.contains(target) {
-                    panic!("The qubit {} is mentioned twice in step {}", target, step.index);
-                }
-                afected_qubits.insert(*target);
+        for target in &gate.targets {
+            if measurements.contains_key(target) {
+                panic!("The qubit {} has been measured. Cannot add gates at step {} after measurement", target, step.index);
             }
-            
-            for control in &gate.controls {
-                if afected_qubits.contains(&control.target) {
-                    panic!("The qubit {} is mentioned twice in step {}", control.target, step.index);
-                }
-                afected_qubits.insert(control.target);
 
-                if !KNOWN_CONTROL_STATES.contains(&&*control.state) {
-                    panic!("Unknown state {} step {}", control.state, step.index);
-                }
+            if afected_qubits.contains(target) {
+                panic!("The qubit {} is mentioned twice in step {}", target, step.index);
             }
-
-            if gate.targets.len() == 0 && gate.gates.len() == 0 {
-                panic!("No targets and no aggregated gates provided for gate {} at step {}", gate.name, step.index);
+            afected_qubits.insert(*target);
+        }
+        
+        for control in &gate.controls {
+            if afected_qubits.contains(&control.target) {
+                panic!("The qubit {} is mentioned twice in step {}", control.target, step.index);
             }
+            afected_qubits.insert(control.target);
 
-            if gate.name == "aggregate" && gate.targets.len() > 1 {
-                panic!("Too many targets for gate {} at step {}", gate.name, step.index);
-            } else if gate.name != "qft" && gate.name != "qft-dagger" && gate.targets.len() > 2 {
-                panic!("Too many targets for gate {} at step {}", gate.name, step.index);
+            if !KNOWN_CONTROL_STATES.contains(&&*control.state) {
+                panic!("Unknown state {} step {}", control.state, step.index);
             }
+        }
 
-            rotate_single_qubit_states_to_match_control_states(&mut statevector, gate.controls.to_vec(), qubit_count);
-
-            if gate.name == "aggregate" {
-              
-              for aggregated_gate in &gate.gates {
-                let regular_gate = Gate {
-                  name: aggregated_gate.name.clone(),
-                  targets: aggregated_gate.targets.to_vec(),
-                  controls: gate.controls.to_vec(),
-                  phi: aggregated_gate.phi,
-                  theta: aggregated_gate.theta,
-                  lambda: aggregated_gate.lambda,
-                  root: aggregated_gate.root.clone(),
-                  bit: None,
-                  gates: Vec::new()
-                };
-                let single_qubit_operator = gate_mapper::get_single_qubit_operator(&regular_gate);
-                apply_operator(single_qubit_operator, &mut statevector, regular_gate.targets[0], regular_gate.controls, qubit_count);
-              }
-            } else if gate.name == "qft" {
-              apply_qft_gate(&mut statevector,  gate.targets.to_vec(), gate.controls.to_vec(), qubit_count);
-            } else if gate.name == "qft-dagger" {
-              apply_qft_dagger_gate(&mut statevector, gate.targets.to_vec(), gate.controls.to_vec(), qubit_count);
-            } else if gate.targets.len() == 2 {
-                let multi_target_operator = gate_mapper::get_double_target_operator(&gate);
-                apply_double_target_operator(multi_target_operator, &mut statevector, gate.targets.to_vec(), gate.controls.to_vec(), qubit_count);
-            } else {
-                let target = gate.targets[0];
-                if gate.name == MEASUREMENT_X || gate.name == MEASUREMENT_Y || gate.name == MEASUREMENT_Z {
-                    let bit = match gate.bit { Some(bit) => bit, None => target };
-                    if bit >= qubit_count {
-                        panic!("Measurement bit cannot be larger than the qubit count - 1 ({}). Received {} for qubit {}", qubit_count-1, bit, target);
-                    }
-                    measurements.insert(target, bit);
-                    if gate.name == MEASUREMENT_Z {
-                        continue;
-                    }
-                }
+        if gate.targets.len() == 0 && gate.gates.len() == 0 {
+            panic!("No targets and no aggregated gates provided for gate {} at step {}", gate.name, step.index);
+        }
 
-                let single_qubit_operator = gate_mapper::get_single_qubit_operator(&gate);
-                apply_operator(single_qubit_operator, &mut statevector, target, gate.controls.to_vec(), qubit_count);
+        if gate.name == "aggregate" && gate.targets.len() > 1 {
+            panic!("Too many targets for gate {} at step {}", gate.name, step.index);
+        } else if gate.name != "qft" && gate.name != "qft-dagger" && gate.targets.len() > 2 {
+            panic!("Too many targets for gate {} at step {}", gate.name, step.index);
+        }
+
+        if gate.name == "aggregate" {
+          
+          for aggregated_gate in &gate.gates {
+            let regular_gate = Gate {
+              name: aggregated_gate.name.clone(),
+              targets: aggregated_gate.targets.to_vec(),
+              controls: gate.controls.to_vec(),
+              phi: aggregated_gate.phi,
+              theta: aggregated_gate.theta,
+              lambda: aggregated_gate.lambda,
+              root: aggregated_gate.root.clone(),
+              bit: None,
+              gates: Vec::new(),
+              dagger: false,
+              arguments: HashMap::new(),
+              label: None
+            };
+            let single_qubit_operator = gate_mapper::get_single_qubit_operator(&regular_gate);
+            apply_operator(single_qubit_operator, statevector, regular_gate.targets[0], regular_gate.controls, qubit_count);
+          }
+        } else if gate.name == "qft" {
+          apply_qft_gate(&mut |operator, target, controls| apply_operator(operator, statevector, target, controls, qubit_count), gate.targets.to_vec(), gate.controls.to_vec());
+        } else if gate.name == "qft-dagger" {
+          apply_qft_dagger_gate(&mut |operator, target, controls| apply_operator(operator, statevector, target, controls, qubit_count), gate.targets.to_vec(), gate.controls.to_vec());
+        } else if gate.targets.len() == 2 {
+            let multi_target_operator = gate_mapper::get_double_target_operator(&gate);
+            apply_double_target_operator(multi_target_operator, statevector, gate.targets.to_vec(), gate.controls.to_vec(), qubit_count);
+        } else {
+            let target = gate.targets[0];
+            if gate.name == MEASUREMENT_X || gate.name == MEASUREMENT_Y || gate.name == MEASUREMENT_Z {
+                let bit = match gate.bit { Some(bit) => bit, None => target };
+                if bit >= qubit_count {
+                    panic!("Measurement bit cannot be larger than the qubit count - 1 ({}). Received {} for qubit {}", qubit_count-1, bit, target);
+                }
+                measurements.insert(target, bit);
+                if gate.name == MEASUREMENT_Z {
+                    continue;
+                }
             }
 
-            undo_rotate_single_qubit_states_to_match_control_states(&mut statevector, gate.controls.to_vec(), qubit_count);
+            let single_qubit_operator = gate_mapper::get_single_qubit_operator(&gate);
+            apply_operator(single_qubit_operator, statevector, target, gate.controls.to_vec(), qubit_count);
         }
     }
-
-    (statevector, measurements)
 }
 
-fn rotate_single_qubit_states_to_match_control_states(statevector: &mut Vec<Complex32>, controls:Vec<Control>, qubit_count:u8){
-  for control in controls {
-    let target = control.target;
-    let state = control.state;
-    if state == "+" || state == "-" {
-      let single_qubit_operator = gate_mapper::get_qubit_rotation_operator("X");
-      apply_operator(single_qubit_operator, statevector, target, Vec::new(), qubit_count);
-    } else if state == "+i" || state == "-i" {
-      let single_qubit_operator = gate_mapper::get_qubit_rotation_operator("Y");
-      apply_operator(single_qubit_operator, statevector, target, Vec::new(), qubit_count);
-    }
-  }
-}
+fn apply_operator(operator:[Complex32; 4], statevector: &mut Vec<Complex32>, target: u8, controls:Vec<Control>, qubit_count:u8) {
+    let layout = get_control_layout(&[target], &controls, qubit_count);
+    let n = 1 << (qubit_count as usize - layout.fixed_bits.len());
 
-fn undo_rotate_single_qubit_states_to_match_control_states(statevector: &mut Vec<Complex32>, controls:Vec<Control>, qubit_count:u8){
-  for control in controls {
-    let target = control.target;
-    let state = control.state;
-    if state == "+" || state == "-" {
-      let single_qubit_operator = gate_mapper::get_qubit_undo_rotation_operator("X");
-      apply_operator(single_qubit_operator, statevector, target, Vec::new(), qubit_count);
-    } else if state == "+i" || state == "-i" {
-      let single_qubit_operator = gate_mapper::get_qubit_undo_rotation_operator("Y");
-      apply_operator(single_qubit_operator, statevector, target, Vec::new(), qubit_count);
-    }
-  }
-}
+    let target_bit = 1 << (qubit_count - target - 1);
 
-fn apply_operator(operator:[Complex32; 4], statevector: &mut Vec<Complex32>, target: u8, controls:Vec<Control>, qubit_count:u8) {
-    let controls_count = controls.len() as u8;
-    let n = 1 << (qubit_count - controls_count - 1);
+    let m00 = operator[0];
+    let m01 = operator[1];
+    let m10 = operator[2];
+    let m11 = operator[3];
 
     for i in 0..n {
-        let mut n_size = qubit_count - controls_count;
+        let base = insert_zero_bits(i, &layout.fixed_bits) | layout.set_bits;
 
-        let mut affected = i;
-        for control in &controls {
-            let control_positon = if control.target < target { control.target } else { control.target-1 };
-            let (affected0, affected1) = get_indexes(affected, control_positon, n_size);
-            affected = if control.state == "1"  || control.state == "-" || control.state == "-i" { affected1 } else { affected0 };
+        if layout.superposed.len() == 1 {
+            let index0 = base;
+            let index1 = base | target_bit;
+            let sv0 = statevector[index0];
+            let sv1 = statevector[index1];
 
-            n_size += 1;
+            statevector[index0] = m00*sv0 + m01*sv1;
+            statevector[index1] = m10*sv0 + m11*sv1;
+            continue;
         }
 
-        let (index0, index1) = get_indexes(affected, target, qubit_count);
-        let sv0 = statevector[index0];
-        let sv1 = statevector[index1];
-        
-        let m00 = operator[0];
-        let m01 = operator[1];
-        let m10 = operator[2];
-        let m11 = operator[3];
+        // project the controls on their states: U_c = I + P⊗(U - I)
+        let mut a0 = C!(0);
+        let mut a1 = C!(0);
+        for (offset, amplitude) in &layout.superposed {
+            a0 += amplitude.conj()*statevector[base | offset];
+            a1 += amplitude.conj()*statevector[base | offset | target_bit];
+        }
+
+        let b0 = (m00 - C!(1))*a0 + m01*a1;
+        let b1 = m10*a0 + (m11 - C!(1))*a1;
 
-        statevector[index0] = m00*sv0 + m01*sv1;
-        statevector[index1] = m10*sv0 + m11*sv1;
+        for (offset, amplitude) in &layout.superposed {
+            statevector[base | offset] += amplitude*b0;
+            statevector[base | offset | target_bit] += amplitude*b1;
+        }
     }
 }
 
 fn apply_double_target_operator(operator:[Complex32; 16], statevector: &mut Vec<Complex32>, targets:Vec<u8>, controls:Vec<Control>, qubit_count:u8) {
-    let controls_count = controls.len() as u8;
-    let n = 1 << (qubit_count - controls_count - 2);
+    let layout = get_control_layout(&targets, &controls, qubit_count);
+    let n = 1 << (qubit_count as usize - layout.fixed_bits.len());
 
-    let target1 = targets[0];
-    let target2 = targets[1];
+    // the first qubit of the operator is the one with the highest index
+    let first = max(targets[0], targets[1]);
+    let last = min(targets[0], targets[1]);
+    let first_bit = 1 << (qubit_count - first - 1);
+    let last_bit = 1 << (qubit_count - last - 1);
+    let target_offsets = [0, last_bit, first_bit, first_bit | last_bit];
 
     for i in 0..n {
-        let mut n_size = qubit_count - controls_count-1;
+        let base = insert_zero_bits(i, &layout.fixed_bits) | layout.set_bits;
+
+        if layout.superposed.len() == 1 {
+            let sv = [statevector[base | target_offsets[0]], statevector[base | target_offsets[1]],
+                      statevector[base | target_offsets[2]], statevector[base | target_offsets[3]]];
+
+            for row in 0..4 {
+                statevector[base | target_offsets[row]] = operator[4*row]*sv[0] + operator[4*row+1]*sv[1] + operator[4*row+2]*sv[2] + operator[4*row+3]*sv[3];
+            }
+            continue;
+        }
+
+        // project the controls on their states: U_c = I + P⊗(U - I)
+        let mut a = [C!(0); 4];
+        for (offset, amplitude) in &layout.superposed {
+            for column in 0..4 {
+                a[column] += amplitude.conj()*statevector[base | offset | target_offsets[column]];
+            }
+        }
+
+        let mut b = [C!(0); 4];
+        for row in 0..4 {
+            b[row] = operator[4*row]*a[0] + operator[4*row+1]*a[1] + operator[4*row+2]*a[2] + operator[4*row+3]*a[3] - a[row];
+        }
+
+        for (offset, amplitude) in &layout.superposed {
+            for row in 0..4 {
+                statevector[base | offset | target_offsets[row]] += amplitude*b[row];
+            }
+        }
+    }
+}
 
-        let mut affected = i;
-        for control in &controls {
-            let control_positon = if control.target < target1 && control.target < target2 { control.target } 
-                          else { if (control.target < target1 && control.target > target2) || (control.target > target1 && control.target < target2)  { control.target-1 }
-                          else { control.target-2 } };
+// Describes how the controls of a gate select the amplitudes it acts on.
+// Controls on |0⟩ and |1⟩ fix a bit of the index. Controls on the X and Y basis states
+// are kept in superposition: every combination of their bits is listed with the amplitude
+// of the control state, so that the controlled gate can be applied as a projector in one sweep.
+pub(crate) struct ControlLayout {
+    pub(crate) fixed_bits: Vec<usize>,
+    pub(crate) set_bits: usize,
+    pub(crate) superposed: Vec<(usize, Complex32)>,
+}
+
+pub(crate) fn get_control_layout(targets:&[u8], controls:&[Control], qubit_count:u8) -> ControlLayout {
+    let mut fixed_bits = Vec::new();
+    let mut set_bits = 0;
+    let mut superposed = vec![(0, C!(1))];
 
-            let (affected0, affected1) = get_indexes(affected, control_positon, n_size);
-            affected = if control.state == "1" || control.state == "-" || control.state == "-i" { affected1 } else { affected0 };
+    for target in targets {
+        fixed_bits.push((qubit_count - target - 1) as usize);
+    }
 
-            n_size += 1;
+    for control in controls {
+        let bit = (qubit_count - control.target - 1) as usize;
+        fixed_bits.push(bit);
+
+        let amplitude1 = match control.state.as_ref() {
+            "0" => continue,
+            "1" => { set_bits |= 1 << bit; continue; },
+            "+" => C!(1),
+            "-" => C!(-1),
+            "+i" => C!(1*i),
+            "-i" => C!(-1*i),
+            unknown_state => panic!("Unknown state {} for control on qubit {}", unknown_state, control.target)
+        };
+        let amplitude0 = C!(1)/2.0_f32.sqrt();
+        let amplitude1 = amplitude1/2.0_f32.sqrt();
+
+        let mut expanded = Vec::with_capacity(2*superposed.len());
+        for (offset, amplitude) in superposed {
+            expanded.push((offset, amplitude*amplitude0));
+            expanded.push((offset | (1 << bit), amplitude*amplitude1));
         }
+        superposed = expanded;
+    }
 
-        let first = max(target1, target2);
-        let last = min(target1, target2);
+    fixed_bits.sort_unstable();
 
-        let (index0, index1) = get_indexes(affected, first, qubit_count);
-        let (index00, index01) = get_indexes(index0, last, qubit_count);
-        let (index10, index11) = get_indexes(index1, last, qubit_count);
+    ControlLayout { fixed_bits, set_bits, superposed }
+}
 
-        let sv00 = statevector[index00];
-        let sv01 = statevector[index01];
-        let sv10 = statevector[index10];
-        let sv11 = statevector[index11];
-        
-        let m0000 = operator[0];
-        let m0001 = operator[1];
-        let m0010 = operator[2];
-        let m0011 = operator[3];
-        let m0100 = operator[4];
-        let m0101 = operator[5];
-        let m0110 = operator[6];
-        let m0111 = operator[7];
-        let m1000 = operator[8];
-        let m1001 = operator[9];
-        let m1010 = operator[10];
-        let m1011 = operator[11];
-        let m1100 = operator[12];
-        let m1101 = operator[13];
-        let m1110 = operator[14];
-        let m1111 = operator[15];
-
-        statevector[index00] = m0000*sv00 + m0001*sv01 + m0010*sv10 + m0011*sv11;
-        statevector[index01] = m0100*sv00 + m0101*sv01 + m0110*sv10 + m0111*sv11;
-        statevector[index10] = m1000*sv00 + m1001*sv01 + m1010*sv10 + m1011*sv11;
-        statevector[index11] = m1100*sv00 + m1101*sv01 + m1110*sv10 + m1111*sv11;
+fn insert_zero_bits(mut i: usize, ascending_bits: &[usize]) -> usize {
+    for bit in ascending_bits {
+        i = ((i >> bit) << (bit+1)) | (i & MASKS[*bit]);
     }
+    i
 }
 
-fn apply_qft_gate(statevector: &mut Vec<Complex32>, targets:Vec<u8>, controls:Vec<Control>, qubit_count:u8) {
+// The qft gates are decomposed in single qubit operators that are handed to `apply` together with their target and controls
+pub(crate) fn apply_qft_gate(apply: &mut dyn FnMut([Complex32; 4], u8, Vec<Control>), targets:Vec<u8>, controls:Vec<Control>) {
 
     let no_targets = targets.len();
     let swapped_targets = get_swapped_targets(targets);
@@ -243,11 +298,11 @@ fn apply_qft_gate(statevector: &mut Vec<Complex32>, targets:Vec<u8>, controls:Ve
         for j in i..no_targets {
           pass_targets.push(swapped_targets[j]);
         }
-        apply_qft_pass(statevector, pass_targets, controls.to_vec(), qubit_count);
+        apply_qft_pass(apply, pass_targets, controls.to_vec());
     }
 }
 
-fn apply_qft_dagger_gate(statevector: &mut Vec<Complex32>, targets:Vec<u8>, controls:Vec<Control>, qubit_count:u8) {
+pub(crate) fn apply_qft_dagger_gate(apply: &mut dyn FnMut([Complex32; 4], u8, Vec<Control>), targets:Vec<u8>, controls:Vec<Control>) {
 
   let no_targets = targets.len();
   let swapped_targets = get_swapped_targets(targets);
@@ -257,18 +312,18 @@ fn apply_qft_dagger_gate(statevector: &mut Vec<Complex32>, targets:Vec<u8>, cont
       for j in (i..no_targets).rev() {
         pass_targets.push(swapped_targets[j]);
       }
-      apply_qft_dagger_pass(statevector, pass_targets, controls.to_vec(), qubit_count);
+      apply_qft_dagger_pass(apply, pass_targets, controls.to_vec());
   }
 }
 
-fn apply_qft_pass(statevector: &mut Vec<Complex32>, targets:Vec<u8>, controls:Vec<Control>, qubit_count:u8) {
+fn apply_qft_pass(apply: &mut dyn FnMut([Complex32; 4], u8, Vec<Control>), targets:Vec<u8>, controls:Vec<Control>) {
 
     let no_targets = targets.len();
     let target = targets[0];
 
     let hadamard_gate = get_hadamard_gate();
     let hadamard_gate_operator = gate_mapper::get_single_qubit_operator(&hadamard_gate);
-    apply_operator(hadamard_gate_operator, statevector, target, controls.to_vec(), qubit_count);
+    apply(hadamard_gate_operator, target, controls.to_vec());
 
     for i in 1..no_targets {
       let mut full_controls =  controls.to_vec();
@@ -277,11 +332,11 @@ fn apply_qft_pass(statevector: &mut Vec<Complex32>, targets:Vec<u8>, controls:Ve
 
       let pauli_z_root_gate = get_pauli_z_root_gate(i);
       let pauli_z_root_gate_operator = gate_mapper::get_single_qubit_operator(&pauli_z_root_gate);
-      apply_operator(pauli_z_root_gate_operator, statevector, target, full_controls, qubit_count);
+      apply(pauli_z_root_gate_operator, target, full_controls);
     }
 }
 
-fn apply_qft_dagger_pass(statevector: &mut Vec<Complex32>, targets:Vec<u8>, controls:Vec<Control>, qubit_count:u8) {
+fn apply_qft_dagger_pass(apply: &mut dyn FnMut([Complex32; 4], u8, Vec<Control>), targets:Vec<u8>, controls:Vec<Control>) {
 
   let no_targets = targets.len();
   let target = targets[no_targets - 1];
@@ -293,12 +348,12 @@ fn apply_qft_dagger_pass(statevector: &mut Vec<Complex32>, targets:Vec<u8>, cont
 
     let pauli_z_root_dagger_gate = get_pauli_z_root_dagger_gate(no_targets - i);
     let pauli_z_root_dagger_gate_operator = gate_mapper::get_single_qubit_operator(&pauli_z_root_dagger_gate);
-    apply_operator(pauli_z_root_dagger_gate_operator, statevector, target, full_controls, qubit_count);
+    apply(pauli_z_root_dagger_gate_operator, target, full_controls);
   }
 
   let hadamard_gate = get_hadamard_gate();
   let hadamard_gate_operator = gate_mapper::get_single_qubit_operator(&hadamard_gate);
-  apply_operator(hadamard_gate_operator, statevector, target, controls.to_vec(), qubit_count);
+  apply(hadamard_gate_operator, target, controls.to_vec());
 }
 
 fn get_swapped_targets(targets:Vec<u8>) -> Vec<u8> {
@@ -331,7 +386,10 @@ fn get_hadamard_gate() -> Gate {
     lambda: None,
     root: None,
     bit: None,
-    gates: Vec::new()
+    gates: Vec::new(),
+    dagger: false,
+    arguments: HashMap::new(),
+    label: None
   }
 }
 
@@ -346,7 +404,10 @@ fn get_pauli_z_root_gate(pow:usize) -> Gate {
     lambda: None,
     root: Some(format!("1/2^{}", pow)),
     bit: None,
-    gates: Vec::new()
+    gates: Vec::new(),
+    dagger: false,
+    arguments: HashMap::new(),
+    label: None
   }
 }
 
@@ -361,21 +422,14 @@ fn get_pauli_z_root_dagger_gate(pow:usize) -> Gate {
     lambda: None,
     root: Some(format!("1/2^{}", pow)),
     bit: None,
-    gates: Vec::new()
+    gates: Vec::new(),
+    dagger: false,
+    arguments: HashMap::new(),
+    label: None
   }
 }
 
 
-fn get_indexes(i: usize, gate_position: u8, qubit_count:u8) -> (usize, usize){
-    let reversed_gate_position = qubit_count - gate_position - 1;
-    let lowbits = i & MASKS[usize::from(reversed_gate_position)];
-    let remainder = (i >> reversed_gate_position) << (reversed_gate_position+1);
-    let index0 = remainder | lowbits;
-    let index1 = index0 | (1 << reversed_gate_position);
-
-    (index0, index1)
-}
-
 #[cfg(target_pointer_width = "64")]
 const MASKS: [usize; 64] = [
     0, 1, 3, 7, 15, 31, 63, 127, 255, 511, 1023, 2047, 4095, 8191, 16383, 32767, 65535, 131071, 262143,
@@ -0,0 +1,83 @@
+use num_complex::Complex32;
+use serde::Serialize;
+use super::density;
+use super::linalg;
+
+// Schmidt coefficients below this value come from the rounding of the f32 amplitudes
+const COEFFICIENT_TOLERANCE: f64 = 1e-6;
+
+// Largest number of qubits on the smaller side of the cut, as the eigenvalues of its 2^k x 2^k matrix take about 8^k operations
+const MAX_SMALLER_SIDE_QUBITS: usize = 12;
+
+// The entanglement of a pure state across the bipartition of its qubits into the given qubits and the others.
+// The entropies are in bits, so a Bell pair has an entropy of 1.
+#[derive(Debug, Clone, Serialize)]
+pub struct Entanglement {
+    pub schmidt_coefficients: Vec<f64>,
+    pub von_neumann_entropy: f64,
+    pub renyi_order: f64,
+    pub renyi_entropy: f64,
+}
+
+pub fn get_entanglement(statevector:&[Complex32], qubit_count:u8, qubits:&[u8], renyi_order:f64) -> Entanglement {
+    let schmidt_coefficients = get_schmidt_coefficients(statevector, qubit_count, qubits);
+
+    Entanglement {
+        von_neumann_entropy: get_von_neumann_entropy(&schmidt_coefficients),
+        renyi_entropy: get_renyi_entropy(&schmidt_coefficients, renyi_order),
+        renyi_order,
+        schmidt_coefficients,
+    }
+}
+
+// The nonzero Schmidt coefficients in descending order, the square roots of the eigenvalues of the reduced density matrix.
+// Both sides of the bipartition have the same nonzero eigenvalues, so the matrix is built for the side with fewer qubits.
+pub fn get_schmidt_coefficients(statevector:&[Complex32], qubit_count:u8, qubits:&[u8]) -> Vec<f64> {
+    density::check_qubits(qubits, qubit_count);
+
+    let others:Vec<u8> = (0..qubit_count).filter(|qubit| !qubits.contains(qubit)).collect();
+    let kept = if qubits.len() <= others.len() { qubits } else { &others };
+    if kept.len() > MAX_SMALLER_SIDE_QUBITS {
+        panic!("The smaller side of the cut can have at most {} qubits. Received {} and {} qubits", MAX_SMALLER_SIDE_QUBITS, qubits.len(), others.len());
+    }
+    let matrix = density::get_reduced_density_matrix_f64(statevector, qubit_count, kept);
+
+    linalg::hermitian_eigenvalues(&matrix, 1 << kept.len()).into_iter()
+        .filter(|eigenvalue| *eigenvalue > COEFFICIENT_TOLERANCE*COEFFICIENT_TOLERANCE)
+        .map(f64::sqrt)
+        .collect()
+}
+
+pub fn get_von_neumann_entropy(schmidt_coefficients:&[f64]) -> f64 {
+    let entropy:f64 = get_weights(schmidt_coefficients).iter().map(|weight| -weight*weight.log2()).sum();
+    nonnegative(entropy)
+}
+
+// Order 1 is the von Neumann entropy, order 0 the logarithm of the Schmidt rank and an infinite order the min-entropy
+pub fn get_renyi_entropy(schmidt_coefficients:&[f64], order:f64) -> f64 {
+    if order.is_nan() || order < 0.0 {
+        panic!("The order of the Rényi entropy must be a nonnegative number. Received {}", order);
+    }
+    if order == 1.0 {
+        return get_von_neumann_entropy(schmidt_coefficients);
+    }
+
+    let weights = get_weights(schmidt_coefficients);
+    let entropy = if order.is_infinite() {
+        -weights.iter().cloned().fold(0.0, f64::max).log2()
+    } else {
+        weights.iter().map(|weight| weight.powf(order)).sum::<f64>().log2()/(1.0 - order)
+    };
+    nonnegative(entropy)
+}
+
+// The squared coefficients, normalized so the rounding of the statevector does not show as entropy
+fn get_weights(schmidt_coefficients:&[f64]) -> Vec<f64> {
+    let total:f64 = schmidt_coefficients.iter().map(|coefficient| coefficient*coefficient).sum();
+    schmidt_coefficients.iter().map(|coefficient| coefficient*coefficient/total).collect()
+}
+
+// Rounding can give tiny negative entropies, and -0.0 for product states
+fn nonnegative(entropy:f64) -> f64 {
+    if entropy > 0.0 { entropy } else { 0.0 }
+}
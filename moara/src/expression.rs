@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use num_complex::Complex64;
+
+// Arithmetic expression used for gate parameters in the imported circuit formats
+#[derive(Clone, Debug)]
+pub enum Expression {
+    Number(f64),
+    Imaginary(f64),
+    Variable(String),
+    Negate(Box<Expression>),
+    Binary(char, Box<Expression>, Box<Expression>),
+    Function(String, Box<Expression>),
+}
+
+impl Expression {
+    pub fn evaluate(&self, variables:&HashMap<String, f64>) -> f64 {
+        let value = self.evaluate_complex(variables);
+        if value.im.abs() > 1e-10 {
+            panic!("Expected a real value. The expression evaluates to {}", value);
+        }
+        value.re
+    }
+
+    // `i` is the imaginary unit unless a parameter has that name
+    pub fn evaluate_complex(&self, variables:&HashMap<String, f64>) -> Complex64 {
+        match self {
+            Expression::Number(value) => Complex64::new(*value, 0.0),
+            Expression::Imaginary(value) => Complex64::new(0.0, *value),
+            Expression::Variable(name) => match variables.get(name) {
+                Some(value) => Complex64::new(*value, 0.0),
+                None if name == "i" => Complex64::new(0.0, 1.0),
+                None => panic!("Unknown parameter {}", name)
+            },
+            Expression::Negate(expression) => -expression.evaluate_complex(variables),
+            Expression::Binary(operator, left, right) => {
+                let left = left.evaluate_complex(variables);
+                let right = right.evaluate_complex(variables);
+                match operator {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    '/' => left / right,
+                    '^' if left.im == 0.0 && right.im == 0.0 && left.re >= 0.0 => Complex64::new(left.re.powf(right.re), 0.0),
+                    '^' => left.powc(right),
+                    unknown_operator => panic!("Unknown operator {}", unknown_operator)
+                }
+            },
+            Expression::Function(name, argument) => {
+                let argument = argument.evaluate_complex(variables);
+                match name.as_ref() {
+                    "sin" => argument.sin(),
+                    "cos" => argument.cos(),
+                    "tan" => argument.tan(),
+                    "exp" => argument.exp(),
+                    "ln" => argument.ln(),
+                    "sqrt" => argument.sqrt(),
+                    "cis" => (Complex64::new(0.0, 1.0)*argument).exp(),
+                    unknown_function => panic!("Unknown function {}", unknown_function)
+                }
+            }
+        }
+    }
+}
+
+// Parses an expression with numbers, imaginary numbers (2.5i or i), pi, named parameters (optionally prefixed with %),
+// + - * / ^ (or **), parentheses and the functions sin, cos, tan, exp, ln, sqrt and cis
+pub fn parse(text:&str) -> Expression {
+    let mut parser = Parser { chars: text.chars().collect(), position: 0 };
+    let expression = parser.parse_sum();
+
+    parser.skip_whitespace();
+    if parser.position < parser.chars.len() {
+        panic!("Unexpected character '{}' in expression {}", parser.chars[parser.position], text);
+    }
+
+    expression
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn parse_sum(&mut self) -> Expression {
+        let mut expression = self.parse_product();
+        while let Some(operator) = self.next_operator(&['+', '-']) {
+            expression = Expression::Binary(operator, Box::new(expression), Box::new(self.parse_product()));
+        }
+        expression
+    }
+
+    fn parse_product(&mut self) -> Expression {
+        let mut expression = self.parse_unary();
+        while let Some(operator) = self.next_operator(&['*', '/']) {
+            expression = Expression::Binary(operator, Box::new(expression), Box::new(self.parse_unary()));
+        }
+        expression
+    }
+
+    fn parse_unary(&mut self) -> Expression {
+        match self.next_operator(&['-', '+']) {
+            Some('-') => Expression::Negate(Box::new(self.parse_unary())),
+            Some(_) => self.parse_unary(),
+            None => self.parse_power()
+        }
+    }
+
+    fn parse_power(&mut self) -> Expression {
+        let base = self.parse_atom();
+        if self.next_operator(&['^']).is_some() {
+            // right associative and binds tighter than a unary minus on its left
+            return Expression::Binary('^', Box::new(base), Box::new(self.parse_unary()));
+        }
+        base
+    }
+
+    fn parse_atom(&mut self) -> Expression {
+        self.skip_whitespace();
+
+        let c = match self.chars.get(self.position) {
+            Some(c) => *c,
+            None => panic!("Unexpected end of expression")
+        };
+
+        if c == '(' {
+            self.position += 1;
+            let expression = self.parse_sum();
+            self.expect(')');
+            return expression;
+        }
+
+        if c.is_ascii_digit() || c == '.' {
+            let value = self.read_number();
+            let is_imaginary = self.chars.get(self.position) == Some(&'i')
+                && !self.chars.get(self.position + 1).is_some_and(|c| c.is_alphanumeric() || *c == '_');
+            if is_imaginary {
+                self.position += 1;
+                return Expression::Imaginary(value);
+            }
+            return Expression::Number(value);
+        }
+
+        if c.is_alphabetic() || c == '_' || c == '%' {
+            let name = self.read_identifier();
+            if name == "pi" {
+                return Expression::Number(PI);
+            }
+
+            self.skip_whitespace();
+            if self.chars.get(self.position) == Some(&'(') {
+                self.position += 1;
+                let argument = self.parse_sum();
+                self.expect(')');
+                return Expression::Function(name, Box::new(argument));
+            }
+
+            return Expression::Variable(name.trim_start_matches('%').to_string());
+        }
+
+        panic!("Unexpected character '{}' in expression", c);
+    }
+
+    fn next_operator(&mut self, operators:&[char]) -> Option<char> {
+        self.skip_whitespace();
+
+        let c = *self.chars.get(self.position)?;
+        if c == '*' && self.chars.get(self.position + 1) == Some(&'*') {
+            if operators.contains(&'^') {
+                self.position += 2;
+                return Some('^');
+            }
+            return None;
+        }
+
+        if operators.contains(&c) {
+            self.position += 1;
+            return Some(c);
+        }
+        None
+    }
+
+    fn read_number(&mut self) -> f64 {
+        let start = self.position;
+        while self.position < self.chars.len() {
+            let c = self.chars[self.position];
+            let exponent_sign = (c == '-' || c == '+') && self.position > start && matches!(self.chars[self.position - 1], 'e' | 'E');
+            if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+
+        let text:String = self.chars[start..self.position].iter().collect();
+        text.parse::<f64>().unwrap_or_else(|_| panic!("Invalid number {}", text))
+    }
+
+    fn read_identifier(&mut self) -> String {
+        let start = self.position;
+        self.position += 1;
+        while self.position < self.chars.len() && (self.chars[self.position].is_alphanumeric() || self.chars[self.position] == '_') {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn expect(&mut self, expected:char) {
+        self.skip_whitespace();
+        if self.chars.get(self.position) != Some(&expected) {
+            panic!("Expected '{}' in expression", expected);
+        }
+        self.position += 1;
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.position < self.chars.len() && self.chars[self.position].is_whitespace() {
+            self.position += 1;
+        }
+    }
+}
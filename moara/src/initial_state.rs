@@ -0,0 +1,107 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+use num_complex::Complex32;
+use serde_json::Value;
+use super::npy;
+
+const PRODUCT_STATES: [&str; 6] = ["0", "1", "+", "-", "+i", "-i"];
+
+// The state the simulation starts from instead of |0…0⟩. Bitstrings, product states and statevectors are written in the order
+// of the statevector index, so with big endian qubit 0 comes first.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InitialState {
+    Bitstring(String),
+    Product(Vec<String>),
+    Statevector(Vec<Complex32>),
+}
+
+impl InitialState {
+    // A bitstring such as 0110, or one of 0, 1, +, -, +i, -i per qubit separated by commas, such as +,0,-i
+    pub fn parse(text:&str) -> InitialState {
+        let text = text.trim();
+        if text.contains(',') || PRODUCT_STATES[2..].contains(&text) {
+            return InitialState::Product(text.split(',').map(|state| state.trim().to_string()).collect());
+        }
+        if !text.is_empty() && text.chars().all(|c| c == '0' || c == '1') {
+            return InitialState::Bitstring(text.to_string());
+        }
+        panic!("Unknown initial state {}. Use a bitstring such as 0110, or one of 0, 1, +, -, +i, -i per qubit separated by commas", text);
+    }
+
+    // A statevector saved as a .npy array, or as the JSON written by the statevector command: an object with the amplitudes or a list of [re, im] pairs
+    pub fn load(contents:&[u8]) -> InitialState {
+        if contents.starts_with(b"\x93NUMPY") {
+            return InitialState::Statevector(npy::read_complex(contents));
+        }
+
+        let value:Value = serde_json::from_slice(contents).unwrap_or_else(|err| panic!("The initial statevector is neither a npy array nor JSON: {}", err));
+        let amplitudes = match value.get("amplitudes") {
+            Some(amplitudes) => amplitudes,
+            None => &value
+        };
+        let amplitudes = match amplitudes.as_array() {
+            Some(amplitudes) => amplitudes,
+            None => panic!("The initial statevector must be a list of [re, im] pairs")
+        };
+
+        InitialState::Statevector(amplitudes.iter().map(|amplitude| match amplitude.as_array().map(|pair| pair.as_slice()) {
+            Some([re, im]) if re.is_number() && im.is_number() => Complex32::new(re.as_f64().unwrap() as f32, im.as_f64().unwrap() as f32),
+            _ => panic!("Invalid amplitude {} in the initial statevector. Use [re, im] pairs", amplitude)
+        }).collect())
+    }
+
+    // Text given through the bindings: a statevector as a JSON list of [re, im] pairs or an object with the amplitudes,
+    // otherwise a bitstring or a product state as accepted by parse
+    pub fn from_text(text:&str) -> InitialState {
+        if text.trim_start().starts_with('[') || text.trim_start().starts_with('{') {
+            InitialState::load(text.as_bytes())
+        } else {
+            InitialState::parse(text)
+        }
+    }
+
+    pub fn get_statevector(&self, qubit_count:u8) -> Vec<Complex32> {
+        match self {
+            InitialState::Bitstring(bitstring) => {
+                if bitstring.len() != qubit_count as usize {
+                    panic!("The initial state {} must have one bit for each of the {} qubits", bitstring, qubit_count);
+                }
+                let mut statevector = vec![C!(0); 1<<qubit_count];
+                statevector[usize::from_str_radix(bitstring, 2).unwrap()] = C!(1);
+                statevector
+            },
+            InitialState::Product(states) => {
+                if states.len() != qubit_count as usize {
+                    panic!("The initial state {} must have one state for each of the {} qubits", states.join(","), qubit_count);
+                }
+                let mut statevector = vec![C!(1)];
+                for state in states {
+                    let [amplitude0, amplitude1] = get_qubit_state(state);
+                    statevector = statevector.iter().flat_map(|amplitude| vec![amplitude*amplitude0, amplitude*amplitude1]).collect();
+                }
+                statevector
+            },
+            InitialState::Statevector(statevector) => {
+                if statevector.len() != 1<<qubit_count {
+                    panic!("The initial statevector has {} amplitudes. Expected {} for {} qubits", statevector.len(), 1usize<<qubit_count, qubit_count);
+                }
+                let norm:f32 = statevector.iter().map(|amplitude| amplitude.norm_sqr()).sum();
+                if (norm - 1.0).abs() > 1e-4 {
+                    panic!("The initial statevector is not normalized. The sum of the squared amplitudes is {}", norm);
+                }
+                statevector.to_vec()
+            }
+        }
+    }
+}
+
+fn get_qubit_state(state:&str) -> [Complex32; 2] {
+    match state {
+        "0" => [C!(1), C!(0)],
+        "1" => [C!(0), C!(1)],
+        "+" => [C!(FRAC_1_SQRT_2), C!(FRAC_1_SQRT_2)],
+        "-" => [C!(FRAC_1_SQRT_2), C!(-FRAC_1_SQRT_2)],
+        "+i" => [C!(FRAC_1_SQRT_2), C!(FRAC_1_SQRT_2*i)],
+        "-i" => [C!(FRAC_1_SQRT_2), C!(-FRAC_1_SQRT_2*i)],
+        unknown_state => panic!("Unknown qubit state {} in the initial state. Use one of {}", unknown_state, PRODUCT_STATES.join(", "))
+    }
+}
@@ -6,21 +6,181 @@ pub mod circuit;
 pub mod simulator;
 pub mod engine;
 pub mod measurement;
+pub mod stabilizer;
+pub mod linalg;
+pub mod mps;
+pub mod sparse;
+pub mod analysis;
+pub mod expression;
+pub mod qasm;
+pub mod decomposition;
+pub mod definitions;
+pub mod qasm_export;
+pub mod quil;
+pub mod cirq;
+pub mod qobj;
+pub mod quirk;
+pub mod validation;
+pub mod npy;
+pub mod initial_state;
+pub mod checkpoint;
+pub mod density;
+pub mod entanglement;
+pub mod stepper;
+pub mod repl;
+pub mod diagram;
 
 
+use std::collections::HashMap;
 use num_complex::Complex32;
+use analysis::CircuitAnalysis;
+use initial_state::InitialState;
+use entanglement::Entanglement;
+use stepper::Snapshot;
 
-pub fn simulate(serialized_circuit:String, shots:u32, endianess:Option<String>, qubit_count:Option<u8>) -> Vec<u32>
+pub fn simulate(serialized_circuit:String, shots:u32, endianess:Option<String>, qubit_count:Option<u8>, seed:Option<u64>, initial_state:Option<InitialState>) -> Vec<u32>
 {
-    simulator::simulate(serialized_circuit, shots, endianess, qubit_count)
+    simulator::simulate(serialized_circuit, shots, endianess, qubit_count, seed, initial_state)
 }
 
-pub fn get_statevector(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>) -> Vec<Complex32>
+pub fn simulate_stabilizer(serialized_circuit:String, shots:u32, endianess:Option<String>, qubit_count:Option<u8>, seed:Option<u64>) -> HashMap<String, u32>
 {
-    simulator::get_statevector(serialized_circuit, endianess, qubit_count)
+    simulator::simulate_stabilizer(serialized_circuit, shots, endianess, qubit_count, seed)
 }
 
-pub fn get_probabilities(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>) -> Vec<f32>
+pub fn simulate_mps(serialized_circuit:String, shots:u32, endianess:Option<String>, qubit_count:Option<u8>, max_bond_dimension:Option<usize>, cutoff:Option<f64>, seed:Option<u64>) -> (HashMap<String, u32>, f64)
+{
+    simulator::simulate_mps(serialized_circuit, shots, endianess, qubit_count, max_bond_dimension, cutoff, seed)
+}
+
+pub fn simulate_sparse(serialized_circuit:String, shots:u32, endianess:Option<String>, qubit_count:Option<u8>, seed:Option<u64>) -> HashMap<String, u32>
+{
+    simulator::simulate_sparse(serialized_circuit, shots, endianess, qubit_count, seed)
+}
+
+pub fn get_statevector(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<Complex32>
+{
+    simulator::get_statevector(serialized_circuit, endianess, qubit_count, initial_state)
+}
+
+pub fn get_probabilities(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<f32>
 {   
-    simulator::get_probabilities(serialized_circuit, endianess, qubit_count)
-}
\ No newline at end of file
+    simulator::get_probabilities(serialized_circuit, endianess, qubit_count, initial_state)
+}
+
+pub fn get_amplitudes(serialized_circuit:String, bitstrings:Vec<String>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<Complex32>
+{
+    simulator::get_amplitudes(serialized_circuit, bitstrings, endianess, qubit_count, initial_state)
+}
+
+pub fn get_bitstring_probabilities(serialized_circuit:String, bitstrings:Vec<String>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<f32>
+{
+    simulator::get_bitstring_probabilities(serialized_circuit, bitstrings, endianess, qubit_count, initial_state)
+}
+
+pub fn get_marginal_probabilities(serialized_circuit:String, qubits:Vec<u8>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<f32>
+{
+    simulator::get_marginal_probabilities(serialized_circuit, qubits, endianess, qubit_count, initial_state)
+}
+
+pub fn get_reduced_density_matrix(serialized_circuit:String, qubits:Vec<u8>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<Complex32>
+{
+    simulator::get_reduced_density_matrix(serialized_circuit, qubits, endianess, qubit_count, initial_state)
+}
+
+pub fn get_entanglement(serialized_circuit:String, qubits:Vec<u8>, renyi_order:f64, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Entanglement
+{
+    simulator::get_entanglement(serialized_circuit, qubits, renyi_order, qubit_count, initial_state)
+}
+
+pub fn get_entanglement_profile(serialized_circuit:String, qubits:Vec<u8>, renyi_order:f64, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<(u16, Entanglement)>
+{
+    simulator::get_entanglement_profile(serialized_circuit, qubits, renyi_order, qubit_count, initial_state)
+}
+
+pub fn get_step_statevectors(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<(u16, Vec<Complex32>)>
+{
+    simulator::get_step_statevectors(serialized_circuit, endianess, qubit_count, initial_state)
+}
+
+pub fn get_step_probabilities(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<(u16, Vec<f32>)>
+{
+    simulator::get_step_probabilities(serialized_circuit, endianess, qubit_count, initial_state)
+}
+
+pub fn get_statevector_with_snapshots(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> (Vec<Complex32>, Vec<Snapshot>)
+{
+    simulator::get_statevector_with_snapshots(serialized_circuit, endianess, qubit_count, initial_state)
+}
+
+pub fn get_probabilities_with_snapshots(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> (Vec<f32>, Vec<Snapshot>)
+{
+    simulator::get_probabilities_with_snapshots(serialized_circuit, endianess, qubit_count, initial_state)
+}
+
+pub fn get_sparse_probabilities(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>) -> HashMap<String, f32>
+{
+    simulator::get_sparse_probabilities(serialized_circuit, endianess, qubit_count)
+}
+
+pub fn get_qubit_count(serialized_circuit:String, qubit_count:Option<u8>) -> u8
+{
+    simulator::get_qubit_count(serialized_circuit, qubit_count)
+}
+
+pub fn analyze(serialized_circuit:String, qubit_count:Option<u8>, shots:u32) -> CircuitAnalysis
+{
+    simulator::analyze(serialized_circuit, qubit_count, shots)
+}
+
+pub fn validate(serialized_circuit:String) -> Vec<String>
+{
+    validation::validate(&serialized_circuit)
+}
+
+pub fn convert_qasm(qasm_source:String) -> (String, u8)
+{
+    let (circuit, qubit_count) = qasm::parse(&qasm_source);
+    (serde_json::to_string(&circuit).unwrap(), qubit_count)
+}
+
+pub fn convert_quil(quil_source:String) -> (String, u8)
+{
+    let (circuit, qubit_count) = quil::parse(&quil_source);
+    (serde_json::to_string(&circuit).unwrap(), qubit_count)
+}
+
+pub fn convert_cirq(cirq_json:String) -> (String, u8)
+{
+    let (circuit, qubit_count) = cirq::parse(&cirq_json);
+    (serde_json::to_string(&circuit).unwrap(), qubit_count)
+}
+
+pub fn convert_quirk(quirk_source:String) -> (String, u8)
+{
+    let (circuit, qubit_count) = quirk::parse(&quirk_source);
+    (serde_json::to_string(&circuit).unwrap(), qubit_count)
+}
+
+pub fn run_qobj(qobj_json:String) -> String
+{
+    qobj::run(&qobj_json).to_string()
+}
+
+pub fn export_qasm(serialized_circuit:String, qubit_count:Option<u8>, version:String) -> String
+{
+    simulator::export_qasm(serialized_circuit, qubit_count, version)
+}
+
+pub fn draw(serialized_circuit:String, qubit_count:Option<u8>, format:String, width:Option<usize>) -> String
+{
+    simulator::draw(serialized_circuit, qubit_count, format, width)
+}
+
+// The message of a panic caught with catch_unwind, for the callers that report errors instead of stopping
+pub(crate) fn get_panic_message(error:&(dyn std::any::Any + Send)) -> String {
+    match error.downcast_ref::<String>() {
+        Some(message) => message.clone(),
+        None => error.downcast_ref::<&str>().map_or_else(|| String::from("Unknown error"), |message| message.to_string())
+    }
+}
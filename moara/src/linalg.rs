@@ -0,0 +1,242 @@
+use num_complex::Complex64;
+
+const MAX_SWEEPS: usize = 60;
+const EPSILON: f64 = 1e-15;
+const MAX_QL_ITERATIONS: usize = 30;
+
+// Singular value decomposition of a row-major rows x columns matrix: a = u * diag(s) * vh.
+// u is rows x k, vh is k x columns, with k = min(rows, columns) and s sorted descending.
+pub fn svd(a:&[Complex64], rows:usize, columns:usize) -> (Vec<Complex64>, Vec<f64>, Vec<Complex64>) {
+    if columns > rows {
+        // decompose the adjoint so that the Jacobi sweeps run over the shorter dimension
+        let (u, s, vh) = svd(&adjoint(a, rows, columns), columns, rows);
+        let k = s.len();
+        return (adjoint(&vh, k, rows), s, adjoint(&u, columns, k));
+    }
+
+    // one-sided Jacobi: orthogonalize the columns of w = a * v
+    let mut w = a.to_vec();
+    let mut v = identity(columns);
+
+    for _ in 0..MAX_SWEEPS {
+        let mut rotated = false;
+
+        for p in 0..columns {
+            for q in p+1..columns {
+                let mut alpha = 0.0;
+                let mut beta = 0.0;
+                let mut gamma = Complex64::new(0.0, 0.0);
+                for i in 0..rows {
+                    let wp = w[i*columns + p];
+                    let wq = w[i*columns + q];
+                    alpha += wp.norm_sqr();
+                    beta += wq.norm_sqr();
+                    gamma += wp.conj()*wq;
+                }
+
+                let gamma_norm = gamma.norm();
+                if gamma_norm <= EPSILON * (alpha*beta).sqrt() || gamma_norm == 0.0 {
+                    continue;
+                }
+                rotated = true;
+
+                let phase = gamma / gamma_norm;
+                let zeta = (beta - alpha) / (2.0*gamma_norm);
+                let t = zeta.signum() / (zeta.abs() + (1.0 + zeta*zeta).sqrt());
+                let c = 1.0 / (1.0 + t*t).sqrt();
+                let s = c*t;
+
+                for i in 0..rows {
+                    let wp = w[i*columns + p];
+                    let wq = w[i*columns + q] * phase.conj();
+                    w[i*columns + p] = wp*c - wq*s;
+                    w[i*columns + q] = wp*s + wq*c;
+                }
+                for i in 0..columns {
+                    let vp = v[i*columns + p];
+                    let vq = v[i*columns + q] * phase.conj();
+                    v[i*columns + p] = vp*c - vq*s;
+                    v[i*columns + q] = vp*s + vq*c;
+                }
+            }
+        }
+
+        if !rotated {
+            break;
+        }
+    }
+
+    let mut singular_values:Vec<(usize, f64)> = (0..columns)
+        .map(|j| (j, (0..rows).map(|i| w[i*columns + j].norm_sqr()).sum::<f64>().sqrt()))
+        .collect();
+    singular_values.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+    let k = columns;
+    let mut u = vec![Complex64::new(0.0, 0.0); rows*k];
+    let mut s = Vec::with_capacity(k);
+    let mut vh = vec![Complex64::new(0.0, 0.0); k*columns];
+
+    for (position, (j, sigma)) in singular_values.iter().enumerate() {
+        s.push(*sigma);
+        if *sigma > 0.0 {
+            for i in 0..rows {
+                u[i*k + position] = w[i*columns + j] / sigma;
+            }
+        }
+        for i in 0..columns {
+            vh[position*columns + i] = v[i*columns + j].conj();
+        }
+    }
+
+    (u, s, vh)
+}
+
+// Eigenvalues of a Hermitian row-major size x size matrix, sorted descending.
+// Householder reflections reduce the matrix to a real symmetric tridiagonal one, whose eigenvalues are found with implicit QL,
+// so the cost is about size^3 operations.
+pub fn hermitian_eigenvalues(a:&[Complex64], size:usize) -> Vec<f64> {
+    let (mut diagonal, mut off_diagonal) = tridiagonalize(a.to_vec(), size);
+    // entries below this bound change the eigenvalues by less than the rounding of the largest ones,
+    // which matters for the zero eigenvalues of low rank matrices
+    let floor = EPSILON * diagonal.iter().zip(&off_diagonal).map(|(d, e)| d.abs() + e.abs()).fold(0.0, f64::max);
+
+    for l in 0..size {
+        let mut iterations = 0;
+        loop {
+            // the block starting at l ends at the first negligible off-diagonal entry
+            let mut m = l;
+            while m + 1 < size && off_diagonal[m].abs() > floor.max(EPSILON * (diagonal[m].abs() + diagonal[m + 1].abs())) {
+                m += 1;
+            }
+            if m == l {
+                break;
+            }
+            iterations += 1;
+            if iterations > MAX_QL_ITERATIONS {
+                panic!("The eigenvalues did not converge after {} iterations", MAX_QL_ITERATIONS);
+            }
+
+            // Wilkinson shift, then chase the bulge from m up to l with Givens rotations
+            let mut g = (diagonal[l + 1] - diagonal[l]) / (2.0*off_diagonal[l]);
+            let mut r = g.hypot(1.0);
+            g = diagonal[m] - diagonal[l] + off_diagonal[l] / (g + r.copysign(g));
+            let mut s = 1.0;
+            let mut c = 1.0;
+            let mut p = 0.0;
+            let mut deflated = false;
+            for i in (l..m).rev() {
+                let f = s*off_diagonal[i];
+                let b = c*off_diagonal[i];
+                r = f.hypot(g);
+                off_diagonal[i + 1] = r;
+                if r == 0.0 {
+                    diagonal[i + 1] -= p;
+                    off_diagonal[m] = 0.0;
+                    deflated = true;
+                    break;
+                }
+                s = f/r;
+                c = g/r;
+                g = diagonal[i + 1] - p;
+                r = (diagonal[i] - g)*s + 2.0*c*b;
+                p = s*r;
+                diagonal[i + 1] = g + p;
+                g = c*r - b;
+            }
+            if deflated {
+                continue;
+            }
+            diagonal[l] -= p;
+            off_diagonal[l] = g;
+            off_diagonal[m] = 0.0;
+        }
+    }
+
+    diagonal.sort_by(|a, b| b.partial_cmp(a).unwrap());
+    diagonal
+}
+
+// The diagonal and the off-diagonal of a real symmetric tridiagonal matrix with the eigenvalues of the Hermitian matrix.
+// Only the lower triangle is read and updated. The reflections leave complex off-diagonal entries,
+// which are replaced by their norms as a diagonal unitary change of basis makes them real.
+fn tridiagonalize(mut a:Vec<Complex64>, size:usize) -> (Vec<f64>, Vec<f64>) {
+    let zero = Complex64::new(0.0, 0.0);
+    let mut off_diagonal = vec![0.0; size];
+    let mut v = vec![zero; size];
+    let mut w = vec![zero; size];
+
+    for k in 0..size.saturating_sub(2) {
+        // reflect the column below the diagonal onto its first entry
+        let offset = k + 1;
+        let alpha = a[offset*size + k];
+        let tail:f64 = (offset + 1..size).map(|i| a[i*size + k].norm_sqr()).sum();
+        if tail == 0.0 {
+            off_diagonal[k] = alpha.norm();
+            continue;
+        }
+        let norm = (alpha.norm_sqr() + tail).sqrt();
+        let phase = if alpha.norm() == 0.0 { Complex64::new(1.0, 0.0) } else { alpha / alpha.norm() };
+        off_diagonal[k] = norm;
+
+        let m = size - offset;
+        v[0] = phase*(alpha.norm() + norm);
+        for i in 1..m {
+            v[i] = a[(offset + i)*size + k];
+        }
+        let tau = 2.0 / ((alpha.norm() + norm).powi(2) + tail);
+
+        // w = tau*a*v - (tau^2/2 * v'*a*v)*v, so that the reflected block is a - v*w' - w*v'
+        for value in w[..m].iter_mut() {
+            *value = zero;
+        }
+        for i in 0..m {
+            let row = (offset + i)*size + offset;
+            let mut sum = zero;
+            for j in 0..i {
+                sum += a[row + j]*v[j];
+                w[j] += a[row + j].conj()*v[i];
+            }
+            w[i] += sum + a[row + i].re*v[i];
+        }
+        let mut product = 0.0;
+        for i in 0..m {
+            w[i] *= tau;
+            product += (v[i].conj()*w[i]).re;
+        }
+        let half = tau*product/2.0;
+        for i in 0..m {
+            w[i] -= v[i]*half;
+        }
+
+        for i in 0..m {
+            let row = (offset + i)*size + offset;
+            let (vi, wi) = (v[i], w[i]);
+            for j in 0..=i {
+                a[row + j] -= vi*w[j].conj() + wi*v[j].conj();
+            }
+        }
+    }
+    if size >= 2 {
+        off_diagonal[size - 2] = a[(size - 1)*size + size - 2].norm();
+    }
+
+    ((0..size).map(|i| a[i*size + i].re).collect(), off_diagonal)
+}
+
+pub fn adjoint(a:&[Complex64], rows:usize, columns:usize) -> Vec<Complex64> {
+    let mut result = vec![Complex64::new(0.0, 0.0); rows*columns];
+    for i in 0..rows {
+        for j in 0..columns {
+            result[j*rows + i] = a[i*columns + j].conj();
+        }
+    }
+    result
+}
+
+fn identity(size:usize) -> Vec<Complex64> {
+    let mut result = vec![Complex64::new(0.0, 0.0); size*size];
+    for i in 0..size {
+        result[i*size + i] = Complex64::new(1.0, 0.0);
+    }
+    result
+}
@@ -1,7 +1,10 @@
 use num_complex::Complex32;
-use std::io::{BufWriter, Write};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufReader, BufWriter, Write};
 use std::fs::File;
 use std::process;
+use std::time::Instant;
 use std::fs;
 use std::error::Error;
 use std::path::PathBuf;
@@ -9,41 +12,480 @@ use std::result::Result;
 use structopt::StructOpt;
 
 use moara;
+use moara::initial_state::InitialState;
+use moara::checkpoint::Checkpoint;
+use moara::checkpoint::Checkpointer;
+use moara::stepper::Snapshot;
+use serde::{Deserialize, Serialize};
 
 fn main() {
     let config = Config::from_args();
 
     match config.command {
-        Command::Sample { circuit_filename, qubit_count, shots, endianess, output } => {
-            let serialized_circuit = read_file(circuit_filename).unwrap_or_else(|err| {
+        Command::Sample { circuit_filename, input_format, qubit_count, shots, endianess, initial_state, method, max_bond_dimension, cutoff, seed, checkpoint, dry_run, format, output } => {
+            let (serialized_circuit, qubit_count) = read_circuit(circuit_filename, input_format, qubit_count);
+
+            if dry_run {
+                println!("{}", moara::analyze(serialized_circuit, qubit_count, shots));
+                return;
+            }
+
+            // a run without a seed gets a random one, so its metadata is enough to reproduce it
+            let seed = Some(seed.unwrap_or_else(rand::random));
+
+            let is_legacy = match get_output_format(format.clone(), &output) {
+                OutputFormat::Npy => {
+                    println!("The npy format is available for probabilities and statevectors. Use 'json' or 'legacy'.");
+                    process::exit(1);
+                },
+                output_format => output_format == OutputFormat::Legacy
+            };
+            let metadata = Metadata::new(&serialized_circuit, qubit_count, &endianess, Some(shots), seed);
+            check_statevector_method(&initial_state, &checkpoint, &method);
+
+            match method.as_ref() {
+                "statevector" => {
+                    let task = Task::new("sample", Some(shots), endianess, seed, format, false, output, &checkpoint);
+                    run_task(task, serialized_circuit, qubit_count, initial_state, checkpoint.path, metadata);
+                },
+                "stabilizer" => {
+                    let results = moara::simulate_stabilizer(serialized_circuit, shots, endianess, qubit_count, seed);
+                    if is_legacy {
+                        output_counts(results, output);
+                    } else {
+                        output_json(metadata.to_json(get_bit_count(&results), "counts", json!(results)), output);
+                    }
+                },
+                "mps" => {
+                    let (results, truncation_error) = moara::simulate_mps(serialized_circuit, shots, endianess, qubit_count, max_bond_dimension, cutoff, seed);
+                    if is_legacy {
+                        output_counts(results, output);
+                        eprintln!("Truncation error: {:e}", truncation_error);
+                    } else {
+                        let mut results_json = metadata.to_json(get_bit_count(&results), "counts", json!(results));
+                        results_json["truncation_error"] = json!(truncation_error);
+                        output_json(results_json, output);
+                    }
+                },
+                "sparse" => {
+                    let results = moara::simulate_sparse(serialized_circuit, shots, endianess, qubit_count, seed);
+                    if is_legacy {
+                        output_counts(results, output);
+                    } else {
+                        output_json(metadata.to_json(get_bit_count(&results), "counts", json!(results)), output);
+                    }
+                },
+                unknown_method => {
+                    println!("Unknown simulation method {}. Use 'statevector', 'stabilizer', 'mps' or 'sparse'.", unknown_method);
+                    process::exit(1);
+                }
+            }
+        },
+        Command::Probabilities { circuit_filename, input_format, qubit_count, endianess, initial_state, marginal, method, checkpoint, format, double_precision, output } => {
+            let (serialized_circuit, qubit_count) = read_circuit(circuit_filename, input_format, qubit_count);
+
+            let metadata = Metadata::new(&serialized_circuit, qubit_count, &endianess, None, None);
+            check_statevector_method(&initial_state, &checkpoint, &method);
+            if marginal.is_some() && method != "statevector" {
+                println!("Marginal probabilities are supported only by the statevector method.");
+                process::exit(1);
+            }
+
+            match method.as_ref() {
+                "statevector" => {
+                    let mut task = Task::new("probabilities", None, endianess, None, format, double_precision, output, &checkpoint);
+                    task.marginal = marginal;
+                    run_task(task, serialized_circuit, qubit_count, initial_state, checkpoint.path, metadata);
+                },
+                "sparse" => {
+                    let output_format = get_output_format(format, &output);
+                    let results = moara::get_sparse_probabilities(serialized_circuit, endianess, qubit_count);
+                    match output_format {
+                        OutputFormat::Legacy => output_probabilities(results, output),
+                        OutputFormat::Npy => {
+                            let dense_results = get_dense_probabilities(&results);
+                            output_npy(|writer| moara::npy::write_float(&dense_results, double_precision, writer), output, true);
+                        },
+                        OutputFormat::Json => output_json_with_control_file(metadata.to_json(get_bit_count(&results), "probabilities", json!(results)), output)
+                    }
+                },
+                unknown_method => {
+                    println!("Unknown simulation method {}. Use 'statevector' or 'sparse'.", unknown_method);
+                    process::exit(1);
+                }
+            }
+        },
+        Command::Statevector { circuit_filename, input_format, qubit_count, endianess, initial_state, checkpoint, format, double_precision, output } => {
+            let (serialized_circuit, qubit_count) = read_circuit(circuit_filename, input_format, qubit_count);
+
+            let metadata = Metadata::new(&serialized_circuit, qubit_count, &endianess, None, None);
+            let task = Task::new("statevector", None, endianess, None, format, double_precision, output, &checkpoint);
+            run_task(task, serialized_circuit, qubit_count, initial_state, checkpoint.path, metadata);
+        },
+        Command::Amplitudes { circuit_filename, input_format, qubit_count, bitstrings, endianess, initial_state, output } => {
+            let (serialized_circuit, qubit_count) = read_circuit(circuit_filename, input_format, qubit_count);
+
+            let metadata = Metadata::new(&serialized_circuit, qubit_count, &endianess, None, None);
+            let amplitudes = moara::get_amplitudes(serialized_circuit, bitstrings.clone(), endianess, qubit_count, read_initial_state(initial_state));
+
+            let mut amplitudes_json = serde_json::Map::new();
+            let mut probabilities_json = serde_json::Map::new();
+            for (bitstring, amplitude) in bitstrings.into_iter().zip(amplitudes) {
+                amplitudes_json.insert(bitstring.clone(), json!([amplitude.re, amplitude.im]));
+                probabilities_json.insert(bitstring, json!(amplitude.norm_sqr()));
+            }
+            let mut results_json = metadata.to_json(metadata.qubit_count as usize, "amplitudes", Value::Object(amplitudes_json));
+            results_json["probabilities"] = Value::Object(probabilities_json);
+            output_json(results_json, output);
+        },
+        Command::Density { circuit_filename, input_format, qubit_count, keep, endianess, initial_state, output } => {
+            let (serialized_circuit, qubit_count) = read_circuit(circuit_filename, input_format, qubit_count);
+
+            let metadata = Metadata::new(&serialized_circuit, qubit_count, &endianess, None, None);
+            let matrix = moara::get_reduced_density_matrix(serialized_circuit, keep.clone(), endianess, qubit_count, read_initial_state(initial_state));
+
+            let size = 1 << keep.len();
+            let rows:Vec<Vec<[f32; 2]>> = matrix.chunks(size).map(|row| row.iter().map(|value| [value.re, value.im]).collect()).collect();
+            let mut results_json = metadata.to_json(keep.len(), "density_matrix", json!(rows));
+            results_json["qubits"] = json!(keep);
+            output_json(results_json, output);
+        },
+        Command::Entanglement { circuit_filename, input_format, qubit_count, partition, renyi_order, profile, initial_state, output } => {
+            let (serialized_circuit, qubit_count) = read_circuit(circuit_filename, input_format, qubit_count);
+
+            let metadata = Metadata::new(&serialized_circuit, qubit_count, &None, None, None);
+            let mut results_json = if profile {
+                let profile = moara::get_entanglement_profile(serialized_circuit, partition.clone(), renyi_order, qubit_count, read_initial_state(initial_state));
+                let steps:Vec<Value> = profile.into_iter().map(|(step_index, entanglement)| {
+                    let mut step = json!(entanglement);
+                    step["step"] = json!(step_index);
+                    step
+                }).collect();
+                metadata.to_json(0, "profile", json!(steps))
+            } else {
+                let entanglement = moara::get_entanglement(serialized_circuit, partition.clone(), renyi_order, qubit_count, read_initial_state(initial_state));
+                metadata.to_json(0, "entanglement", json!(entanglement))
+            };
+            results_json["partition"] = json!(partition);
+            output_json(results_json, output);
+        },
+        Command::Steps { circuit_filename, input_format, qubit_count, endianess, initial_state, probabilities, output } => {
+            let (serialized_circuit, qubit_count) = read_circuit(circuit_filename, input_format, qubit_count);
+
+            let metadata = Metadata::new(&serialized_circuit, qubit_count, &endianess, None, None);
+            let mut stepper = moara::simulator::get_stepper(serialized_circuit, endianess, qubit_count, read_initial_state(initial_state));
+            let mut steps = Vec::new();
+            while let Some(step_index) = stepper.step() {
+                let mut step = json!({ "step": step_index });
+                if probabilities {
+                    step["probabilities"] = get_dense_results(&stepper.get_probabilities(), |probability| *probability > 0.0).0;
+                } else {
+                    step["amplitudes"] = get_amplitudes_json(&stepper.get_statevector());
+                }
+                steps.push(step);
+            }
+
+            let snapshots = stepper.get_snapshots().to_vec();
+            output_json(with_snapshots(metadata.to_json(metadata.qubit_count as usize, "steps", json!(steps)), &snapshots, !probabilities), output);
+        },
+        Command::Repl { qubit_count } => {
+            if qubit_count > moara::repl::MAX_QUBIT_COUNT {
+                println!("The session is limited to {} qubits. Received {} qubits", moara::repl::MAX_QUBIT_COUNT, qubit_count);
+                process::exit(1);
+            }
+            let mut session = moara::repl::Session::new(qubit_count);
+            println!("moara repl on {} qubits. Type help for the gates and the commands.", session.get_qubit_count());
+            let mut line = String::new();
+            loop {
+                print!("moara> ");
+                std::io::stdout().flush().unwrap();
+                line.clear();
+                if std::io::stdin().read_line(&mut line).unwrap() == 0 || matches!(line.trim(), "quit" | "exit") {
+                    break;
+                }
+                // the errors are reported by the session, so the panic messages are silenced while it runs
+                let hook = std::panic::take_hook();
+                std::panic::set_hook(Box::new(|_| {}));
+                let result = session.execute(&line);
+                std::panic::set_hook(hook);
+
+                match result {
+                    Ok(output) if output.is_empty() => {},
+                    Ok(output) => println!("{}", output),
+                    Err(message) => println!("Error: {}", message)
+                }
+            }
+        },
+        Command::Resume { checkpoint_filename } => {
+            let file = File::open(&checkpoint_filename).unwrap_or_else(|err| {
                 println!("{}", err);
                 process::exit(1);
             });
+            let checkpoint = Checkpoint::read(&mut BufReader::new(file));
+            let task:Task = serde_json::from_str(&checkpoint.metadata).unwrap_or_else(|_| {
+                println!("The checkpoint does not hold the settings of a moara run.");
+                process::exit(1);
+            });
+
+            let metadata = Metadata {
+                qubit_count: checkpoint.qubit_count,
+                endianess: task.endianess.clone().unwrap_or_else(|| String::from("bigendian")),
+                shots: task.shots,
+                seed: task.seed,
+                start: Instant::now(),
+            };
+            let mut checkpointer = Checkpointer::new(checkpoint_filename, task.checkpoint_steps, task.checkpoint_minutes, checkpoint.metadata.clone());
+            let (statevector, measurements, count, snapshots) = moara::simulator::resume(checkpoint, &task.endianess, Some(&mut checkpointer));
+            finish_task(task, statevector, measurements, count, snapshots, metadata);
+        },
+        Command::Analyze { circuit_filename, input_format, qubit_count, shots } => {
+            let (serialized_circuit, qubit_count) = read_circuit(circuit_filename, input_format, qubit_count);
+
+            println!("{}", moara::analyze(serialized_circuit, qubit_count, shots));
+        },
+        Command::Convert { circuit_filename, input_format, qubit_count, to, output } => {
+            let (serialized_circuit, qubit_count) = read_circuit(circuit_filename, input_format, qubit_count);
+
+            if to != "qasm2" && to != "qasm3" {
+                println!("Unknown output format {}. Use 'qasm2' or 'qasm3'.", to);
+                process::exit(1);
+            }
+
+            let program = moara::export_qasm(serialized_circuit, qubit_count, to);
+            match output {
+                Some(filename) => fs::write(filename, program).unwrap_or_else(|err| {
+                    println!("{}", err);
+                    process::exit(1);
+                }),
+                None => print!("{}", program)
+            }
+        },
+        Command::Draw { circuit_filename, input_format, qubit_count, format, width, output } => {
+            let (serialized_circuit, qubit_count) = read_circuit(circuit_filename, input_format, qubit_count);
 
-            let results = moara::simulate(serialized_circuit, shots, endianess, qubit_count);
-            output_u32(results, output);
+            if format != "text" && format != "svg" && format != "latex" {
+                println!("Unknown output format {}. Use 'text', 'svg' or 'latex'.", format);
+                process::exit(1);
+            }
+
+            // the width of the terminal, then $COLUMNS when the output is not a terminal, then 80 characters
+            let width = width.unwrap_or_else(|| match terminal_size::terminal_size() {
+                Some((terminal_size::Width(columns), _)) => columns as usize,
+                None => std::env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok()).unwrap_or(80)
+            });
+            let diagram = moara::draw(serialized_circuit, qubit_count, format, Some(width).filter(|width| *width > 0));
+            match output {
+                Some(filename) => fs::write(filename, diagram + "\n").unwrap_or_else(|err| {
+                    println!("{}", err);
+                    process::exit(1);
+                }),
+                None => println!("{}", diagram)
+            }
         },
-        Command::Probabilities { circuit_filename, qubit_count, endianess, output } => {
-            let serialized_circuit = read_file(circuit_filename).unwrap_or_else(|err| {
+        Command::Validate { circuit_filename } => {
+            let contents = read_file(circuit_filename).unwrap_or_else(|err| {
                 println!("{}", err);
                 process::exit(1);
             });
 
-            let results = moara::get_probabilities(serialized_circuit, endianess, qubit_count);
-            output_f32(results, output);
+            let problems = moara::validate(contents);
+            if !problems.is_empty() {
+                for problem in &problems {
+                    println!("{}", problem);
+                }
+                process::exit(1);
+            }
+            println!("The circuit is valid");
+        },
+    }
+
+    
+}
+
+// Reads a moara JSON circuit, an OpenQASM 2.0 program, a Quil program, a Cirq JSON circuit or a Quirk circuit. When the format is not given
+// it is detected from the extension, and JSON files written by cirq.to_json are recognized by their cirq_type fields,
+// Quirk circuits by their cols or by a circuit= URL.
+// For the imported formats the program sets the qubit count unless it is given explicitly.
+fn read_circuit(circuit_filename:PathBuf, format:Option<String>, qubit_count:Option<u8>) -> (String, Option<u8>) {
+    let extension = circuit_filename.extension().map(|extension| extension.to_string_lossy().to_string());
+
+    let contents = read_file(circuit_filename).unwrap_or_else(|err| {
+        println!("{}", err);
+        process::exit(1);
+    });
+
+    let format = match format {
+        Some(format) => format,
+        None => match extension.as_deref() {
+            Some("qasm") => String::from("qasm"),
+            Some("quil") => String::from("quil"),
+            _ if contents.contains("\"cirq_type\"") => String::from("cirq"),
+            _ if contents.contains("\"cols\"") || contents.contains("circuit=") => String::from("quirk"),
+            _ => String::from("json")
+        }
+    };
+
+    match format.as_ref() {
+        "json" => (contents, qubit_count),
+        "qasm" => {
+            let (serialized_circuit, declared_qubit_count) = moara::convert_qasm(contents);
+            (serialized_circuit, qubit_count.or(Some(declared_qubit_count)))
+        },
+        "quil" => {
+            let (serialized_circuit, used_qubit_count) = moara::convert_quil(contents);
+            (serialized_circuit, qubit_count.or(Some(used_qubit_count)))
+        },
+        "cirq" => {
+            let (serialized_circuit, used_qubit_count) = moara::convert_cirq(contents);
+            (serialized_circuit, qubit_count.or(Some(used_qubit_count)))
+        },
+        "quirk" => {
+            let (serialized_circuit, used_qubit_count) = moara::convert_quirk(contents);
+            (serialized_circuit, qubit_count.or(Some(used_qubit_count)))
         },
-        Command::Statevector { circuit_filename, qubit_count, endianess, output } => {
-            let serialized_circuit = read_file(circuit_filename).unwrap_or_else(|err| {
+        // -f/--format is the format of the circuit, the results take --output-format
+        "legacy" | "npy" => {
+            println!("{} is an output format. Use --output-format {}. -f/--format sets the format of the circuit.", format, format);
+            process::exit(1);
+        },
+        unknown_format => {
+            println!("Unknown circuit format {}. Use 'json', 'qasm', 'quil', 'cirq' or 'quirk'.", unknown_format);
+            process::exit(1);
+        }
+    }
+}
+
+// The initial state is a file with a statevector when such a file exists, and a bitstring or a product state otherwise
+fn read_initial_state(initial_state:Option<String>) -> Option<InitialState> {
+    initial_state.map(|initial_state| {
+        let path = PathBuf::from(&initial_state);
+        if path.is_file() {
+            let contents = fs::read(path).unwrap_or_else(|err| {
                 println!("{}", err);
                 process::exit(1);
             });
+            InitialState::load(&contents)
+        } else {
+            InitialState::parse(&initial_state)
+        }
+    })
+}
+
+fn check_statevector_method(initial_state:&Option<String>, checkpoint:&CheckpointOptions, method:&str) {
+    if initial_state.is_some() && method != "statevector" {
+        println!("The initial state is supported only by the statevector method.");
+        process::exit(1);
+    }
+    if checkpoint.path.is_some() && method != "statevector" {
+        println!("Checkpoints are supported only by the statevector method.");
+        process::exit(1);
+    }
+}
 
-            let results = moara::get_statevector(serialized_circuit, endianess, qubit_count);
-            output_complex32(results, output);
+// The settings of a statevector run, stored in its checkpoints so `moara resume` finishes the run like the command that started it
+#[derive(Serialize, Deserialize)]
+struct Task {
+    command:String,
+    shots:Option<u32>,
+    endianess:Option<String>,
+    seed:Option<u64>,
+    format:Option<String>,
+    double_precision:bool,
+    output:Option<PathBuf>,
+    checkpoint_steps:Option<u32>,
+    checkpoint_minutes:Option<f64>,
+    #[serde(default)]
+    marginal:Option<Vec<u8>>,
+}
+
+impl Task {
+    #[allow(clippy::too_many_arguments)]
+    fn new(command:&str, shots:Option<u32>, endianess:Option<String>, seed:Option<u64>, format:Option<String>, double_precision:bool, output:Option<PathBuf>, checkpoint:&CheckpointOptions) -> Task {
+        Task {
+            command: command.to_string(),
+            shots,
+            endianess,
+            seed,
+            format,
+            double_precision,
+            output,
+            checkpoint_steps: checkpoint.every_steps,
+            checkpoint_minutes: checkpoint.every_minutes,
+            marginal: None,
+        }
+    }
+}
+
+fn run_task(task:Task, serialized_circuit:String, qubit_count:Option<u8>, initial_state:Option<String>, checkpoint:Option<PathBuf>, metadata:Metadata) {
+    let mut checkpointer = checkpoint.map(|path| Checkpointer::new(path, task.checkpoint_steps, task.checkpoint_minutes, serde_json::to_string(&task).unwrap()));
+    let (statevector, measurements, count, snapshots) = moara::simulator::get_final_state(serialized_circuit, &task.endianess, qubit_count, read_initial_state(initial_state), checkpointer.as_mut());
+
+    finish_task(task, statevector, measurements, count, snapshots, metadata);
+}
+
+// The snapshots of the circuit are added to the JSON output, with their amplitudes for the statevector command and their probabilities otherwise
+fn finish_task(task:Task, statevector:Vec<Complex32>, measurements:HashMap<u8,u8>, count:u8, snapshots:Vec<Snapshot>, metadata:Metadata) {
+    let output_format = get_output_format(task.format, &task.output);
+    let output = task.output;
+    let double_precision = task.double_precision;
+
+    match task.command.as_ref() {
+        "sample" => {
+            let results = moara::simulator::sample_final_state(statevector, measurements, count, task.shots.unwrap_or(0), task.endianess, task.seed);
+            if output_format == OutputFormat::Legacy {
+                output_u32(results, output);
+            } else {
+                let (counts, bit_count) = get_dense_results(&results, |count| *count > 0);
+                output_json(with_snapshots(metadata.to_json(bit_count, "counts", counts), &snapshots, false), output);
+            }
+        },
+        "probabilities" => {
+            let results = match task.marginal {
+                Some(qubits) => moara::simulator::get_final_state_marginal_probabilities(statevector, count, qubits, task.endianess),
+                None => moara::simulator::get_final_state_probabilities(statevector, measurements, count, task.endianess)
+            };
+            match output_format {
+                OutputFormat::Legacy => output_f32(results, output),
+                OutputFormat::Npy => output_npy(|writer| moara::npy::write_float(&results, double_precision, writer), output, true),
+                OutputFormat::Json => {
+                    let (probabilities, bit_count) = get_dense_results(&results, |probability| *probability > 0.0);
+                    output_json_with_control_file(with_snapshots(metadata.to_json(bit_count, "probabilities", probabilities), &snapshots, false), output);
+                }
+            }
         },
+        _ => {
+            let results = moara::simulator::order_final_statevector(statevector, count, task.endianess);
+            match output_format {
+                OutputFormat::Legacy => output_complex32(results, output),
+                OutputFormat::Npy => output_npy(|writer| moara::npy::write_complex(&results, double_precision, writer), output, false),
+                OutputFormat::Json => {
+                    output_json(with_snapshots(metadata.to_json(metadata.qubit_count as usize, "amplitudes", get_amplitudes_json(&results)), &snapshots, true), output);
+                }
+            }
+        }
     }
+}
 
-    
+fn with_snapshots(mut results:Value, snapshots:&[Snapshot], with_amplitudes:bool) -> Value {
+    if snapshots.is_empty() {
+        return results;
+    }
+
+    results["snapshots"] = snapshots.iter().map(|snapshot| {
+        let mut snapshot_json = json!({ "label": snapshot.label, "step": snapshot.step_index });
+        if with_amplitudes {
+            snapshot_json["amplitudes"] = get_amplitudes_json(&snapshot.statevector);
+        } else {
+            snapshot_json["probabilities"] = get_dense_results(&snapshot.probabilities, |probability| *probability > 0.0).0;
+        }
+        snapshot_json
+    }).collect();
+    results
+}
+
+fn get_amplitudes_json(statevector:&[Complex32]) -> Value {
+    let amplitudes:Vec<[f32; 2]> = statevector.iter().map(|amplitude| [amplitude.re, amplitude.im]).collect();
+    json!(amplitudes)
 }
 
 fn read_file(circuit_filename:PathBuf) -> Result<String, Box<dyn Error>> {
@@ -70,6 +512,40 @@ fn output_u32(results:Vec<u32>, output:Option<PathBuf>) {
     }
 }
 
+fn output_counts(results:HashMap<String, u32>, output:Option<PathBuf>) {
+    match output {
+        Some(filename) => {
+            let f = File::create(filename).unwrap_or_else(|err| {
+                println!("{}", err);
+                process::exit(1);
+            });
+            let mut writer = BufWriter::new(f);
+            write_counts(results, &mut writer);
+        },
+        None => {
+            let mut writer = std::io::stdout();
+            write_counts(results, &mut writer);
+        }
+    }
+}
+
+fn output_probabilities(results:HashMap<String, f32>, output:Option<PathBuf>) {
+    match output {
+        Some(filename) => {
+            let f = File::create(filename).unwrap_or_else(|err| {
+                println!("{}", err);
+                process::exit(1);
+            });
+            let mut writer = BufWriter::new(f);
+            write_probabilities(results, &mut writer);
+        },
+        None => {
+            let mut writer = std::io::stdout();
+            write_probabilities(results, &mut writer);
+        }
+    }
+}
+
 fn add_extension(path: &mut std::path::PathBuf, extension: impl AsRef<std::path::Path>) {
   match path.extension() {
       Some(ext) => {
@@ -85,7 +561,7 @@ fn add_extension(path: &mut std::path::PathBuf, extension: impl AsRef<std::path:
 fn output_f32(results:Vec<f32>, output:Option<PathBuf>) {
     match output {
         Some(filename) => {
-            let mut control_file = filename.clone();
+            let control_file = filename.clone();
             // writing simulation results
             let f = File::create(filename).unwrap_or_else(|err| {
                 println!("{}", err);
@@ -93,16 +569,7 @@ fn output_f32(results:Vec<f32>, output:Option<PathBuf>) {
             });
             let mut writer = BufWriter::new(f);
             write_f32(results, &mut writer);
-            // writing control file          
-            add_extension(&mut control_file, "ctrl");
-            let mut file = File::create(control_file).unwrap_or_else(|err| {
-              println!("{}", err);
-              process::exit(1);
-            }); 
-            if let Err(e) = file.write_all(b"done") {
-              println!("Writing error: {}", e.to_string());
-              process::exit(1);
-            }
+            write_control_file(control_file);
         },
         None => {
             let mut writer = std::io::stdout();
@@ -111,6 +578,19 @@ fn output_f32(results:Vec<f32>, output:Option<PathBuf>) {
     }
 }
 
+// The control file tells the readers of the output file that it is complete
+fn write_control_file(mut control_file:PathBuf) {
+    add_extension(&mut control_file, "ctrl");
+    let mut file = File::create(control_file).unwrap_or_else(|err| {
+      println!("{}", err);
+      process::exit(1);
+    });
+    if let Err(e) = file.write_all(b"done") {
+      println!("Writing error: {}", e);
+      process::exit(1);
+    }
+}
+
 fn output_complex32(results:Vec<Complex32>, output:Option<PathBuf>) {
     match output {
         Some(filename) => {
@@ -128,6 +608,130 @@ fn output_complex32(results:Vec<Complex32>, output:Option<PathBuf>) {
     }
 }
 
+fn output_json(results:Value, output:Option<PathBuf>) {
+    let serialized_results = serde_json::to_string(&results).unwrap();
+    match output {
+        Some(filename) => {
+            fs::write(filename, serialized_results).unwrap_or_else(|err| {
+                println!("{}", err);
+                process::exit(1);
+            });
+        },
+        None => println!("{}", serialized_results)
+    }
+}
+
+fn output_json_with_control_file(results:Value, output:Option<PathBuf>) {
+    output_json(results, output.clone());
+    if let Some(filename) = output {
+        write_control_file(filename);
+    }
+}
+
+#[derive(PartialEq)]
+enum OutputFormat {
+    Json,
+    Legacy,
+    Npy,
+}
+
+// Without a format, output files ending with .npy get the npy format and everything else json
+fn get_output_format(format:Option<String>, output:&Option<PathBuf>) -> OutputFormat {
+    let format = format.unwrap_or_else(|| match output {
+        Some(filename) if filename.extension() == Some("npy".as_ref()) => String::from("npy"),
+        _ => String::from("json")
+    });
+
+    match format.as_ref() {
+        "json" => OutputFormat::Json,
+        "legacy" => OutputFormat::Legacy,
+        "npy" => OutputFormat::Npy,
+        unknown_format => {
+            println!("Unknown output format {}. Use 'json', 'legacy' or 'npy'.", unknown_format);
+            process::exit(1);
+        }
+    }
+}
+
+fn output_npy(write:impl FnOnce(&mut dyn Write) -> std::io::Result<()>, output:Option<PathBuf>, with_control_file:bool) {
+    let result = match &output {
+        Some(filename) => {
+            let f = File::create(filename).unwrap_or_else(|err| {
+                println!("{}", err);
+                process::exit(1);
+            });
+            write(&mut BufWriter::new(f))
+        },
+        None => write(&mut std::io::stdout().lock())
+    };
+    if let Err(err) = result {
+        println!("Writing error: {}", err);
+        process::exit(1);
+    }
+
+    if let (Some(filename), true) = (output, with_control_file) {
+        write_control_file(filename);
+    }
+}
+
+// The settings of a run, reported with its results in the JSON output
+struct Metadata {
+    qubit_count:u8,
+    endianess:String,
+    shots:Option<u32>,
+    seed:Option<u64>,
+    start:Instant,
+}
+
+impl Metadata {
+    fn new(serialized_circuit:&str, qubit_count:Option<u8>, endianess:&Option<String>, shots:Option<u32>, seed:Option<u64>) -> Metadata {
+        Metadata {
+            qubit_count: moara::get_qubit_count(serialized_circuit.to_string(), qubit_count),
+            endianess: endianess.clone().unwrap_or_else(|| String::from("bigendian")),
+            shots,
+            seed,
+            start: Instant::now(),
+        }
+    }
+
+    fn to_json(&self, measured_bits:usize, results_name:&str, results:Value) -> Value {
+        let mut metadata = json!({
+            "qubit_count": self.qubit_count,
+            "endianess": self.endianess,
+            "shots": self.shots,
+            "seed": self.seed,
+            "measured_bits": measured_bits,
+            "runtime": self.start.elapsed().as_secs_f64(),
+        });
+        metadata[results_name] = results;
+        metadata
+    }
+}
+
+// Keys the values of a dense list of results by the bitstring of their index, keeping those that pass the filter
+fn get_dense_results<T:Copy + Into<Value>>(results:&[T], filter:impl Fn(&T) -> bool) -> (Value, usize) {
+    let bit_count = results.len().trailing_zeros() as usize;
+    let map = results.iter().enumerate()
+        .filter(|(_, value)| filter(value))
+        .map(|(index, value)| (format!("{:0width$b}", index, width = bit_count), (*value).into()))
+        .collect::<serde_json::Map<String, Value>>();
+
+    (Value::Object(map), bit_count)
+}
+
+// The probabilities of all the bitstrings, ordered by their value
+fn get_dense_probabilities(results:&HashMap<String, f32>) -> Vec<f32> {
+    let mut dense_results = vec![0.0; 1 << get_bit_count(results)];
+    for (bitstring, probability) in results {
+        dense_results[usize::from_str_radix(bitstring, 2).unwrap()] = *probability;
+    }
+    dense_results
+}
+
+fn get_bit_count<T>(results:&HashMap<String, T>) -> usize {
+    results.keys().next().map_or(0, |bitstring| bitstring.len())
+}
+
 fn write_u32(results:Vec<u32>, writer:&mut dyn Write) {
     writer.write("[".as_bytes()).unwrap_or_else(|err| { println!("{}", err); process::exit(1); });
     for r in &results[0..results.len() - 1] {
@@ -191,6 +795,24 @@ fn write_complex32(results:Vec<Complex32>, writer:&mut dyn Write) {
     writer.flush().unwrap_or_else(|err| { println!("{}", err); process::exit(1); });
 }
 
+fn write_counts(results:HashMap<String, u32>, writer:&mut dyn Write) {
+    let mut bitstrings:Vec<&String> = results.keys().collect();
+    bitstrings.sort();
+
+    let entries:Vec<String> = bitstrings.iter().map(|bitstring| format!("\"{}\": {}", bitstring, results[*bitstring])).collect();
+    write!(writer, "{{{}}}", entries.join(", ")).unwrap_or_else(|err| { println!("{}", err); process::exit(1); });
+    writer.flush().unwrap_or_else(|err| { println!("{}", err); process::exit(1); });
+}
+
+fn write_probabilities(results:HashMap<String, f32>, writer:&mut dyn Write) {
+    let mut bitstrings:Vec<&String> = results.keys().collect();
+    bitstrings.sort();
+
+    let entries:Vec<String> = bitstrings.iter().map(|bitstring| format!("\"{}\": {:e}", bitstring, results[*bitstring])).collect();
+    write!(writer, "{{{}}}", entries.join(", ")).unwrap_or_else(|err| { println!("{}", err); process::exit(1); });
+    writer.flush().unwrap_or_else(|err| { println!("{}", err); process::exit(1); });
+}
+
 #[derive(StructOpt)]
 #[structopt(about = "Moara quantum simulator")]
 struct Config {
@@ -205,6 +827,9 @@ enum Command {
     Sample {
         #[structopt(parse(from_os_str))]
         circuit_filename:PathBuf,
+
+        #[structopt(short = "f", long = "format", help = "Circuit format: 'json', 'qasm' (OpenQASM 2.0), 'quil', 'cirq' (cirq.to_json) or 'quirk' (JSON or URL). Detected from the file by default. The format of the results is set with --output-format.")]
+        input_format:Option<String>,
     
         #[structopt(short = "q", long = "qubits", help = "The number of qubits. Must be at least the width of the circuit.")]
         qubit_count:Option<u8>,
@@ -215,6 +840,30 @@ enum Command {
         #[structopt(short = "e", long = "endianess", help = "Ordering for state vectors in returned array with results: 'bigendian' or 'littleendian'.")]
         endianess:Option<String>,
 
+        #[structopt(long = "initial-state", help = "State to start from instead of |0…0⟩, in the order of the endianess: a bitstring such as 0110, one of 0, 1, +, -, +i, -i per qubit separated by commas, or a file with a statevector in the npy or JSON format. Only for the statevector method.")]
+        initial_state:Option<String>,
+
+        #[structopt(short = "m", long = "method", default_value = "statevector", help = "Simulation method: 'statevector', 'stabilizer', 'mps' or 'sparse'. The stabilizer method supports only Clifford circuits. The other methods return the counts keyed by bitstring.")]
+        method:String,
+
+        #[structopt(long = "bond-dimension", help = "Maximum bond dimension for the mps method. Defaults to 64.")]
+        max_bond_dimension:Option<usize>,
+
+        #[structopt(long = "cutoff", help = "Maximum discarded weight of the singular values at each truncation for the mps method. Defaults to 1e-12.")]
+        cutoff:Option<f64>,
+
+        #[structopt(long = "seed", help = "Seed for the random number generator, to get the same samples on every run. A random seed is used and reported in the JSON output otherwise.")]
+        seed:Option<u64>,
+
+        #[structopt(flatten)]
+        checkpoint:CheckpointOptions,
+
+        #[structopt(long = "dry-run", help = "Print the circuit analysis and the estimated cost of the simulation without running it")]
+        dry_run:bool,
+
+        #[structopt(long = "output-format", help = "Output format: 'json', an object with the results and the settings of the run, or 'legacy', the bare list of results. The default is json.")]
+        format:Option<String>,
+
         #[structopt(short = "o", long = "output", help = "Output filename")]
         output:Option<PathBuf>,
     },
@@ -223,6 +872,9 @@ enum Command {
     Probabilities{
         #[structopt(parse(from_os_str))]
         circuit_filename:PathBuf,
+
+        #[structopt(short = "f", long = "format", help = "Circuit format: 'json', 'qasm' (OpenQASM 2.0), 'quil', 'cirq' (cirq.to_json) or 'quirk' (JSON or URL). Detected from the file by default. The format of the results is set with --output-format.")]
+        input_format:Option<String>,
     
         #[structopt(short = "q", long = "qubits", help = "The number of qubits. Must be at least the width of the circuit.")]
         qubit_count:Option<u8>,
@@ -230,6 +882,24 @@ enum Command {
         #[structopt(short = "e", long = "endianess", help = "Ordering for state vectors in returned array with results: 'bigendian' or 'littleendian'.")]
         endianess:Option<String>,
 
+        #[structopt(long = "initial-state", help = "State to start from instead of |0…0⟩, in the order of the endianess: a bitstring such as 0110, one of 0, 1, +, -, +i, -i per qubit separated by commas, or a file with a statevector in the npy or JSON format. Only for the statevector method.")]
+        initial_state:Option<String>,
+
+        #[structopt(long = "marginal", use_delimiter = true, help = "Qubits to keep, separated by commas, such as 0,2. Gives the marginal distribution of these qubits instead of the probabilities of the measured bits. Only for the statevector method.")]
+        marginal:Option<Vec<u8>>,
+
+        #[structopt(short = "m", long = "method", default_value = "statevector", help = "Simulation method: 'statevector' or 'sparse'. The sparse method returns only the nonzero probabilities keyed by bitstring.")]
+        method:String,
+
+        #[structopt(flatten)]
+        checkpoint:CheckpointOptions,
+
+        #[structopt(long = "output-format", help = "Output format: 'json', an object with the results and the settings of the run, 'legacy', the bare list of results, or 'npy', a NumPy array. The default is npy for output files ending with .npy and json otherwise.")]
+        format:Option<String>,
+
+        #[structopt(long = "double-precision", help = "Writes complex128 or float64 arrays in the npy format instead of complex64 or float32")]
+        double_precision:bool,
+
         #[structopt(short = "o", long = "output", help = "Output filename")]
         output:Option<PathBuf>,
     },
@@ -238,6 +908,9 @@ enum Command {
     Statevector {
         #[structopt(parse(from_os_str))]
         circuit_filename:PathBuf,
+
+        #[structopt(short = "f", long = "format", help = "Circuit format: 'json', 'qasm' (OpenQASM 2.0), 'quil', 'cirq' (cirq.to_json) or 'quirk' (JSON or URL). Detected from the file by default. The format of the results is set with --output-format.")]
+        input_format:Option<String>,
     
         #[structopt(short = "q", long = "qubits", help = "The number of qubits. Must be at least the width of the circuit.")]
         qubit_count:Option<u8>,
@@ -245,7 +918,202 @@ enum Command {
         #[structopt(short = "e", long = "endianess", help = "Ordering for state vectors in returned array with results: 'bigendian' or 'littleendian'.")]
         endianess:Option<String>,
 
+        #[structopt(long = "initial-state", help = "State to start from instead of |0…0⟩, in the order of the endianess: a bitstring such as 0110, one of 0, 1, +, -, +i, -i per qubit separated by commas, or a file with a statevector in the npy or JSON format. Only for the statevector method.")]
+        initial_state:Option<String>,
+
+        #[structopt(flatten)]
+        checkpoint:CheckpointOptions,
+
+        #[structopt(long = "output-format", help = "Output format: 'json', an object with the results and the settings of the run, 'legacy', the bare list of results, or 'npy', a NumPy array. The default is npy for output files ending with .npy and json otherwise.")]
+        format:Option<String>,
+
+        #[structopt(long = "double-precision", help = "Writes complex128 or float64 arrays in the npy format instead of complex64 or float32")]
+        double_precision:bool,
+
+        #[structopt(short = "o", long = "output", help = "Output filename")]
+        output:Option<PathBuf>,
+    },
+
+    #[structopt(about = "Get the final amplitudes and probabilities of some bitstrings without printing the whole statevector")]
+    Amplitudes {
+        #[structopt(parse(from_os_str))]
+        circuit_filename:PathBuf,
+
+        #[structopt(short = "f", long = "format", help = "Circuit format: 'json', 'qasm' (OpenQASM 2.0), 'quil', 'cirq' (cirq.to_json) or 'quirk' (JSON or URL). Detected from the file by default. The format of the results is set with --output-format.")]
+        input_format:Option<String>,
+
+        #[structopt(short = "q", long = "qubits", help = "The number of qubits. Must be at least the width of the circuit.")]
+        qubit_count:Option<u8>,
+
+        #[structopt(short = "b", long = "bitstrings", use_delimiter = true, required = true, help = "The bitstrings to query, separated by commas, such as 0101,1100, with one bit per qubit in the order of the endianess")]
+        bitstrings:Vec<String>,
+
+        #[structopt(short = "e", long = "endianess", help = "Order of the qubits in the bitstrings: 'bigendian', qubit 0 first, or 'littleendian', qubit 0 last.")]
+        endianess:Option<String>,
+
+        #[structopt(long = "initial-state", help = "State to start from instead of |0…0⟩, in the order of the endianess: a bitstring such as 0110, one of 0, 1, +, -, +i, -i per qubit separated by commas, or a file with a statevector in the npy or JSON format.")]
+        initial_state:Option<String>,
+
+        #[structopt(short = "o", long = "output", help = "Output filename")]
+        output:Option<PathBuf>,
+    },
+
+    #[structopt(about = "Get the reduced density matrix of some qubits of the final statevector, tracing out the other qubits")]
+    Density {
+        #[structopt(parse(from_os_str))]
+        circuit_filename:PathBuf,
+
+        #[structopt(short = "f", long = "format", help = "Circuit format: 'json', 'qasm' (OpenQASM 2.0), 'quil', 'cirq' (cirq.to_json) or 'quirk' (JSON or URL). Detected from the file by default. The format of the results is set with --output-format.")]
+        input_format:Option<String>,
+
+        #[structopt(short = "q", long = "qubits", help = "The number of qubits. Must be at least the width of the circuit.")]
+        qubit_count:Option<u8>,
+
+        #[structopt(short = "k", long = "keep", use_delimiter = true, required = true, help = "The qubits to keep, separated by commas, such as 0,2. The other qubits are traced out.")]
+        keep:Vec<u8>,
+
+        #[structopt(short = "e", long = "endianess", help = "Ordering of the kept qubits in the matrix indices: 'bigendian', the first kept qubit being the most significant bit, or 'littleendian'.")]
+        endianess:Option<String>,
+
+        #[structopt(long = "initial-state", help = "State to start from instead of |0…0⟩, in the order of the endianess: a bitstring such as 0110, one of 0, 1, +, -, +i, -i per qubit separated by commas, or a file with a statevector in the npy or JSON format.")]
+        initial_state:Option<String>,
+
         #[structopt(short = "o", long = "output", help = "Output filename")]
         output:Option<PathBuf>,
+    },
+
+    #[structopt(about = "Get the Schmidt coefficients and the von Neumann and Rényi entropies of the final statevector across a bipartition of the qubits")]
+    Entanglement {
+        #[structopt(parse(from_os_str))]
+        circuit_filename:PathBuf,
+
+        #[structopt(short = "f", long = "format", help = "Circuit format: 'json', 'qasm' (OpenQASM 2.0), 'quil', 'cirq' (cirq.to_json) or 'quirk' (JSON or URL). Detected from the file by default. The format of the results is set with --output-format.")]
+        input_format:Option<String>,
+
+        #[structopt(short = "q", long = "qubits", help = "The number of qubits. Must be at least the width of the circuit.")]
+        qubit_count:Option<u8>,
+
+        #[structopt(short = "p", long = "partition", use_delimiter = true, required = true, help = "The qubits of one side of the bipartition, separated by commas, such as 0,1. The other qubits form the other side.")]
+        partition:Vec<u8>,
+
+        #[structopt(long = "renyi-order", default_value = "2", help = "Order of the Rényi entropy. 1 gives the von Neumann entropy and inf the min-entropy.")]
+        renyi_order:f64,
+
+        #[structopt(long = "profile", help = "Report the entanglement after every step instead of only for the final statevector")]
+        profile:bool,
+
+        #[structopt(long = "initial-state", help = "State to start from instead of |0…0⟩, with qubit 0 first: a bitstring such as 0110, one of 0, 1, +, -, +i, -i per qubit separated by commas, or a file with a statevector in the npy or JSON format.")]
+        initial_state:Option<String>,
+
+        #[structopt(short = "o", long = "output", help = "Output filename")]
+        output:Option<PathBuf>,
+    },
+
+    #[structopt(about = "Run the circuit step by step and get the statevector or the probabilities after each step")]
+    Steps {
+        #[structopt(parse(from_os_str))]
+        circuit_filename:PathBuf,
+
+        #[structopt(short = "f", long = "format", help = "Circuit format: 'json', 'qasm' (OpenQASM 2.0), 'quil', 'cirq' (cirq.to_json) or 'quirk' (JSON or URL). Detected from the file by default. The format of the results is set with --output-format.")]
+        input_format:Option<String>,
+
+        #[structopt(short = "q", long = "qubits", help = "The number of qubits. Must be at least the width of the circuit.")]
+        qubit_count:Option<u8>,
+
+        #[structopt(short = "e", long = "endianess", help = "Ordering for state vectors in returned array with results: 'bigendian' or 'littleendian'.")]
+        endianess:Option<String>,
+
+        #[structopt(long = "initial-state", help = "State to start from instead of |0…0⟩, in the order of the endianess: a bitstring such as 0110, one of 0, 1, +, -, +i, -i per qubit separated by commas, or a file with a statevector in the npy or JSON format.")]
+        initial_state:Option<String>,
+
+        #[structopt(long = "probabilities", help = "Report the probabilities of the measured bits after each step instead of the statevector")]
+        probabilities:bool,
+
+        #[structopt(short = "o", long = "output", help = "Output filename")]
+        output:Option<PathBuf>,
+    },
+
+    #[structopt(about = "Start an interactive shell that applies gates to a statevector as they are typed")]
+    Repl {
+        #[structopt(short = "q", long = "qubits", default_value = "1", help = "The number of qubits to start with. It grows with the qubits used by the gates.")]
+        qubit_count:u8,
+    },
+
+    #[structopt(about = "Continue a statevector run from its checkpoint and output its results like the command that started it")]
+    Resume {
+        #[structopt(parse(from_os_str))]
+        checkpoint_filename:PathBuf,
+    },
+
+    #[structopt(about = "Analyze the circuit and estimate the cost of simulating it")]
+    Analyze {
+        #[structopt(parse(from_os_str))]
+        circuit_filename:PathBuf,
+
+        #[structopt(short = "f", long = "format", help = "Circuit format: 'json', 'qasm' (OpenQASM 2.0), 'quil', 'cirq' (cirq.to_json) or 'quirk' (JSON or URL). Detected from the file by default. The format of the results is set with --output-format.")]
+        input_format:Option<String>,
+
+        #[structopt(short = "q", long = "qubits", help = "The number of qubits. Must be at least the width of the circuit.")]
+        qubit_count:Option<u8>,
+
+        #[structopt(short = "s", long = "shots", default_value = "1024", help = "The number of shots used for the runtime estimate")]
+        shots:u32,
+    },
+
+    #[structopt(about = "Convert the circuit to OpenQASM")]
+    Convert {
+        #[structopt(parse(from_os_str))]
+        circuit_filename:PathBuf,
+
+        #[structopt(short = "f", long = "format", help = "Circuit format: 'json', 'qasm' (OpenQASM 2.0), 'quil', 'cirq' (cirq.to_json) or 'quirk' (JSON or URL). Detected from the file by default. The format of the results is set with --output-format.")]
+        input_format:Option<String>,
+
+        #[structopt(short = "q", long = "qubits", help = "The number of qubits. Must be at least the width of the circuit.")]
+        qubit_count:Option<u8>,
+
+        #[structopt(short = "t", long = "to", help = "Output format: 'qasm2' or 'qasm3'")]
+        to:String,
+
+        #[structopt(short = "o", long = "output", help = "Output filename")]
+        output:Option<PathBuf>,
+    },
+
+    #[structopt(about = "Draw the circuit as text, SVG or LaTeX, with one wire per qubit and one column per step")]
+    Draw {
+        #[structopt(parse(from_os_str))]
+        circuit_filename:PathBuf,
+
+        #[structopt(short = "f", long = "format", help = "Circuit format: 'json', 'qasm' (OpenQASM 2.0), 'quil', 'cirq' (cirq.to_json) or 'quirk' (JSON or URL). Detected from the file by default. The format of the results is set with --output-format.")]
+        input_format:Option<String>,
+
+        #[structopt(short = "q", long = "qubits", help = "The number of qubits. Must be at least the width of the circuit.")]
+        qubit_count:Option<u8>,
+
+        #[structopt(long = "output-format", default_value = "text", help = "Output format: 'text', 'svg' or 'latex' (a quantikz environment)")]
+        format:String,
+
+        #[structopt(short = "w", long = "width", help = "The width to wrap the text diagram to. The terminal width by default, or 80 characters when it is not known. 0 does not wrap.")]
+        width:Option<usize>,
+
+        #[structopt(short = "o", long = "output", help = "Output filename")]
+        output:Option<PathBuf>,
+    },
+
+    #[structopt(about = "Check a JSON circuit against the schema and the gates known to moara, and list all the problems")]
+    Validate {
+        #[structopt(parse(from_os_str))]
+        circuit_filename:PathBuf,
     }
-}
\ No newline at end of file
+}
+
+#[derive(StructOpt)]
+struct CheckpointOptions {
+    #[structopt(long = "checkpoint", parse(from_os_str), help = "Checkpoint file where the state of the run is saved, to continue it with 'moara resume' after a crash. Only for the statevector method.")]
+    path:Option<PathBuf>,
+
+    #[structopt(long = "checkpoint-steps", help = "Number of steps between checkpoints")]
+    every_steps:Option<u32>,
+
+    #[structopt(long = "checkpoint-minutes", help = "Minutes between checkpoints. Defaults to 10 when no number of steps is given.")]
+    every_minutes:Option<f64>,
+}
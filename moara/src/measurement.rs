@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 use num_complex::Complex32;
 use rand::Rng;
-use rand::prelude::ThreadRng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use super::density;
 
-pub fn measure(statevector:Vec<Complex32>, shots:u32, measurements:HashMap<u8,u8>, qubit_count:u8) -> Vec<u32> {
-    let mut rng = rand::thread_rng();
+pub fn measure(statevector:Vec<Complex32>, shots:u32, measurements:HashMap<u8,u8>, qubit_count:u8, seed:Option<u64>) -> Vec<u32> {
+    let mut rng = get_rng(seed);
 
     let len = statevector.len();
     let bit_count = get_bit_count_from_measurements(&measurements);
@@ -25,7 +27,7 @@ pub fn measure(statevector:Vec<Complex32>, shots:u32, measurements:HashMap<u8,u8
     measurement_results
 }
 
-pub fn get_probabilities(statevector:Vec<Complex32>, measurements:HashMap<u8,u8>, qubit_count:u8) -> Vec<f32> {
+pub fn get_probabilities(statevector:&[Complex32], measurements:HashMap<u8,u8>, qubit_count:u8) -> Vec<f32> {
     let len = statevector.len();
     let bit_count = get_bit_count_from_measurements(&measurements);
     let probabilities_len = if measurements.is_empty() { len } else { 1 << bit_count };
@@ -44,7 +46,65 @@ pub fn get_probabilities(statevector:Vec<Complex32>, measurements:HashMap<u8,u8>
     probabilities
 }
 
-fn sample(statevector:&Vec<Complex32>, rng: &mut ThreadRng, len:usize) -> usize {
+// The marginal distribution of the given qubits, the first qubit being the most significant bit of the index.
+// The index bits of every byte of the statevector index are looked up in tables, so the statevector is read once.
+pub fn get_marginal_probabilities(statevector:&[Complex32], qubit_count:u8, qubits:&[u8]) -> Vec<f32> {
+    density::check_qubits(qubits, qubit_count);
+
+    let byte_count = (qubit_count as usize).div_ceil(8);
+    let mut tables = vec![[0usize; 256]; byte_count];
+    for (position, qubit) in qubits.iter().enumerate() {
+        let statevector_bit = (qubit_count - qubit - 1) as usize;
+        let marginal_bit = 1 << (qubits.len() - position - 1);
+        for (value, entry) in tables[statevector_bit/8].iter_mut().enumerate() {
+            if value & (1 << (statevector_bit%8)) != 0 {
+                *entry |= marginal_bit;
+            }
+        }
+    }
+
+    let mut probabilities = vec![0f64; 1 << qubits.len()];
+    for (index, amplitude) in statevector.iter().enumerate() {
+        let marginal_index = tables.iter().enumerate().fold(0, |marginal_index, (byte, table)| marginal_index | table[(index >> (8*byte)) & 0xff]);
+        probabilities[marginal_index] += amplitude.norm_sqr() as f64;
+    }
+
+    probabilities.iter().map(|probability| *probability as f32).collect()
+}
+
+// The number of times each statevector index is sampled, for the callers that cannot hold a count for every value of the bits
+pub fn sample_counts(statevector:&[Complex32], shots:u32, seed:Option<u64>) -> HashMap<usize, u32> {
+    let mut rng = get_rng(seed);
+    let mut counts = HashMap::new();
+    for _ in 0..shots {
+        *counts.entry(sample(statevector, &mut rng, statevector.len())).or_insert(0) += 1;
+    }
+    counts
+}
+
+// The same seed gives the same samples. Without a seed the generator is seeded from the operating system
+pub fn get_rng(seed:Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy()
+    }
+}
+
+pub fn get_bitstring(outcomes:&[bool], measurements:&HashMap<u8,u8>) -> String {
+    if measurements.is_empty() {
+        return outcomes.iter().map(|outcome| if *outcome { '1' } else { '0' }).collect();
+    }
+
+    let bit_count = get_bit_count_from_measurements(measurements);
+    let mut bits = vec![false; bit_count as usize];
+    for (qubit, bit) in measurements {
+        bits[*bit as usize] ^= outcomes[*qubit as usize];
+    }
+
+    bits.iter().map(|bit| if *bit { '1' } else { '0' }).collect()
+}
+
+fn sample(statevector:&[Complex32], rng: &mut StdRng, len:usize) -> usize {
     let sample:f64 = rng.gen();
     let mut running_sum:f64 = 0.0;
     let mut i = 0;
@@ -0,0 +1,468 @@
+use std::cmp::max;
+use std::cmp::min;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use num_complex::Complex32;
+use num_complex::Complex64;
+use rand::Rng;
+use rand::rngs::StdRng;
+use super::circuit::Circuit;
+use super::circuit::Gate;
+use super::gate_mapper;
+use super::linalg;
+
+const MEASUREMENT_X: &str = "measure-x";
+const MEASUREMENT_Y: &str = "measure-y";
+const MEASUREMENT_Z: &str = "measure-z";
+
+pub const DEFAULT_MAX_BOND_DIMENSION: usize = 64;
+pub const DEFAULT_CUTOFF: f64 = 1e-12;
+
+// singular values below this fraction of the largest one are dropped even when moving the orthogonality center
+const RANK_THRESHOLD: f64 = 1e-14;
+
+// A site tensor with indexes (left bond, physical, right bond), stored as data[(left*2 + physical)*right_dimension + right]
+#[derive(Clone)]
+struct Site {
+    left_dimension: usize,
+    right_dimension: usize,
+    data: Vec<Complex64>,
+}
+
+// Matrix product state kept in mixed canonical form around `center`.
+// Two-qubit gates on non-adjacent qubits are applied by swapping the qubits next to each other and back.
+#[derive(Clone)]
+pub struct MatrixProductState {
+    sites: Vec<Site>,
+    center: usize,
+    max_bond_dimension: usize,
+    cutoff: f64,
+    truncation_error: f64,
+    largest_bond_dimension: usize,
+}
+
+impl MatrixProductState {
+    pub fn new(qubit_count:usize, max_bond_dimension:usize, cutoff:f64) -> MatrixProductState {
+        let site = Site { left_dimension: 1, right_dimension: 1, data: vec![Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0)] };
+
+        MatrixProductState {
+            sites: vec![site; qubit_count],
+            center: 0,
+            max_bond_dimension: max(max_bond_dimension, 1),
+            cutoff,
+            truncation_error: 0.0,
+            largest_bond_dimension: 1,
+        }
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.sites.len()
+    }
+
+    // Sum of the squared singular values discarded by all truncations, relative to the norm of the state
+    pub fn truncation_error(&self) -> f64 {
+        self.truncation_error
+    }
+
+    pub fn largest_bond_dimension(&self) -> usize {
+        self.largest_bond_dimension
+    }
+
+    pub fn apply_single_qubit_operator(&mut self, operator:[Complex32; 4], qubit:usize) {
+        let site = &mut self.sites[qubit];
+        let right_dimension = site.right_dimension;
+        let operator = to_complex64(&operator);
+
+        for l in 0..site.left_dimension {
+            for r in 0..right_dimension {
+                let a0 = site.data[(l*2)*right_dimension + r];
+                let a1 = site.data[(l*2 + 1)*right_dimension + r];
+                site.data[(l*2)*right_dimension + r] = operator[0]*a0 + operator[1]*a1;
+                site.data[(l*2 + 1)*right_dimension + r] = operator[2]*a0 + operator[3]*a1;
+            }
+        }
+    }
+
+    // The operator rows and columns are indexed by 2*bit(first) + bit(second)
+    pub fn apply_two_qubit_operator(&mut self, operator:[Complex32; 16], first:usize, second:usize) {
+        let mut operator = to_complex64(&operator);
+        let (left, right) = if first < second { (first, second) } else {
+            operator = swap_qubit_order(&operator);
+            (second, first)
+        };
+
+        let swap = to_complex64(&super::gates::swap());
+        for site in (left+2..=right).rev() {
+            self.apply_adjacent(&swap, site-1);
+        }
+        self.apply_adjacent(&operator, left);
+        for site in left+2..=right {
+            self.apply_adjacent(&swap, site-1);
+        }
+    }
+
+    pub fn amplitude(&self, bits:&[bool]) -> Complex32 {
+        let mut vector = vec![Complex64::new(1.0, 0.0)];
+        for (site, bit) in self.sites.iter().zip(bits) {
+            let physical = *bit as usize;
+            let mut next = vec![Complex64::new(0.0, 0.0); site.right_dimension];
+            for (l, value) in vector.iter().enumerate() {
+                for (r, next_value) in next.iter_mut().enumerate() {
+                    *next_value += value * site.data[(l*2 + physical)*site.right_dimension + r];
+                }
+            }
+            vector = next;
+        }
+        Complex32::new(vector[0].re as f32, vector[0].im as f32)
+    }
+
+    pub fn probability(&self, bits:&[bool]) -> f32 {
+        self.amplitude(bits).norm_sqr()
+    }
+
+    pub fn expectation_value(&mut self, operator:[Complex32; 4], qubit:usize) -> f32 {
+        self.move_center(qubit);
+
+        let operator = to_complex64(&operator);
+        let site = &self.sites[qubit];
+        let right_dimension = site.right_dimension;
+        let mut value = Complex64::new(0.0, 0.0);
+        for l in 0..site.left_dimension {
+            for r in 0..right_dimension {
+                let a0 = site.data[(l*2)*right_dimension + r];
+                let a1 = site.data[(l*2 + 1)*right_dimension + r];
+                value += a0.conj()*(operator[0]*a0 + operator[1]*a1) + a1.conj()*(operator[2]*a0 + operator[3]*a1);
+            }
+        }
+        value.re as f32
+    }
+
+    pub fn sample(&mut self, rng:&mut StdRng) -> Vec<bool> {
+        // with the center on the first site all the other sites are right-canonical
+        self.move_center(0);
+
+        let mut outcomes = Vec::with_capacity(self.sites.len());
+        let mut vector = vec![Complex64::new(1.0, 0.0)];
+        for site in &self.sites {
+            let mut branches = Vec::with_capacity(2);
+            for physical in 0..2 {
+                let mut next = vec![Complex64::new(0.0, 0.0); site.right_dimension];
+                for (l, value) in vector.iter().enumerate() {
+                    for (r, next_value) in next.iter_mut().enumerate() {
+                        *next_value += value * site.data[(l*2 + physical)*site.right_dimension + r];
+                    }
+                }
+                let weight:f64 = next.iter().map(|value| value.norm_sqr()).sum();
+                branches.push((next, weight));
+            }
+
+            let total = branches[0].1 + branches[1].1;
+            let outcome = rng.gen::<f64>() * total >= branches[0].1;
+            let (next, weight) = branches.swap_remove(outcome as usize);
+            vector = next.iter().map(|value| value / weight.sqrt()).collect();
+            outcomes.push(outcome);
+        }
+        outcomes
+    }
+
+    fn apply_adjacent(&mut self, operator:&[Complex64], left:usize) {
+        self.move_center(left);
+
+        let left_dimension = self.sites[left].left_dimension;
+        let bond_dimension = self.sites[left].right_dimension;
+        let right_dimension = self.sites[left+1].right_dimension;
+
+        // theta[l][p1][p2][r] = sum_m A[l][p1][m] * B[m][p2][r], with the operator applied on (p1, p2)
+        let mut theta = vec![Complex64::new(0.0, 0.0); left_dimension*4*right_dimension];
+        for l in 0..left_dimension {
+            for p1 in 0..2 {
+                for m in 0..bond_dimension {
+                    let a = self.sites[left].data[(l*2 + p1)*bond_dimension + m];
+                    if a == Complex64::new(0.0, 0.0) {
+                        continue;
+                    }
+                    for p2 in 0..2 {
+                        for r in 0..right_dimension {
+                            theta[((l*2 + p1)*2 + p2)*right_dimension + r] += a * self.sites[left+1].data[(m*2 + p2)*right_dimension + r];
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut updated = vec![Complex64::new(0.0, 0.0); theta.len()];
+        for l in 0..left_dimension {
+            for r in 0..right_dimension {
+                for row in 0..4 {
+                    let mut value = Complex64::new(0.0, 0.0);
+                    for column in 0..4 {
+                        value += operator[row*4 + column] * theta[(l*4 + column)*right_dimension + r];
+                    }
+                    updated[(l*4 + row)*right_dimension + r] = value;
+                }
+            }
+        }
+
+        let rows = left_dimension*2;
+        let columns = 2*right_dimension;
+        let (u, s, vh) = linalg::svd(&updated, rows, columns);
+        let kept = self.get_kept_count(&s);
+
+        let total:f64 = s.iter().map(|sigma| sigma*sigma).sum();
+        let kept_weight:f64 = s[..kept].iter().map(|sigma| sigma*sigma).sum();
+        if total > 0.0 {
+            self.truncation_error += (total - kept_weight) / total;
+        }
+        let normalization = if kept_weight > 0.0 { (total / kept_weight).sqrt() } else { 1.0 };
+
+        let k = s.len();
+        let mut left_data = vec![Complex64::new(0.0, 0.0); rows*kept];
+        for row in 0..rows {
+            for c in 0..kept {
+                left_data[row*kept + c] = u[row*k + c];
+            }
+        }
+        let mut right_data = vec![Complex64::new(0.0, 0.0); kept*columns];
+        for c in 0..kept {
+            for column in 0..columns {
+                right_data[c*columns + column] = vh[c*columns + column] * s[c] * normalization;
+            }
+        }
+
+        self.sites[left] = Site { left_dimension, right_dimension: kept, data: left_data };
+        self.sites[left+1] = Site { left_dimension: kept, right_dimension, data: right_data };
+        self.center = left + 1;
+        self.largest_bond_dimension = max(self.largest_bond_dimension, kept);
+    }
+
+    // Number of singular values kept: the discarded weight stays under the cutoff and the bond under the maximum
+    fn get_kept_count(&self, singular_values:&[f64]) -> usize {
+        let total:f64 = singular_values.iter().map(|sigma| sigma*sigma).sum();
+        let mut kept = singular_values.len();
+        let mut discarded = 0.0;
+        while kept > 1 {
+            let weight = singular_values[kept-1]*singular_values[kept-1];
+            if total == 0.0 || (discarded + weight) / total > self.cutoff {
+                break;
+            }
+            discarded += weight;
+            kept -= 1;
+        }
+        min(kept, self.max_bond_dimension)
+    }
+
+    fn move_center(&mut self, target:usize) {
+        while self.center < target {
+            let j = self.center;
+            let left_dimension = self.sites[j].left_dimension;
+            let bond_dimension = self.sites[j].right_dimension;
+            let (u, s, vh) = linalg::svd(&self.sites[j].data, left_dimension*2, bond_dimension);
+            let k = s.len();
+            let kept = get_rank(&s);
+
+            let mut left_data = vec![Complex64::new(0.0, 0.0); left_dimension*2*kept];
+            for row in 0..left_dimension*2 {
+                for c in 0..kept {
+                    left_data[row*kept + c] = u[row*k + c];
+                }
+            }
+
+            let next = &self.sites[j+1];
+            let right_dimension = next.right_dimension;
+            let mut right_data = vec![Complex64::new(0.0, 0.0); kept*2*right_dimension];
+            for c in 0..kept {
+                for m in 0..bond_dimension {
+                    let factor = s[c]*vh[c*bond_dimension + m];
+                    for column in 0..2*right_dimension {
+                        right_data[c*2*right_dimension + column] += factor * next.data[m*2*right_dimension + column];
+                    }
+                }
+            }
+
+            self.sites[j] = Site { left_dimension, right_dimension: kept, data: left_data };
+            self.sites[j+1] = Site { left_dimension: kept, right_dimension, data: right_data };
+            self.center += 1;
+        }
+
+        while self.center > target {
+            let j = self.center;
+            let bond_dimension = self.sites[j].left_dimension;
+            let right_dimension = self.sites[j].right_dimension;
+            let (u, s, vh) = linalg::svd(&self.sites[j].data, bond_dimension, 2*right_dimension);
+            let k = s.len();
+            let kept = get_rank(&s);
+
+            let right_data = vh[..kept*2*right_dimension].to_vec();
+
+            let previous = &self.sites[j-1];
+            let left_dimension = previous.left_dimension;
+            let mut left_data = vec![Complex64::new(0.0, 0.0); left_dimension*2*kept];
+            for row in 0..left_dimension*2 {
+                for m in 0..bond_dimension {
+                    let value = previous.data[row*bond_dimension + m];
+                    for c in 0..kept {
+                        left_data[row*kept + c] += value * u[m*k + c] * s[c];
+                    }
+                }
+            }
+
+            self.sites[j-1] = Site { left_dimension, right_dimension: kept, data: left_data };
+            self.sites[j] = Site { left_dimension: kept, right_dimension, data: right_data };
+            self.center -= 1;
+        }
+    }
+}
+
+pub fn get_final_state(qubit_count:usize, circuit:Circuit, max_bond_dimension:usize, cutoff:f64) -> (MatrixProductState, HashMap<u8,u8>) {
+    let mut measurements = HashMap::new();
+
+    let mut ordered_steps = circuit.steps;
+    ordered_steps.sort_by_key(|step| step.index);
+
+    let mut state = MatrixProductState::new(qubit_count, max_bond_dimension, cutoff);
+
+    for step in ordered_steps {
+
+        let mut afected_qubits = HashSet::new();
+
+        for gate in step.gates {
+
+            if matches!(gate.name.as_str(), "barrier" | "snapshot") {
+                continue;
+            }
+
+            for qubit in gate.targets.iter().chain(gate.controls.iter().map(|control| &control.target)) {
+                if measurements.contains_key(qubit) {
+                    panic!("The qubit {} has been measured. Cannot add gates at step {} after measurement", qubit, step.index);
+                }
+
+                if !afected_qubits.insert(*qubit) {
+                    panic!("The qubit {} is mentioned twice in step {}", qubit, step.index);
+                }
+            }
+
+            if gate.targets.is_empty() && gate.gates.is_empty() {
+                panic!("No targets and no aggregated gates provided for gate {} at step {}", gate.name, step.index);
+            }
+
+            if gate.name == "qft" || gate.name == "qft-dagger" {
+                panic!("The gate {} at step {} is not supported by the mps method", gate.name, step.index);
+            }
+
+            if gate.controls.len() > 1 || (gate.controls.len() == 1 && gate.targets.len() > 1) {
+                panic!("The gate {} at step {} acts on more than two qubits and is not supported by the mps method", gate.name, step.index);
+            }
+
+            if gate.name == "aggregate" {
+                for aggregated_gate in &gate.gates {
+                    let regular_gate = Gate {
+                        name: aggregated_gate.name.clone(),
+                        targets: aggregated_gate.targets.to_vec(),
+                        controls: gate.controls.to_vec(),
+                        phi: aggregated_gate.phi,
+                        theta: aggregated_gate.theta,
+                        lambda: aggregated_gate.lambda,
+                        root: aggregated_gate.root.clone(),
+                        bit: None,
+                        gates: Vec::new(),
+                        dagger: false,
+                        arguments: HashMap::new(),
+                        label: None
+                    };
+                    apply_gate(&mut state, &regular_gate);
+                }
+            } else if gate.targets.len() == 2 {
+                let operator = gate_mapper::get_double_target_operator(&gate);
+                // the first qubit of the operator is the one with the highest index
+                let first = max(gate.targets[0], gate.targets[1]) as usize;
+                let second = min(gate.targets[0], gate.targets[1]) as usize;
+                state.apply_two_qubit_operator(operator, first, second);
+            } else if gate.targets.len() == 1 {
+                let target = gate.targets[0];
+                if gate.name == MEASUREMENT_X || gate.name == MEASUREMENT_Y || gate.name == MEASUREMENT_Z {
+                    let bit = match gate.bit { Some(bit) => bit, None => target };
+                    if bit as usize >= qubit_count {
+                        panic!("Measurement bit cannot be larger than the qubit count - 1 ({}). Received {} for qubit {}", qubit_count-1, bit, target);
+                    }
+                    measurements.insert(target, bit);
+                    if gate.name == MEASUREMENT_Z {
+                        continue;
+                    }
+                }
+                apply_gate(&mut state, &gate);
+            } else {
+                panic!("Too many targets for gate {} at step {}", gate.name, step.index);
+            }
+        }
+    }
+
+    (state, measurements)
+}
+
+fn apply_gate(state:&mut MatrixProductState, gate:&Gate) {
+    let operator = gate_mapper::get_single_qubit_operator(gate);
+    let target = gate.targets[0] as usize;
+
+    if gate.controls.is_empty() {
+        state.apply_single_qubit_operator(operator, target);
+        return;
+    }
+
+    let control = &gate.controls[0];
+    let projector = get_control_projector(&control.state);
+
+    // P⊗U + (1-P)⊗I with the control as the first qubit
+    let mut controlled = [Complex32::new(0.0, 0.0); 16];
+    for c_row in 0..2 {
+        for c_column in 0..2 {
+            for t_row in 0..2 {
+                for t_column in 0..2 {
+                    let identity_part = if c_row == c_column && t_row == t_column { Complex32::new(1.0, 0.0) } else { Complex32::new(0.0, 0.0) };
+                    let identity_target = if t_row == t_column { Complex32::new(1.0, 0.0) } else { Complex32::new(0.0, 0.0) };
+                    let value = identity_part + projector[c_row*2 + c_column] * (operator[t_row*2 + t_column] - identity_target);
+                    controlled[(c_row*2 + t_row)*4 + c_column*2 + t_column] = value;
+                }
+            }
+        }
+    }
+
+    state.apply_two_qubit_operator(controlled, control.target as usize, target);
+}
+
+fn get_control_projector(state:&str) -> [Complex32; 4] {
+    let half = Complex32::new(0.5, 0.0);
+    let half_i = Complex32::new(0.0, 0.5);
+    let zero = Complex32::new(0.0, 0.0);
+    let one = Complex32::new(1.0, 0.0);
+
+    match state {
+        "0" => [one, zero, zero, zero],
+        "1" => [zero, zero, zero, one],
+        "+" => [half, half, half, half],
+        "-" => [half, -half, -half, half],
+        "+i" => [half, -half_i, half_i, half],
+        "-i" => [half, half_i, -half_i, half],
+        unknown_state => panic!("Unknown state {}", unknown_state)
+    }
+}
+
+fn get_rank(singular_values:&[f64]) -> usize {
+    let largest = singular_values[0];
+    let rank = singular_values.iter().take_while(|sigma| **sigma > largest*RANK_THRESHOLD).count();
+    max(rank, 1)
+}
+
+fn swap_qubit_order(operator:&[Complex64]) -> Vec<Complex64> {
+    let swapped_index = |index:usize| ((index & 1) << 1) | (index >> 1);
+    let mut swapped = vec![Complex64::new(0.0, 0.0); 16];
+    for row in 0..4 {
+        for column in 0..4 {
+            swapped[swapped_index(row)*4 + swapped_index(column)] = operator[row*4 + column];
+        }
+    }
+    swapped
+}
+
+fn to_complex64(operator:&[Complex32]) -> Vec<Complex64> {
+    operator.iter().map(|value| Complex64::new(value.re as f64, value.im as f64)).collect()
+}
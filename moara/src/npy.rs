@@ -0,0 +1,123 @@
+use std::io::{Result, Write};
+use num_complex::Complex32;
+
+// Reading and writing of one dimensional arrays in the NumPy .npy format (version 1.0, little endian), so numpy.load reads
+// the statevectors and probabilities written by moara and moara reads statevectors saved with numpy.save.
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+pub fn write_complex(values:&[Complex32], double_precision:bool, writer:&mut dyn Write) -> Result<()> {
+    if double_precision {
+        write_header("<c16", values.len(), writer)?;
+        for value in values {
+            writer.write_all(&(value.re as f64).to_le_bytes())?;
+            writer.write_all(&(value.im as f64).to_le_bytes())?;
+        }
+    } else {
+        write_header("<c8", values.len(), writer)?;
+        for value in values {
+            writer.write_all(&value.re.to_le_bytes())?;
+            writer.write_all(&value.im.to_le_bytes())?;
+        }
+    }
+    writer.flush()
+}
+
+pub fn write_float(values:&[f32], double_precision:bool, writer:&mut dyn Write) -> Result<()> {
+    if double_precision {
+        write_header("<f8", values.len(), writer)?;
+        for value in values {
+            writer.write_all(&(*value as f64).to_le_bytes())?;
+        }
+    } else {
+        write_header("<f4", values.len(), writer)?;
+        for value in values {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+    }
+    writer.flush()
+}
+
+// The header is padded with spaces so the data starts at a multiple of 64 bytes, as numpy does
+fn write_header(descr:&str, length:usize, writer:&mut dyn Write) -> Result<()> {
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': ({},), }}", descr, length);
+    let unpadded_length = MAGIC.len() + 2 + 2 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded_length % 64) % 64));
+    header.push('\n');
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())
+}
+
+// Reads a one dimensional array of complex64, complex128, float32 or float64 values, real arrays giving amplitudes with no imaginary part
+pub fn read_complex(bytes:&[u8]) -> Vec<Complex32> {
+    if bytes.len() < 10 || &bytes[0..6] != MAGIC {
+        panic!("The file is not in the npy format");
+    }
+    let (header_length, header_start) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize, 12),
+        version => panic!("Unsupported npy version {}", version)
+    };
+    let data_start = header_start + header_length;
+    if bytes.len() < data_start {
+        panic!("The npy header is truncated");
+    }
+    let header = String::from_utf8_lossy(&bytes[header_start..data_start]);
+
+    let descr = get_header_value(&header, "descr").trim_matches(|c| c == '\'' || c == '"').to_string();
+    if get_header_value(&header, "fortran_order") != "False" {
+        panic!("Arrays in fortran order are not supported");
+    }
+    let shape = get_header_value(&header, "shape");
+    let dimensions:Vec<&str> = shape.trim_matches(|c| c == '(' || c == ')').split(',').map(|dimension| dimension.trim()).filter(|dimension| !dimension.is_empty()).collect();
+    let length:usize = match dimensions.as_slice() {
+        [length] => length.parse().unwrap_or_else(|_| panic!("Invalid npy shape {}", shape)),
+        _ => panic!("Only one dimensional arrays are supported. Found the shape {}", shape)
+    };
+
+    let data = &bytes[data_start..];
+    let item_size = match descr.as_ref() {
+        "<c8" | "<f8" => 8,
+        "<c16" => 16,
+        "<f4" => 4,
+        unknown_type => panic!("Unsupported npy data type {}. Use complex64, complex128, float32 or float64", unknown_type)
+    };
+    if data.len() != length * item_size {
+        panic!("The npy file has {} bytes of data. Expected {} values of {} bytes", data.len(), length, item_size);
+    }
+
+    let f32_at = |offset:usize| f32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
+    let f64_at = |offset:usize| {
+        let mut value = [0u8; 8];
+        value.copy_from_slice(&data[offset..offset + 8]);
+        f64::from_le_bytes(value) as f32
+    };
+
+    (0..length).map(|index| {
+        let offset = index * item_size;
+        match descr.as_ref() {
+            "<c8" => Complex32::new(f32_at(offset), f32_at(offset + 4)),
+            "<c16" => Complex32::new(f64_at(offset), f64_at(offset + 8)),
+            "<f4" => Complex32::new(f32_at(offset), 0.0),
+            _ => Complex32::new(f64_at(offset), 0.0)
+        }
+    }).collect()
+}
+
+// The text of a value in the header dictionary, e.g. '<c8' for descr or (8,) for shape
+fn get_header_value<'a>(header:&'a str, key:&str) -> &'a str {
+    let start = match header.find(&format!("'{}':", key)) {
+        Some(position) => position + key.len() + 3,
+        None => panic!("The npy header has no {}", key)
+    };
+    let rest = header[start..].trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')').map_or(rest.len(), |position| position + 1)
+    } else {
+        rest.find(',').unwrap_or(rest.len())
+    };
+    rest[..end].trim()
+}
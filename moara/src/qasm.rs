@@ -0,0 +1,600 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use super::circuit::Circuit;
+use super::circuit::Control;
+use super::circuit::Gate;
+use super::expression;
+use super::expression::Expression;
+
+// qelib1.inc gates that have no direct moara equivalent, expanded from their standard definitions
+const QELIB_DEFINITIONS: &str = "
+gate rccx a,b,c { u2(0,pi) c; u1(pi/4) c; cx b,c; u1(-pi/4) c; cx a,c; u1(pi/4) c; cx b,c; u1(-pi/4) c; u2(0,pi) c; }
+gate rc3x a,b,c,d { u2(0,pi) d; u1(pi/4) d; cx c,d; u1(-pi/4) d; u2(0,pi) d; cx a,d; u1(pi/4) d; cx b,d; u1(-pi/4) d;
+    cx a,d; u1(pi/4) d; cx b,d; u1(-pi/4) d; u2(0,pi) d; u1(pi/4) d; cx c,d; u1(-pi/4) d; u2(0,pi) d; }
+";
+
+// (name, parameter count, qubit count) of the qelib1.inc gates mapped directly to moara gates
+pub(crate) const QELIB_GATES: [(&str, usize, usize); 40] = [
+    ("U", 3, 1), ("u3", 3, 1), ("u", 3, 1), ("u2", 2, 1), ("u1", 1, 1), ("p", 1, 1), ("u0", 1, 1),
+    ("id", 0, 1), ("x", 0, 1), ("y", 0, 1), ("z", 0, 1), ("h", 0, 1),
+    ("s", 0, 1), ("sdg", 0, 1), ("t", 0, 1), ("tdg", 0, 1), ("sx", 0, 1), ("sxdg", 0, 1),
+    ("rx", 1, 1), ("ry", 1, 1), ("rz", 1, 1),
+    ("CX", 0, 2), ("cx", 0, 2), ("cy", 0, 2), ("cz", 0, 2), ("ch", 0, 2), ("csx", 0, 2),
+    ("crx", 1, 2), ("cry", 1, 2), ("crz", 1, 2), ("cu1", 1, 2), ("cp", 1, 2), ("cu3", 3, 2), ("cu", 4, 2),
+    ("swap", 0, 2), ("rxx", 1, 2), ("ryy", 1, 2), ("rzz", 1, 2),
+    ("ccx", 0, 3), ("cswap", 0, 3),
+];
+
+// multi-controlled gates from qelib1.inc: (name, number of controls)
+const MULTI_CONTROLLED_GATES: [(&str, usize); 2] = [("c3x", 3), ("c4x", 4)];
+
+// Maps a qelib1.inc gate to moara gates. The first qubits of the controlled gates are the controls.
+pub(crate) fn get_qelib_gates(name:&str, parameters:&[f64], qubits:&[u8]) -> Vec<Gate> {
+    let on_one = |qubit:u8| Control { target: qubit, state: String::from("1") };
+    let with_theta = |mut gate:Gate, theta:f64| { gate.theta = Some(theta as f32); gate };
+    let with_root = |mut gate:Gate| { gate.root = Some(String::from("2")); gate };
+
+    let single = |moara_name:&str| Gate::new(moara_name, vec![qubits[0]], Vec::new());
+    let controlled = |moara_name:&str| Gate::new(moara_name, vec![qubits[1]], vec![on_one(qubits[0])]);
+
+    let u3 = |mut gate:Gate| {
+        gate.theta = Some(parameters[0] as f32);
+        gate.phi = Some(parameters[1] as f32);
+        gate.lambda = Some(parameters[2] as f32);
+        gate
+    };
+
+    match name {
+        "U" | "u3" | "u" => vec![u3(single("u3"))],
+        "u2" => {
+            let mut gate = single("u2");
+            gate.phi = Some(parameters[0] as f32);
+            gate.lambda = Some(parameters[1] as f32);
+            vec![gate]
+        },
+        "u1" => {
+            let mut gate = single("u1");
+            gate.lambda = Some(parameters[0] as f32);
+            vec![gate]
+        },
+        "p" => vec![with_theta(single("p"), parameters[0])],
+        "u0" | "id" => vec![single("identity")],
+        "x" => vec![single("pauli-x")],
+        "y" => vec![single("pauli-y")],
+        "z" => vec![single("pauli-z")],
+        "h" => vec![single("hadamard")],
+        "s" => vec![single("s")],
+        "sdg" => vec![single("s-dagger")],
+        "t" => vec![single("t")],
+        "tdg" => vec![single("t-dagger")],
+        "sx" => vec![with_root(single("pauli-x-root"))],
+        "sxdg" => vec![with_root(single("pauli-x-root-dagger"))],
+        "rx" => vec![with_theta(single("rx-theta"), parameters[0])],
+        "ry" => vec![with_theta(single("ry-theta"), parameters[0])],
+        "rz" => vec![with_theta(single("rz-theta"), parameters[0])],
+        "CX" | "cx" => vec![controlled("pauli-x")],
+        "cy" => vec![controlled("pauli-y")],
+        "cz" => vec![controlled("pauli-z")],
+        "ch" => vec![controlled("hadamard")],
+        "csx" => vec![with_root(controlled("pauli-x-root"))],
+        "crx" => vec![with_theta(controlled("rx-theta"), parameters[0])],
+        "cry" => vec![with_theta(controlled("ry-theta"), parameters[0])],
+        "crz" => vec![with_theta(controlled("rz-theta"), parameters[0])],
+        "cu1" | "cp" => vec![with_theta(controlled("p"), parameters[0])],
+        "cu3" => vec![u3(controlled("u3"))],
+        // the global phase gamma of the controlled unitary becomes a phase on the control
+        "cu" => vec![u3(controlled("u3")), with_theta(single("p"), parameters[3])],
+        "swap" => vec![Gate::new("swap", vec![qubits[0], qubits[1]], Vec::new())],
+        "rxx" => vec![with_theta(Gate::new("xx", vec![qubits[0], qubits[1]], Vec::new()), parameters[0])],
+        "ryy" => vec![with_theta(Gate::new("yy", vec![qubits[0], qubits[1]], Vec::new()), parameters[0])],
+        "rzz" => vec![with_theta(Gate::new("zz", vec![qubits[0], qubits[1]], Vec::new()), parameters[0])],
+        "ccx" => vec![Gate::new("pauli-x", vec![qubits[2]], vec![on_one(qubits[0]), on_one(qubits[1])])],
+        "cswap" => vec![Gate::new("swap", vec![qubits[1], qubits[2]], vec![on_one(qubits[0])])],
+        "c3x" | "c4x" => {
+            let control_count = qubits.len() - 1;
+            vec![Gate::new("pauli-x", vec![qubits[control_count]], qubits[..control_count].iter().map(|qubit| on_one(*qubit)).collect())]
+        },
+        "c3sqrtx" => vec![with_root(Gate::new("pauli-x-root", vec![qubits[3]], qubits[..3].iter().map(|qubit| on_one(*qubit)).collect()))],
+        unknown_gate => panic!("Unknown gate {}", unknown_gate)
+    }
+}
+
+pub(crate) fn get_qelib_signature(name:&str) -> Option<(usize, usize)> {
+    if let Some((_, parameter_count, qubit_count)) = QELIB_GATES.iter().find(|(gate_name, _, _)| *gate_name == name) {
+        return Some((*parameter_count, *qubit_count));
+    }
+    if let Some((_, control_count)) = MULTI_CONTROLLED_GATES.iter().find(|(gate_name, _)| *gate_name == name) {
+        return Some((0, control_count + 1));
+    }
+    if name == "c3sqrtx" {
+        return Some((0, 4));
+    }
+    None
+}
+
+// Parses an OpenQASM 2.0 program. Returns the circuit and the number of declared qubits.
+// There is no classical control in moara, so `if` is evaluated when the circuit is built: it is supported only
+// on registers that have not been measured yet. For the same reason `reset` is accepted only on unused qubits.
+pub fn parse(source:&str) -> (Circuit, u8) {
+    let mut parser = Parser::new(tokenize(source));
+    parser.definitions = parse_definitions(QELIB_DEFINITIONS);
+    parser.parse_program();
+
+    if parser.qubit_count > u8::MAX as usize {
+        panic!("At most {} qubits are supported. The program declares {}", u8::MAX, parser.qubit_count);
+    }
+
+    (Circuit::from_gates(parser.gates), parser.qubit_count as u8)
+}
+
+fn parse_definitions(source:&str) -> HashMap<String, Definition> {
+    let mut parser = Parser::new(tokenize(source));
+    while !parser.is_at_end() {
+        parser.expect("gate");
+        parser.parse_gate_definition();
+    }
+    parser.definitions
+}
+
+#[derive(Clone, PartialEq)]
+enum TokenKind {
+    Identifier,
+    Number,
+    Text,
+    Symbol,
+}
+
+#[derive(Clone)]
+struct Token {
+    kind: TokenKind,
+    text: String,
+    line: usize,
+}
+
+fn tokenize(source:&str) -> Vec<Token> {
+    let chars:Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    let mut line = 1;
+
+    while position < chars.len() {
+        let c = chars[position];
+        let start = position;
+
+        if c == '\n' {
+            line += 1;
+            position += 1;
+        } else if c.is_whitespace() {
+            position += 1;
+        } else if c == '/' && chars.get(position + 1) == Some(&'/') {
+            while position < chars.len() && chars[position] != '\n' {
+                position += 1;
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            while position < chars.len() && (chars[position].is_alphanumeric() || chars[position] == '_') {
+                position += 1;
+            }
+            tokens.push(Token { kind: TokenKind::Identifier, text: chars[start..position].iter().collect(), line });
+        } else if c.is_ascii_digit() || c == '.' {
+            while position < chars.len() {
+                let d = chars[position];
+                let exponent_sign = (d == '-' || d == '+') && matches!(chars[position - 1], 'e' | 'E');
+                if d.is_ascii_digit() || d == '.' || d == 'e' || d == 'E' || exponent_sign {
+                    position += 1;
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token { kind: TokenKind::Number, text: chars[start..position].iter().collect(), line });
+        } else if c == '"' {
+            position += 1;
+            while position < chars.len() && chars[position] != '"' {
+                position += 1;
+            }
+            if position == chars.len() {
+                panic!("Unterminated string at line {}", line);
+            }
+            position += 1;
+            tokens.push(Token { kind: TokenKind::Text, text: chars[start+1..position-1].iter().collect(), line });
+        } else {
+            let pair:String = chars[position..chars.len().min(position + 2)].iter().collect();
+            let text = if pair == "->" || pair == "==" { pair } else { c.to_string() };
+            position += text.chars().count();
+            tokens.push(Token { kind: TokenKind::Symbol, text, line });
+        }
+    }
+
+    tokens
+}
+
+// Gate defined with the `gate` statement. The body refers to the parameters and the qubits by name.
+struct Definition {
+    parameters: Vec<String>,
+    qubits: Vec<String>,
+    body: Vec<Call>,
+}
+
+struct Call {
+    name: String,
+    parameters: Vec<Expression>,
+    arguments: Vec<Argument>,
+}
+
+struct Argument {
+    register: String,
+    index: Option<usize>,
+}
+
+struct Register {
+    offset: usize,
+    size: usize,
+    measured: bool,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    definitions: HashMap<String, Definition>,
+    quantum_registers: HashMap<String, Register>,
+    classical_registers: HashMap<String, Register>,
+    qubit_count: usize,
+    bit_count: usize,
+    used_qubits: HashSet<u8>,
+    gates: Vec<Gate>,
+}
+
+impl Parser {
+    fn new(tokens:Vec<Token>) -> Parser {
+        Parser {
+            tokens,
+            position: 0,
+            definitions: HashMap::new(),
+            quantum_registers: HashMap::new(),
+            classical_registers: HashMap::new(),
+            qubit_count: 0,
+            bit_count: 0,
+            used_qubits: HashSet::new(),
+            gates: Vec::new(),
+        }
+    }
+
+    fn parse_program(&mut self) {
+        if self.peek_is("OPENQASM") {
+            self.next();
+            let version = self.next();
+            if version.text != "2.0" && version.text != "2" {
+                panic!("Only OpenQASM 2.0 is supported. Received version {}", version.text);
+            }
+            self.expect(";");
+        }
+
+        while !self.is_at_end() {
+            self.parse_statement();
+        }
+    }
+
+    fn parse_statement(&mut self) {
+        let token = self.next();
+        let line = token.line;
+
+        match token.text.as_ref() {
+            "include" => {
+                let filename = self.next();
+                if filename.kind != TokenKind::Text || filename.text != "qelib1.inc" {
+                    panic!("Only qelib1.inc can be included. Received {} at line {}", filename.text, line);
+                }
+                self.expect(";");
+            },
+            "qreg" | "creg" => {
+                let name = self.next_identifier();
+                self.expect("[");
+                let size = self.next_integer();
+                self.expect("]");
+                self.expect(";");
+
+                let (registers, count) = if token.text == "qreg" {
+                    (&mut self.quantum_registers, &mut self.qubit_count)
+                } else {
+                    (&mut self.classical_registers, &mut self.bit_count)
+                };
+                if registers.contains_key(&name) {
+                    panic!("The register {} is declared twice at line {}", name, line);
+                }
+                registers.insert(name, Register { offset: *count, size, measured: false });
+                *count += size;
+            },
+            "gate" => self.parse_gate_definition(),
+            "opaque" => panic!("Opaque gates cannot be simulated. Found one at line {}", line),
+            "if" => {
+                self.expect("(");
+                let name = self.next_identifier();
+                self.expect("==");
+                let value = self.next_integer();
+                self.expect(")");
+
+                let register = match self.classical_registers.get(&name) {
+                    Some(register) => register,
+                    None => panic!("Unknown classical register {} at line {}", name, line)
+                };
+                if register.measured {
+                    panic!("The condition on register {} at line {} depends on a measurement. Classically controlled gates are supported only on registers that have not been measured", name, line);
+                }
+
+                // an unmeasured register holds zero, so the condition is known before the simulation
+                let gate_count = self.gates.len();
+                self.parse_statement();
+                if value != 0 {
+                    self.gates.truncate(gate_count);
+                }
+            },
+            "measure" => {
+                let qubits = self.parse_argument();
+                self.expect("->");
+                let bits = self.parse_argument();
+                self.expect(";");
+
+                let qubits = self.resolve_qubits(&qubits, line);
+                let bits = self.resolve_bits(&bits, line);
+                if qubits.len() != bits.len() {
+                    panic!("The quantum and classical registers at line {} have different sizes", line);
+                }
+                for (qubit, bit) in qubits.iter().zip(bits) {
+                    let mut gate = Gate::new("measure-z", vec![*qubit], Vec::new());
+                    gate.bit = Some(bit as u8);
+                    self.add_gate(gate);
+                }
+            },
+            "reset" => {
+                let argument = self.parse_argument();
+                self.expect(";");
+
+                for qubit in self.resolve_qubits(&argument, line) {
+                    if self.used_qubits.contains(&qubit) {
+                        panic!("reset at line {} is supported only on qubits that have not been used yet", line);
+                    }
+                }
+            },
+            "barrier" => {
+                let arguments = self.parse_arguments();
+                self.expect(";");
+
+                let mut qubits = Vec::new();
+                for argument in &arguments {
+                    qubits.extend(self.resolve_qubits(argument, line));
+                }
+                self.add_gate(Gate::new("barrier", qubits, Vec::new()));
+            },
+            _ => {
+                if token.kind != TokenKind::Identifier {
+                    panic!("Unexpected '{}' at line {}", token.text, line);
+                }
+                let call = self.parse_call(token.text);
+                self.apply_call(&call, line);
+            }
+        }
+    }
+
+    fn parse_gate_definition(&mut self) {
+        let name = self.next_identifier();
+
+        let mut parameters = Vec::new();
+        if self.peek_is("(") {
+            self.next();
+            while !self.peek_is(")") {
+                parameters.push(self.next_identifier());
+                if !self.peek_is(")") {
+                    self.expect(",");
+                }
+            }
+            self.next();
+        }
+
+        let mut qubits = vec![self.next_identifier()];
+        while self.peek_is(",") {
+            self.next();
+            qubits.push(self.next_identifier());
+        }
+
+        self.expect("{");
+        let mut body = Vec::new();
+        while !self.peek_is("}") {
+            let token = self.next();
+            if token.kind != TokenKind::Identifier {
+                panic!("Unexpected '{}' in the definition of gate {} at line {}", token.text, name, token.line);
+            }
+            body.push(self.parse_call(token.text));
+        }
+        self.next();
+
+        self.definitions.insert(name, Definition { parameters, qubits, body });
+    }
+
+    fn parse_call(&mut self, name:String) -> Call {
+        let mut parameters = Vec::new();
+        if self.peek_is("(") {
+            self.next();
+            let mut depth = 0;
+            let mut text = String::new();
+            loop {
+                let token = self.next();
+                match token.text.as_ref() {
+                    "(" => depth += 1,
+                    ")" if depth == 0 => break,
+                    ")" => depth -= 1,
+                    "," if depth == 0 => {
+                        parameters.push(expression::parse(&text));
+                        text.clear();
+                        continue;
+                    },
+                    _ => {}
+                }
+                text.push_str(&token.text);
+                text.push(' ');
+            }
+            if !text.trim().is_empty() {
+                parameters.push(expression::parse(&text));
+            }
+        }
+
+        let arguments = self.parse_arguments();
+        self.expect(";");
+
+        Call { name, parameters, arguments }
+    }
+
+    fn parse_arguments(&mut self) -> Vec<Argument> {
+        let mut arguments = vec![self.parse_argument()];
+        while self.peek_is(",") {
+            self.next();
+            arguments.push(self.parse_argument());
+        }
+        arguments
+    }
+
+    fn parse_argument(&mut self) -> Argument {
+        let register = self.next_identifier();
+        let mut index = None;
+        if self.peek_is("[") {
+            self.next();
+            index = Some(self.next_integer());
+            self.expect("]");
+        }
+        Argument { register, index }
+    }
+
+    // Applies a gate statement, repeating it for each qubit when whole registers are passed as arguments
+    fn apply_call(&mut self, call:&Call, line:usize) {
+        let parameters:Vec<f64> = call.parameters.iter().map(|parameter| parameter.evaluate(&HashMap::new())).collect();
+        let arguments:Vec<Vec<u8>> = call.arguments.iter().map(|argument| self.resolve_qubits(argument, line)).collect();
+
+        let mut size = 1;
+        for (argument, qubits) in call.arguments.iter().zip(&arguments) {
+            if argument.index.is_none() && qubits.len() != 1 {
+                if size != 1 && size != qubits.len() {
+                    panic!("The registers passed to {} at line {} have different sizes", call.name, line);
+                }
+                size = qubits.len();
+            }
+        }
+
+        for i in 0..size {
+            let qubits:Vec<u8> = arguments.iter().map(|qubits| if qubits.len() == 1 { qubits[0] } else { qubits[i] }).collect();
+            self.apply_gate(&call.name, &parameters, &qubits, line);
+        }
+    }
+
+    fn apply_gate(&mut self, name:&str, parameters:&[f64], qubits:&[u8], line:usize) {
+        if let Some(definition) = self.definitions.get(name) {
+            check_signature(name, parameters, qubits, definition.parameters.len(), definition.qubits.len(), line);
+
+            let variables:HashMap<String, f64> = definition.parameters.iter().cloned().zip(parameters.iter().cloned()).collect();
+            let names:HashMap<&String, u8> = definition.qubits.iter().zip(qubits.iter().cloned()).collect();
+
+            let mut expanded = Vec::new();
+            for call in &definition.body {
+                let call_parameters:Vec<f64> = call.parameters.iter().map(|parameter| parameter.evaluate(&variables)).collect();
+                let call_qubits:Vec<u8> = call.arguments.iter().map(|argument| match names.get(&argument.register) {
+                    Some(qubit) if argument.index.is_none() => *qubit,
+                    _ => panic!("Unknown qubit {} in the definition of gate {}", argument.register, name)
+                }).collect();
+                expanded.push((call.name.clone(), call_parameters, call_qubits));
+            }
+
+            for (call_name, call_parameters, call_qubits) in expanded {
+                if call_name == "barrier" {
+                    self.add_gate(Gate::new("barrier", call_qubits, Vec::new()));
+                } else {
+                    self.apply_gate(&call_name, &call_parameters, &call_qubits, line);
+                }
+            }
+            return;
+        }
+
+        match get_qelib_signature(name) {
+            Some((parameter_count, qubit_count)) => {
+                check_signature(name, parameters, qubits, parameter_count, qubit_count, line);
+                for gate in get_qelib_gates(name, parameters, qubits) {
+                    self.add_gate(gate);
+                }
+            },
+            None => panic!("Unknown gate {} at line {}", name, line)
+        }
+    }
+
+    fn add_gate(&mut self, gate:Gate) {
+        if gate.name != "barrier" {
+            self.used_qubits.extend(gate.get_qubits());
+        }
+        self.gates.push(gate);
+    }
+
+    fn resolve_qubits(&self, argument:&Argument, line:usize) -> Vec<u8> {
+        let register = match self.quantum_registers.get(&argument.register) {
+            Some(register) => register,
+            None => panic!("Unknown quantum register {} at line {}", argument.register, line)
+        };
+        get_indexes(register, argument, line).into_iter().map(|index| index as u8).collect()
+    }
+
+    fn resolve_bits(&mut self, argument:&Argument, line:usize) -> Vec<usize> {
+        let register = match self.classical_registers.get_mut(&argument.register) {
+            Some(register) => register,
+            None => panic!("Unknown classical register {} at line {}", argument.register, line)
+        };
+        register.measured = true;
+        get_indexes(register, argument, line)
+    }
+
+    fn next(&mut self) -> Token {
+        if self.is_at_end() {
+            panic!("Unexpected end of program");
+        }
+        self.position += 1;
+        self.tokens[self.position - 1].clone()
+    }
+
+    fn next_identifier(&mut self) -> String {
+        let token = self.next();
+        if token.kind != TokenKind::Identifier {
+            panic!("Expected a name at line {}. Received '{}'", token.line, token.text);
+        }
+        token.text
+    }
+
+    fn next_integer(&mut self) -> usize {
+        let token = self.next();
+        match token.text.parse::<usize>() {
+            Ok(value) if token.kind == TokenKind::Number => value,
+            _ => panic!("Expected an integer at line {}. Received '{}'", token.line, token.text)
+        }
+    }
+
+    fn expect(&mut self, expected:&str) {
+        let token = self.next();
+        if token.text != expected {
+            panic!("Expected '{}' at line {}. Received '{}'", expected, token.line, token.text);
+        }
+    }
+
+    fn peek_is(&self, text:&str) -> bool {
+        !self.is_at_end() && self.tokens[self.position].text == text
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+}
+
+fn get_indexes(register:&Register, argument:&Argument, line:usize) -> Vec<usize> {
+    match argument.index {
+        Some(index) if index < register.size => vec![register.offset + index],
+        Some(index) => panic!("Index {} is out of the bounds of register {} at line {}", index, argument.register, line),
+        None => (register.offset..register.offset + register.size).collect()
+    }
+}
+
+fn check_signature(name:&str, parameters:&[f64], qubits:&[u8], parameter_count:usize, qubit_count:usize, line:usize) {
+    if parameters.len() != parameter_count || qubits.len() != qubit_count {
+        panic!("The gate {} at line {} expects {} parameters and {} qubits. Received {} and {}", name, line, parameter_count, qubit_count, parameters.len(), qubits.len());
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use num_complex::Complex32;
+use num_complex::Complex64;
+use super::circuit::Circuit;
+use super::circuit::Control;
+use super::circuit::Gate;
+use super::decomposition;
+use super::decomposition::Operation;
+use super::engine;
+use super::gate_mapper;
+
+const ANGLE_TOLERANCE: f64 = 1e-6;
+
+// qelib1.inc gates with controls, by (gate, number of controls)
+const CONTROLLED_QELIB_GATES: [(&str, usize, &str); 15] = [
+    ("x", 1, "cx"), ("y", 1, "cy"), ("z", 1, "cz"), ("h", 1, "ch"), ("sx", 1, "csx"),
+    ("rx", 1, "crx"), ("ry", 1, "cry"), ("rz", 1, "crz"), ("u1", 1, "cu1"), ("u3", 1, "cu3"), ("swap", 1, "cswap"),
+    ("x", 2, "ccx"), ("x", 3, "c3x"), ("sx", 3, "c3sqrtx"), ("x", 4, "c4x"),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Version {
+    Qasm2,
+    Qasm3,
+}
+
+// Unitary of a gate, used to build the definitions of the gates with no standard equivalent
+enum Unitary {
+    Single([Complex64; 4]),
+    Double([Complex64; 16]),
+    Qft { dagger:bool },
+}
+
+// Writes the circuit as OpenQASM. Gates without a standard equivalent, and in OpenQASM 2.0 also the controlled
+// variants missing from qelib1.inc, are emitted as gate definitions decomposed into u3, cu3 and cx.
+// Controls on the X and Y basis states are rotated to the Z basis around the gate, as the engine does.
+pub fn export(circuit:Circuit, qubit_count:u8, version:&str) -> String {
+    let version = match version {
+        "qasm2" => Version::Qasm2,
+        "qasm3" => Version::Qasm3,
+        unknown_version => panic!("Unknown OpenQASM version {}. Use 'qasm2' or 'qasm3'", unknown_version)
+    };
+
+    let mut writer = Writer { version, definitions: Vec::new(), definition_names: HashMap::new(), lines: Vec::new() };
+
+    let mut ordered_steps = circuit.steps;
+    ordered_steps.sort_by_key(|step| step.index);
+
+    let mut bit_count = 0;
+    for step in &ordered_steps {
+        for gate in &step.gates {
+            if gate.name.starts_with("measure-") {
+                let bit = gate.bit.unwrap_or(gate.targets[0]);
+                bit_count = bit_count.max(bit as usize + 1);
+            }
+            writer.write_gate(gate);
+        }
+    }
+
+    let mut program = match version {
+        Version::Qasm2 => String::from("OPENQASM 2.0;\ninclude \"qelib1.inc\";\n"),
+        Version::Qasm3 => String::from("OPENQASM 3.0;\ninclude \"stdgates.inc\";\n"),
+    };
+    for definition in &writer.definitions {
+        program.push_str(definition);
+        program.push('\n');
+    }
+    match version {
+        Version::Qasm2 => {
+            program.push_str(&format!("qreg q[{}];\n", qubit_count));
+            if bit_count > 0 {
+                program.push_str(&format!("creg c[{}];\n", bit_count));
+            }
+        },
+        Version::Qasm3 => {
+            program.push_str(&format!("qubit[{}] q;\n", qubit_count));
+            if bit_count > 0 {
+                program.push_str(&format!("bit[{}] c;\n", bit_count));
+            }
+        }
+    }
+    for line in &writer.lines {
+        program.push_str(line);
+        program.push('\n');
+    }
+    program
+}
+
+struct Writer {
+    version: Version,
+    definitions: Vec<String>,
+    // definition body -> gate name, so that identical definitions are written once
+    definition_names: HashMap<String, String>,
+    lines: Vec<String>,
+}
+
+impl Writer {
+    fn write_gate(&mut self, gate:&Gate) {
+        match gate.name.as_ref() {
+            "barrier" => {
+                let qubits:Vec<String> = gate.targets.iter().map(|qubit| get_qubit(*qubit)).collect();
+                self.lines.push(format!("barrier {};", qubits.join(", ")));
+            },
+            "snapshot" => self.lines.push(format!("// snapshot {}", gate.label.as_deref().unwrap_or(""))),
+            "measure-x" | "measure-y" | "measure-z" => {
+                let target = gate.targets[0];
+                let bit = gate.bit.unwrap_or(target);
+                if gate.name == "measure-y" {
+                    self.lines.push(format!("sdg {};", get_qubit(target)));
+                }
+                if gate.name != "measure-z" {
+                    self.lines.push(format!("h {};", get_qubit(target)));
+                }
+                self.lines.push(match self.version {
+                    Version::Qasm2 => format!("measure {} -> c[{}];", get_qubit(target), bit),
+                    Version::Qasm3 => format!("c[{}] = measure {};", bit, get_qubit(target)),
+                });
+            },
+            "identity" => {
+                // a controlled identity is still the identity
+                self.lines.push(format!("id {};", get_qubit(gate.targets[0])));
+            },
+            "aggregate" => {
+                for aggregated_gate in &gate.gates {
+                    let mut regular_gate = Gate::new(&aggregated_gate.name, aggregated_gate.targets.to_vec(), gate.controls.to_vec());
+                    regular_gate.phi = aggregated_gate.phi;
+                    regular_gate.theta = aggregated_gate.theta;
+                    regular_gate.lambda = aggregated_gate.lambda;
+                    regular_gate.root = aggregated_gate.root.clone();
+                    self.write_gate(&regular_gate);
+                }
+            },
+            _ => self.write_unitary_gate(gate)
+        }
+    }
+
+    fn write_unitary_gate(&mut self, gate:&Gate) {
+        let (before, after, controls) = self.get_control_rotations(&gate.controls);
+        self.lines.extend(before);
+
+        let (unitary, targets) = get_unitary(gate);
+        let name = match unitary {
+            Unitary::Qft { .. } => format!("{}-{}", gate.name, targets.len()),
+            _ => gate.name.clone()
+        };
+        let control_count = controls.len();
+        let native_gate = get_native_gate(gate, self.version);
+
+        let call = match (self.version, native_gate) {
+            (Version::Qasm2, Some((name, parameters))) if control_count == 0 => format_call(name, &parameters, self.version),
+            (Version::Qasm2, Some((name, parameters))) if get_controlled_qelib_gate(name, control_count).is_some() => {
+                format_call(get_controlled_qelib_gate(name, control_count).unwrap(), &parameters, self.version)
+            },
+            (Version::Qasm2, _) => {
+                let controls:Vec<(u8, bool)> = (0..control_count as u8).map(|qubit| (qubit, true)).collect();
+                let prefix = if control_count == 0 { String::new() } else { format!("c{}_", control_count) };
+                self.define(&format!("{}{}", prefix, name), &unitary, &controls, targets.len())
+            },
+            (Version::Qasm3, native_gate) => {
+                let modifiers:String = controls.iter().map(|(_, value)| if *value { "ctrl @ " } else { "negctrl @ " }).collect();
+                let name = match native_gate {
+                    Some((name, parameters)) => format_call(name, &parameters, self.version),
+                    None => self.define(&name, &unitary, &[], targets.len())
+                };
+                format!("{}{}", modifiers, name)
+            }
+        };
+
+        let mut qubits:Vec<String> = controls.iter().map(|(qubit, _)| get_qubit(*qubit)).collect();
+        qubits.extend(targets.iter().map(|qubit| get_qubit(*qubit)));
+        self.lines.push(format!("{} {};", call, qubits.join(", ")));
+
+        self.lines.extend(after);
+    }
+
+    // Rotations that map the control states to |1> (or |0> in OpenQASM 3) before the gate and back after it
+    fn get_control_rotations(&self, controls:&[Control]) -> (Vec<String>, Vec<String>, Vec<(u8, bool)>) {
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+        let mut values = Vec::new();
+
+        for control in controls {
+            let qubit = get_qubit(control.target);
+            let (rotation, undo, value):(&[&str], &[&str], bool) = match (control.state.as_ref(), self.version) {
+                ("1", _) => (&[], &[], true),
+                ("0", Version::Qasm3) => (&[], &[], false),
+                ("0", Version::Qasm2) => (&["x"], &["x"], true),
+                ("-", _) => (&["h"], &["h"], true),
+                ("+", _) => (&["h", "x"], &["x", "h"], true),
+                ("+i", _) => (&["sdg", "h", "x"], &["x", "h", "s"], true),
+                ("-i", _) => (&["sdg", "h"], &["h", "s"], true),
+                (unknown_state, _) => panic!("Unknown control state {} for qubit {}", unknown_state, control.target)
+            };
+            before.extend(rotation.iter().map(|name| format!("{} {};", name, qubit)));
+            after.extend(undo.iter().map(|name| format!("{} {};", name, qubit)));
+            values.push((control.target, value));
+        }
+
+        (before, after, values)
+    }
+
+    // Writes a gate definition on control_count + target_count qubits, the controls first. Returns its name.
+    fn define(&mut self, name:&str, unitary:&Unitary, controls:&[(u8, bool)], target_count:usize) -> String {
+        let control_count = controls.len() as u8;
+        let targets:Vec<u8> = (control_count..control_count + target_count as u8).collect();
+
+        let operations = match unitary {
+            Unitary::Single(matrix) => decomposition::decompose_controlled(*matrix, targets[0], controls),
+            Unitary::Double(matrix) => decomposition::decompose_two_qubit(*matrix, targets[0], targets[1], controls),
+            Unitary::Qft { dagger } => {
+                let mut operations = Vec::new();
+                let qft_controls:Vec<Control> = controls.iter().map(|(qubit, _)| Control { target: *qubit, state: String::from("1") }).collect();
+                let mut apply = |operator:[Complex32; 4], target:u8, controls:Vec<Control>| {
+                    let controls:Vec<(u8, bool)> = controls.iter().map(|control| (control.target, control.state == "1")).collect();
+                    operations.extend(decomposition::decompose_controlled(to_complex64(&operator), target, &controls));
+                };
+                if *dagger {
+                    engine::apply_qft_dagger_gate(&mut apply, targets.to_vec(), qft_controls);
+                } else {
+                    engine::apply_qft_gate(&mut apply, targets.to_vec(), qft_controls);
+                }
+                operations
+            }
+        };
+
+        let qubit_count = control_count as usize + target_count;
+        let formal_qubits:Vec<String> = (0..qubit_count).map(|qubit| format!("q{}", qubit)).collect();
+        let body:Vec<String> = operations.iter().filter_map(|operation| format_operation(operation, self.version)).collect();
+        let signature = format!("{} {{ {} }}", formal_qubits.join(", "), body.join(" "));
+
+        if let Some(name) = self.definition_names.get(&signature) {
+            return name.clone();
+        }
+
+        let mut base_name = name.replace('-', "_");
+        if is_reserved_name(&base_name) {
+            base_name = format!("moara_{}", base_name);
+        }
+        let mut unique_name = base_name.clone();
+        let mut suffix = 2;
+        while self.definition_names.values().any(|existing| *existing == unique_name) {
+            unique_name = format!("{}_{}", base_name, suffix);
+            suffix += 1;
+        }
+
+        self.definitions.push(format!("gate {} {}", unique_name, signature));
+        self.definition_names.insert(signature, unique_name.clone());
+        unique_name
+    }
+}
+
+// Unitary of the gate and the target qubits in the order of the definition arguments.
+// Double target operators are written with the highest qubit first, as the engine applies them.
+fn get_unitary(gate:&Gate) -> (Unitary, Vec<u8>) {
+    match gate.name.as_ref() {
+        "qft" | "qft-dagger" => (Unitary::Qft { dagger: gate.name == "qft-dagger" }, gate.targets.to_vec()),
+        _ if gate.targets.len() == 2 => {
+            let operator = gate_mapper::get_double_target_operator(gate);
+            let mut matrix = [Complex64::new(0.0, 0.0); 16];
+            for (i, value) in operator.iter().enumerate() {
+                matrix[i] = Complex64::new(value.re as f64, value.im as f64);
+            }
+            let first = gate.targets[0].max(gate.targets[1]);
+            let last = gate.targets[0].min(gate.targets[1]);
+            (Unitary::Double(matrix), vec![first, last])
+        },
+        _ => (Unitary::Single(to_complex64(&gate_mapper::get_single_qubit_operator(gate))), gate.targets.to_vec())
+    }
+}
+
+// Standard gate equivalent to a moara gate, named as in qelib1.inc
+fn get_native_gate(gate:&Gate, version:Version) -> Option<(&'static str, Vec<f64>)> {
+    let theta = gate.theta.unwrap_or(0.0) as f64;
+    let phi = gate.phi.unwrap_or(0.0) as f64;
+    let lambda = gate.lambda.unwrap_or(0.0) as f64;
+    let root = gate.root.as_ref().map(|root| gate_mapper::get_value_from_root(root) as f64);
+
+    let native_gate = match gate.name.as_ref() {
+        "pauli-x" => ("x", vec![]),
+        "pauli-y" => ("y", vec![]),
+        "pauli-z" => ("z", vec![]),
+        "hadamard" => ("h", vec![]),
+        "t" => ("t", vec![]),
+        "t-dagger" => ("tdg", vec![]),
+        "s" => ("s", vec![]),
+        "s-dagger" => ("sdg", vec![]),
+        "u3" => ("u3", vec![theta, phi, lambda]),
+        "u2" => ("u2", vec![phi, lambda]),
+        "u1" => ("u1", vec![lambda]),
+        "p" => ("u1", vec![theta]),
+        "rx-theta" => ("rx", vec![theta]),
+        "ry-theta" => ("ry", vec![theta]),
+        "rz-theta" => ("rz", vec![theta]),
+        "pauli-z-root" => ("u1", vec![PI/root?]),
+        "pauli-z-root-dagger" => ("u1", vec![-PI/root?]),
+        "pauli-x-root" if root == Some(2.0) => ("sx", vec![]),
+        "pauli-x-root-dagger" if root == Some(2.0) => ("sxdg", vec![]),
+        "swap" => ("swap", vec![]),
+        "xx" if version == Version::Qasm2 => ("rxx", vec![theta]),
+        "zz" if version == Version::Qasm2 => ("rzz", vec![theta]),
+        _ => return None
+    };
+    Some(native_gate)
+}
+
+fn get_controlled_qelib_gate(name:&str, control_count:usize) -> Option<&'static str> {
+    CONTROLLED_QELIB_GATES.iter()
+        .find(|(gate_name, count, _)| *gate_name == name && *count == control_count)
+        .map(|(_, _, controlled_name)| *controlled_name)
+}
+
+// Translates the qelib1.inc names to stdgates.inc, where u3 and u2 differ from U by a global phase
+fn format_call(name:&str, parameters:&[f64], version:Version) -> String {
+    let (name, parameters) = match (version, name) {
+        (Version::Qasm3, "u3") => ("U", parameters.to_vec()),
+        (Version::Qasm3, "u2") => ("U", vec![PI/2.0, parameters[0], parameters[1]]),
+        (Version::Qasm3, "u1") => ("p", parameters.to_vec()),
+        (Version::Qasm3, "sxdg") => ("inv @ sx", vec![]),
+        _ => (name, parameters.to_vec())
+    };
+
+    if parameters.is_empty() {
+        name.to_string()
+    } else {
+        let parameters:Vec<String> = parameters.iter().map(|parameter| format_angle(*parameter)).collect();
+        format!("{}({})", name, parameters.join(", "))
+    }
+}
+
+fn format_operation(operation:&Operation, version:Version) -> Option<String> {
+    let qubit = |index:&u8| format!("q{}", index);
+
+    match operation {
+        Operation::GlobalPhase(alpha) => match version {
+            // a global phase is not observable in OpenQASM 2.0, where defined gates cannot be controlled
+            Version::Qasm2 => None,
+            Version::Qasm3 => Some(format!("gphase({});", format_angle(*alpha)))
+        },
+        Operation::X(target) => Some(format!("x {};", qubit(target))),
+        Operation::Phase { lambda, target } => Some(format!("{} {};", format_call("u1", &[*lambda], version), qubit(target))),
+        Operation::U3 { theta, phi, lambda, target } => {
+            if theta.abs() < ANGLE_TOLERANCE {
+                if is_multiple_of_two_pi(phi + lambda) {
+                    return None;
+                }
+                return Some(format!("{} {};", format_call("u1", &[phi + lambda], version), qubit(target)));
+            }
+            Some(format!("{} {};", format_call("u3", &[*theta, *phi, *lambda], version), qubit(target)))
+        },
+        Operation::Cx { control, target } => Some(format!("cx {}, {};", qubit(control), qubit(target))),
+        Operation::Cu3 { theta, phi, lambda, control, target } => {
+            let call = if theta.abs() < ANGLE_TOLERANCE {
+                if is_multiple_of_two_pi(phi + lambda) {
+                    return None;
+                }
+                let name = if version == Version::Qasm2 { "cu1" } else { "cp" };
+                format_call(name, &[phi + lambda], version)
+            } else if (theta - PI).abs() < ANGLE_TOLERANCE && is_multiple_of_two_pi(*phi) && is_multiple_of_two_pi(lambda - PI) {
+                String::from("cx")
+            } else if version == Version::Qasm2 {
+                format_call("cu3", &[*theta, *phi, *lambda], version)
+            } else {
+                format_call("cu", &[*theta, *phi, *lambda, 0.0], version)
+            };
+            Some(format!("{} {}, {};", call, qubit(control), qubit(target)))
+        }
+    }
+}
+
+// Writes angles close to a simple fraction of pi as such
+fn format_angle(angle:f64) -> String {
+    if angle.abs() < 1e-12 {
+        return String::from("0");
+    }
+
+    for denominator in 1..=16 {
+        let numerator = angle * denominator as f64 / PI;
+        if (numerator - numerator.round()).abs() < ANGLE_TOLERANCE && numerator.round().abs() <= 64.0 {
+            let numerator = numerator.round() as i64;
+            let multiple = match numerator {
+                1 => String::from("pi"),
+                -1 => String::from("-pi"),
+                _ => format!("{}*pi", numerator)
+            };
+            return if denominator == 1 { multiple } else { format!("{}/{}", multiple, denominator) };
+        }
+    }
+
+    format!("{}", angle)
+}
+
+fn is_multiple_of_two_pi(angle:f64) -> bool {
+    let turns = angle / (2.0*PI);
+    (turns - turns.round()).abs() < ANGLE_TOLERANCE
+}
+
+fn is_reserved_name(name:&str) -> bool {
+    matches!(name, "U" | "CX" | "u3" | "u2" | "u1" | "u0" | "u" | "p" | "id" | "x" | "y" | "z" | "h" | "s" | "sdg" | "t" | "tdg"
+        | "sx" | "sxdg" | "rx" | "ry" | "rz" | "cx" | "cy" | "cz" | "ch" | "csx" | "crx" | "cry" | "crz" | "cu1" | "cp" | "cu3" | "cu"
+        | "swap" | "rxx" | "ryy" | "rzz" | "ccx" | "cswap" | "c3x" | "c4x" | "c3sqrtx" | "rccx" | "rc3x" | "phase" | "cphase"
+        | "gate" | "qreg" | "creg" | "qubit" | "bit" | "measure" | "reset" | "barrier" | "if" | "pi")
+}
+
+fn get_qubit(qubit:u8) -> String {
+    format!("q[{}]", qubit)
+}
+
+fn to_complex64(operator:&[Complex32; 4]) -> [Complex64; 4] {
+    [Complex64::new(operator[0].re as f64, operator[0].im as f64), Complex64::new(operator[1].re as f64, operator[1].im as f64),
+     Complex64::new(operator[2].re as f64, operator[2].im as f64), Complex64::new(operator[3].re as f64, operator[3].im as f64)]
+}
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::panic;
+use std::time::Instant;
+use num_complex::Complex64;
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
+use super::circuit::Circuit;
+use super::circuit::Control;
+use super::circuit::Gate;
+use super::decomposition;
+use super::engine;
+use super::measurement;
+use super::qasm;
+
+const DEFAULT_SHOTS: u32 = 1024;
+
+// Runs every experiment of a QasmQobj (the dictionary of qobj.to_dict()) and returns the results in Qiskit's result schema.
+// Counts are keyed by the hexadecimal value of the memory slots, with slot 0 as the least significant bit.
+// Complex matrices of `unitary` instructions are expected as [real, imaginary] pairs.
+pub fn run(qobj_json:&str) -> Value {
+    let start = Instant::now();
+    let qobj:Value = serde_json::from_str(qobj_json).unwrap_or_else(|err| panic!("Invalid Qobj: {}", err));
+
+    let experiments = match qobj["experiments"].as_array() {
+        Some(experiments) => experiments,
+        None => panic!("The Qobj has no experiments")
+    };
+    // an experiment that cannot run is reported as failed in its result, so the others still run
+    let results:Vec<Value> = experiments.iter().map(|experiment| {
+        let start = Instant::now();
+        panic::catch_unwind(|| run_experiment(experiment, &qobj["config"])).unwrap_or_else(|error| json!({
+            "shots": get_setting(experiment, &qobj["config"], "shots").unwrap_or(DEFAULT_SHOTS as u64),
+            "success": false,
+            "status": format!("ERROR: {}", super::get_panic_message(error.as_ref())),
+            "data": {},
+            "header": experiment["header"],
+            "time_taken": start.elapsed().as_secs_f64(),
+        }))
+    }).collect();
+
+    let succeeded = results.iter().filter(|result| result["success"] == true).count();
+    let status = match succeeded {
+        succeeded if succeeded == results.len() => "COMPLETED",
+        0 => "ERROR",
+        _ => "PARTIAL COMPLETED"
+    };
+
+    json!({
+        "backend_name": "moara",
+        "backend_version": env!("CARGO_PKG_VERSION"),
+        "qobj_id": qobj["qobj_id"],
+        "job_id": qobj["qobj_id"],
+        "success": succeeded == results.len(),
+        "status": status,
+        "header": qobj["header"],
+        "results": results,
+        "time_taken": start.elapsed().as_secs_f64(),
+    })
+}
+
+fn run_experiment(experiment:&Value, qobj_config:&Value) -> Value {
+    let start = Instant::now();
+
+    let get_setting = |name:&str| get_setting(experiment, qobj_config, name);
+    let shots = get_setting("shots").map_or(DEFAULT_SHOTS, |shots| shots as u32);
+    let qubit_count = match get_setting("n_qubits") {
+        Some(count) if count <= u8::MAX as u64 => count as u8,
+        Some(count) => panic!("At most {} qubits are supported. The experiment uses {}", u8::MAX, count),
+        None => panic!("The experiment has no n_qubits")
+    };
+    let memory_slots = match get_setting("memory_slots") {
+        Some(slots) if slots <= 128 => slots as u8,
+        Some(slots) => panic!("At most 128 memory slots are supported. The experiment uses {}", slots),
+        None => panic!("The experiment has no memory_slots")
+    };
+
+    let instructions = match experiment["instructions"].as_array() {
+        Some(instructions) => instructions,
+        None => panic!("The experiment has no instructions")
+    };
+    let mut builder = Builder { registers: HashMap::new(), memory_slots, measured_slots: 0, slots: HashMap::new(), used_qubits: HashSet::new(), gates: Vec::new() };
+    for instruction in instructions {
+        builder.add_instruction(instruction);
+    }
+
+    let mut counts = Map::new();
+    if builder.measured_slots != 0 && shots > 0 && qubit_count > 0 {
+        let (statevector, _) = engine::get_final_statevector(qubit_count, Circuit::from_gates(builder.gates));
+
+        // the counts are kept by memory value, as the slots can be too many for a count of every value.
+        // Qiskit reads the memory as an integer with slot 0 as the least significant bit
+        let mut memory_counts:HashMap<u128, u32> = HashMap::new();
+        for (index, count) in measurement::sample_counts(&statevector, shots, get_setting("seed_simulator")) {
+            let memory = builder.slots.iter().filter(|(qubit, _)| index & (1 << (qubit_count - 1 - **qubit)) != 0).fold(0_u128, |memory, (_, slot)| memory | 1 << slot);
+            *memory_counts.entry(memory).or_insert(0) += count;
+        }
+        for (memory, count) in memory_counts {
+            counts.insert(format!("{:#x}", memory), json!(count));
+        }
+    }
+
+    json!({
+        "shots": shots,
+        "success": true,
+        "status": "DONE",
+        "meas_level": 2,
+        "data": { "counts": counts },
+        "header": experiment["header"],
+        "time_taken": start.elapsed().as_secs_f64(),
+    })
+}
+
+fn get_setting(experiment:&Value, qobj_config:&Value, name:&str) -> Option<u64> {
+    experiment["config"][name].as_u64().or_else(|| qobj_config[name].as_u64()).or_else(|| experiment["header"][name].as_u64())
+}
+
+struct Builder {
+    // values of the registers set by bfunc, known only when they do not read measured slots
+    registers: HashMap<u64, bool>,
+    memory_slots: u8,
+    measured_slots: u128,
+    // the memory slot of every measured qubit. The engine measures each qubit into its own bit, as slots can exceed the qubit count
+    slots: HashMap<u8, u8>,
+    used_qubits: HashSet<u8>,
+    gates: Vec<Gate>,
+}
+
+impl Builder {
+    fn add_instruction(&mut self, instruction:&Value) {
+        let name = match instruction["name"].as_str() {
+            Some(name) => name,
+            None => panic!("Instruction without a name: {}", instruction)
+        };
+        let qubits:Vec<u8> = match instruction["qubits"].as_array() {
+            Some(qubits) => qubits.iter().map(|qubit| match qubit.as_u64() {
+                Some(qubit) if qubit < u8::MAX as u64 => qubit as u8,
+                _ => panic!("Invalid qubit {} in {}", qubit, name)
+            }).collect(),
+            None => Vec::new()
+        };
+
+        // there is no classical control in moara, so conditions are evaluated while the circuit is built
+        if !self.is_condition_met(instruction) {
+            return;
+        }
+
+        match name {
+            "bfunc" => {
+                let register = instruction["register"].as_u64().unwrap_or_else(|| panic!("bfunc without a register"));
+                let value = self.evaluate_condition(instruction, instruction["relation"].as_str().unwrap_or("=="));
+                self.registers.insert(register, value);
+            },
+            "measure" => {
+                let slots = match instruction["memory"].as_array() {
+                    Some(slots) if slots.len() == qubits.len() => slots,
+                    _ => panic!("measure needs one memory slot for every qubit")
+                };
+                for (qubit, slot) in qubits.iter().zip(slots) {
+                    let slot = match slot.as_u64() {
+                        Some(slot) if slot < self.memory_slots as u64 => slot as u8,
+                        _ => panic!("Invalid memory slot {}. The experiment has {} memory slots", slot, self.memory_slots)
+                    };
+                    self.measured_slots |= 1 << slot;
+                    self.slots.insert(*qubit, slot);
+                    self.add_gate(Gate::new("measure-z", vec![*qubit], Vec::new()));
+                }
+            },
+            "reset" => {
+                if qubits.iter().any(|qubit| self.used_qubits.contains(qubit)) {
+                    panic!("reset is supported only on qubits that have not been used yet");
+                }
+            },
+            "barrier" => self.gates.push(Gate::new("barrier", qubits, Vec::new())),
+            "unitary" => {
+                let matrix = get_matrix(&instruction["params"][0]);
+                if matrix.len() != 1 << (2*qubits.len()) {
+                    panic!("The unitary on {} qubits has {} elements", qubits.len(), matrix.len());
+                }
+                // Qiskit puts the first qubit in the least significant bit of the row index
+                let targets:Vec<u8> = qubits.iter().rev().cloned().collect();
+                for gate in decomposition::get_unitary_gates(&matrix, &targets, &[]) {
+                    self.add_gate(gate);
+                }
+            },
+            "mcx" => {
+                let (target, controls) = match qubits.split_last() {
+                    Some((target, controls)) => (*target, controls),
+                    None => panic!("mcx needs at least one qubit")
+                };
+                let controls = controls.iter().map(|qubit| Control { target: *qubit, state: String::from("1") }).collect();
+                self.add_gate(Gate::new("pauli-x", vec![target], controls));
+            },
+            _ => {
+                let parameters:Vec<f64> = match instruction["params"].as_array() {
+                    Some(parameters) => parameters.iter().map(|parameter| match parameter.as_f64() {
+                        Some(parameter) => parameter,
+                        None => panic!("Invalid parameter {} of {}", parameter, name)
+                    }).collect(),
+                    None => Vec::new()
+                };
+
+                match qasm::get_qelib_signature(name) {
+                    Some((parameter_count, qubit_count)) if parameters.len() == parameter_count && qubits.len() == qubit_count => {
+                        for gate in qasm::get_qelib_gates(name, &parameters, &qubits) {
+                            self.add_gate(gate);
+                        }
+                    },
+                    Some((parameter_count, qubit_count)) => panic!("The gate {} expects {} parameters and {} qubits. Received {} and {}",
+                        name, parameter_count, qubit_count, parameters.len(), qubits.len()),
+                    None => panic!("Unknown instruction {}", name)
+                }
+            }
+        }
+    }
+
+    // "conditional" is either the register set by a bfunc or, in older Qobjs, a {mask, type, val} condition
+    fn is_condition_met(&self, instruction:&Value) -> bool {
+        let conditional = &instruction["conditional"];
+        match conditional {
+            Value::Null => true,
+            Value::Number(register) => match self.registers.get(&register.as_u64().unwrap_or(u64::MAX)) {
+                Some(value) => *value,
+                None => panic!("The condition of {} uses register {}, which is not set by a bfunc", instruction["name"], register)
+            },
+            Value::Object(_) => match conditional["type"].as_str() {
+                Some("equals") => self.evaluate_condition(conditional, "=="),
+                _ => panic!("Unknown condition {}", conditional)
+            },
+            _ => panic!("Unknown condition {}", conditional)
+        }
+    }
+
+    // Unmeasured slots hold zero, so (memory & mask) relation val is known before the simulation unless its mask covers a measured slot
+    fn evaluate_condition(&self, condition:&Value, relation:&str) -> bool {
+        let mask = parse_hex(&condition["mask"]);
+        let value = parse_hex(&condition["val"]);
+        if mask & self.measured_slots != 0 {
+            panic!("The condition {} depends on a measurement. Classically controlled gates are supported only on memory that has not been measured", condition);
+        }
+
+        match relation {
+            "==" => value == 0,
+            "!=" => value != 0,
+            "<" => value > 0,
+            "<=" => true,
+            ">" => false,
+            ">=" => value == 0,
+            unknown_relation => panic!("Unknown relation {}", unknown_relation)
+        }
+    }
+
+    fn add_gate(&mut self, gate:Gate) {
+        self.used_qubits.extend(gate.get_qubits());
+        self.gates.push(gate);
+    }
+}
+
+fn parse_hex(value:&Value) -> u128 {
+    let text = match value.as_str() {
+        Some(text) => text,
+        None => panic!("Expected a hexadecimal string. Received {}", value)
+    };
+    u128::from_str_radix(text.trim_start_matches("0x"), 16).unwrap_or_else(|_| panic!("Invalid hexadecimal value {}", text))
+}
+
+// A matrix is a list of rows whose elements are [real, imaginary] pairs or real numbers
+fn get_matrix(value:&Value) -> Vec<Complex64> {
+    let rows = match value.as_array() {
+        Some(rows) => rows,
+        None => panic!("Invalid matrix {}", value)
+    };
+
+    let mut elements = Vec::new();
+    for row in rows {
+        let row = match row.as_array() {
+            Some(row) if row.len() == rows.len() => row,
+            _ => panic!("The matrix {} is not square", value)
+        };
+        elements.extend(row.iter().map(|element| match element {
+            Value::Number(number) => Complex64::new(number.as_f64().unwrap(), 0.0),
+            Value::Array(pair) if pair.len() == 2 && pair[0].is_number() && pair[1].is_number() => Complex64::new(pair[0].as_f64().unwrap(), pair[1].as_f64().unwrap()),
+            _ => panic!("Invalid matrix element {}", element)
+        }));
+    }
+    elements
+}
@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use num_complex::Complex64;
+use super::circuit::Circuit;
+use super::circuit::Control;
+use super::circuit::Gate;
+use super::decomposition;
+use super::expression;
+use super::expression::Expression;
+
+// (name, parameter count, qubit count) of the standard Quil gates
+const STANDARD_GATES: [(&str, usize, usize); 23] = [
+    ("I", 0, 1), ("X", 0, 1), ("Y", 0, 1), ("Z", 0, 1), ("H", 0, 1), ("S", 0, 1), ("T", 0, 1),
+    ("PHASE", 1, 1), ("RX", 1, 1), ("RY", 1, 1), ("RZ", 1, 1),
+    ("CNOT", 0, 2), ("CZ", 0, 2), ("CPHASE", 1, 2), ("CPHASE00", 1, 2), ("CPHASE01", 1, 2), ("CPHASE10", 1, 2),
+    ("SWAP", 0, 2), ("ISWAP", 0, 2), ("PSWAP", 1, 2), ("XY", 1, 2),
+    ("CCNOT", 0, 3), ("CSWAP", 0, 3),
+];
+
+// instructions that need classical control or pulse level access, which moara does not have
+const UNSUPPORTED_INSTRUCTIONS: [&str; 29] = [
+    "LABEL", "JUMP", "JUMP-WHEN", "JUMP-UNLESS", "WAIT",
+    "MOVE", "EXCHANGE", "CONVERT", "LOAD", "STORE", "NEG", "NOT", "AND", "IOR", "XOR",
+    "ADD", "SUB", "MUL", "DIV", "EQ", "GT", "GE", "LT", "LE",
+    "DEFCIRCUIT", "DEFCAL", "DEFFRAME", "DEFWAVEFORM", "PULSE",
+];
+
+// Parses a Quil program. Returns the circuit and the number of qubits (one more than the largest qubit index).
+// Measurements into BIT memory are mapped to classical bits, with the BIT regions laid out in declaration order.
+// As in the OpenQASM importer, RESET is accepted only on qubits that have not been used yet.
+pub fn parse(source:&str) -> (Circuit, u8) {
+    let mut parser = Parser {
+        definitions: HashMap::new(),
+        memory: HashMap::new(),
+        qubit_count: 0,
+        bit_count: 0,
+        used_qubits: HashSet::new(),
+        gates: Vec::new(),
+    };
+
+    let lines:Vec<&str> = source.lines().map(|line| match line.find('#') {
+        Some(position) => &line[..position],
+        None => line
+    }).collect();
+
+    let mut index = 0;
+    while index < lines.len() {
+        let line = index + 1;
+        let text = lines[index].trim();
+        index += 1;
+
+        if text.starts_with("DEFGATE") {
+            // the rows are the following lines indented more than the definition
+            let indentation = get_indentation(lines[index-1]);
+            let mut body = Vec::new();
+            while index < lines.len() && get_indentation(lines[index]) > indentation && !lines[index].trim().is_empty() {
+                body.push(lines[index].trim());
+                index += 1;
+            }
+            parser.parse_gate_definition(text, &body, line);
+            continue;
+        }
+
+        if text == "HALT" {
+            break;
+        }
+
+        for instruction in text.split(';').map(|instruction| instruction.trim()).filter(|instruction| !instruction.is_empty()) {
+            parser.parse_instruction(instruction, line);
+        }
+    }
+
+    (Circuit::from_gates(parser.gates), parser.qubit_count as u8)
+}
+
+// Gate defined with DEFGATE. The matrix elements refer to the parameters by name.
+struct Definition {
+    parameters: Vec<String>,
+    qubit_count: usize,
+    matrix: Vec<Expression>,
+}
+
+struct Memory {
+    offset: usize,
+    size: usize,
+    is_bit: bool,
+}
+
+struct Parser {
+    definitions: HashMap<String, Definition>,
+    memory: HashMap<String, Memory>,
+    qubit_count: usize,
+    bit_count: usize,
+    used_qubits: HashSet<u8>,
+    gates: Vec<Gate>,
+}
+
+impl Parser {
+    fn parse_instruction(&mut self, text:&str, line:usize) {
+        let (word, rest) = split_word(text);
+
+        match word {
+            "PRAGMA" | "NOP" => {},
+            "DECLARE" => {
+                let words:Vec<&str> = rest.split_whitespace().collect();
+                if words.len() < 2 {
+                    panic!("Expected a name and a type after DECLARE at line {}", line);
+                }
+                let name = words[0].to_string();
+                let (memory_type, size) = parse_memory_reference(words[1], line);
+                if self.memory.contains_key(&name) {
+                    panic!("The memory region {} is declared twice at line {}", name, line);
+                }
+
+                let is_bit = memory_type == "BIT";
+                self.memory.insert(name, Memory { offset: self.bit_count, size: size.unwrap_or(1), is_bit });
+                if is_bit {
+                    self.bit_count += size.unwrap_or(1);
+                }
+            },
+            "MEASURE" => {
+                let words:Vec<&str> = rest.split_whitespace().collect();
+                if words.is_empty() || words.len() > 2 {
+                    panic!("Expected a qubit and an optional memory reference after MEASURE at line {}", line);
+                }
+                let qubit = self.parse_qubit(words[0], line);
+
+                // without a memory reference the outcome is discarded, so nothing is recorded
+                if words.len() == 2 {
+                    let bit = self.resolve_bit(words[1], line);
+                    let mut gate = Gate::new("measure-z", vec![qubit], Vec::new());
+                    gate.bit = Some(bit as u8);
+                    self.add_gate(gate);
+                }
+            },
+            "RESET" => {
+                let qubits:Vec<u8> = rest.split_whitespace().map(|word| self.parse_qubit(word, line)).collect();
+                let is_used = if qubits.is_empty() { !self.used_qubits.is_empty() } else { qubits.iter().any(|qubit| self.used_qubits.contains(qubit)) };
+                if is_used {
+                    panic!("RESET at line {} is supported only on qubits that have not been used yet", line);
+                }
+            },
+            _ if word.starts_with('@') || UNSUPPORTED_INSTRUCTIONS.contains(&word) => {
+                panic!("{} at line {} is not supported. Only gates, DECLARE, MEASURE and RESET can be simulated", word, line);
+            },
+            _ => self.parse_gate(text, line)
+        }
+    }
+
+    // DEFGATE name[(%parameter, ...)] [AS MATRIX | AS PERMUTATION]: followed by the indented rows
+    fn parse_gate_definition(&mut self, header:&str, body:&[&str], line:usize) {
+        let header = match header.strip_suffix(':') {
+            Some(header) => header[7..].trim(),
+            None => panic!("Expected ':' at the end of DEFGATE at line {}", line)
+        };
+
+        let (header, kind) = match header.find(" AS ") {
+            Some(position) => (header[..position].trim(), header[position+4..].trim()),
+            None => (header, "MATRIX")
+        };
+        let (name, rest) = split_word(header);
+        let (parameters, rest) = split_parameters(rest, line);
+        if !rest.trim().is_empty() {
+            panic!("Unexpected '{}' in the definition of gate {} at line {}", rest.trim(), name, line);
+        }
+        let parameters:Vec<String> = parameters.iter().map(|parameter| parameter.trim().trim_start_matches('%').to_string()).collect();
+
+        let rows:Vec<Vec<String>> = body.iter().map(|row| split_top_level(row, ',')).collect();
+        let matrix = match kind {
+            "MATRIX" => {
+                if rows.iter().any(|row| row.len() != rows.len()) {
+                    panic!("The matrix of gate {} defined at line {} is not square", name, line);
+                }
+                rows.iter().flatten().map(|element| expression::parse(element)).collect()
+            },
+            "PERMUTATION" => {
+                if rows.len() != 1 || !parameters.is_empty() {
+                    panic!("The permutation of gate {} defined at line {} must be a single row without parameters", name, line);
+                }
+                let permutation:Vec<usize> = rows[0].iter().map(|element| match element.trim().parse::<usize>() {
+                    Ok(value) if value < rows[0].len() => value,
+                    _ => panic!("Invalid element {} in the permutation of gate {} at line {}", element.trim(), name, line)
+                }).collect();
+
+                // |j> goes to |permutation[j]>
+                let size = permutation.len();
+                let mut matrix = vec![Expression::Number(0.0); size*size];
+                for (column, row) in permutation.iter().enumerate() {
+                    matrix[row*size + column] = Expression::Number(1.0);
+                }
+                matrix
+            },
+            unknown_kind => panic!("DEFGATE AS {} at line {} is not supported. Use MATRIX or PERMUTATION", unknown_kind, line)
+        };
+
+        let size = (matrix.len() as f64).sqrt() as usize;
+        if size < 2 || !size.is_power_of_two() {
+            panic!("The matrix of gate {} defined at line {} must have a power of two rows", name, line);
+        }
+        let qubit_count = size.trailing_zeros() as usize;
+
+        self.definitions.insert(name.to_string(), Definition { parameters, qubit_count, matrix });
+    }
+
+    // [CONTROLLED | DAGGER]* name[(parameters)] qubits. Every CONTROLLED takes the next qubit from the left as a control.
+    fn parse_gate(&mut self, text:&str, line:usize) {
+        let mut control_count = 0;
+        let mut dagger = false;
+        let mut rest = text;
+        let name = loop {
+            let (word, remaining) = split_word(rest);
+            rest = remaining;
+            match word {
+                "CONTROLLED" => control_count += 1,
+                "DAGGER" => dagger = !dagger,
+                "FORKED" => panic!("FORKED at line {} is not supported", line),
+                "" => panic!("Expected a gate name at line {}", line),
+                name => break name
+            }
+        };
+
+        let (parameters, rest) = split_parameters(rest, line);
+        if parameters.iter().any(|parameter| parameter.contains('[')) {
+            panic!("Gate parameters read from memory are not supported. Found one at line {}", line);
+        }
+        let parameters:Vec<f64> = parameters.iter().map(|parameter| expression::parse(parameter).evaluate(&HashMap::new())).collect();
+
+        let qubits:Vec<u8> = rest.split_whitespace().map(|word| self.parse_qubit(word, line)).collect();
+        let distinct:HashSet<&u8> = qubits.iter().collect();
+        if distinct.len() != qubits.len() {
+            panic!("The gate {} at line {} is applied more than once on the same qubit", name, line);
+        }
+
+        let (parameter_count, qubit_count) = match self.definitions.get(name) {
+            Some(definition) => (definition.parameters.len(), definition.qubit_count),
+            None => match STANDARD_GATES.iter().find(|(gate_name, _, _)| *gate_name == name) {
+                Some((_, parameter_count, qubit_count)) => (*parameter_count, *qubit_count),
+                None => panic!("Unknown gate {} at line {}", name, line)
+            }
+        };
+        if parameters.len() != parameter_count || qubits.len() != qubit_count + control_count {
+            panic!("The gate {} at line {} expects {} parameters and {} qubits. Received {} and {}",
+                name, line, parameter_count, qubit_count + control_count, parameters.len(), qubits.len());
+        }
+
+        let controls:Vec<Control> = qubits[..control_count].iter().map(|qubit| Control { target: *qubit, state: String::from("1") }).collect();
+        let targets = &qubits[control_count..];
+
+        let gates = match self.definitions.get(name) {
+            Some(definition) => {
+                let variables:HashMap<String, f64> = definition.parameters.iter().cloned().zip(parameters.iter().cloned()).collect();
+                let matrix:Vec<Complex64> = definition.matrix.iter().map(|element| element.evaluate_complex(&variables)).collect();
+                decomposition::get_unitary_gates(&get_dagger_if(matrix, dagger), targets, &controls)
+            },
+            None => match get_standard_gates(name, &parameters, targets, dagger) {
+                Some(mut gates) => {
+                    for gate in &mut gates {
+                        gate.controls.extend(controls.iter().cloned());
+                    }
+                    gates
+                },
+                None => decomposition::get_unitary_gates(&get_dagger_if(get_standard_matrix(name, &parameters), dagger), targets, &controls)
+            }
+        };
+
+        for gate in gates {
+            self.add_gate(gate);
+        }
+    }
+
+    fn add_gate(&mut self, gate:Gate) {
+        self.used_qubits.extend(gate.get_qubits());
+        self.gates.push(gate);
+    }
+
+    fn parse_qubit(&mut self, text:&str, line:usize) -> u8 {
+        let qubit = match text.parse::<u8>() {
+            Ok(qubit) if qubit < u8::MAX => qubit,
+            _ => panic!("Expected a qubit index smaller than {} at line {}. Received '{}'", u8::MAX, line, text)
+        };
+        self.qubit_count = self.qubit_count.max(qubit as usize + 1);
+        qubit
+    }
+
+    fn resolve_bit(&self, text:&str, line:usize) -> usize {
+        let (name, index) = parse_memory_reference(text, line);
+        let index = index.unwrap_or(0);
+        match self.memory.get(name) {
+            Some(memory) if !memory.is_bit => panic!("Measurements can be stored only in BIT memory. {} at line {} is not BIT", name, line),
+            Some(memory) if index < memory.size => memory.offset + index,
+            Some(_) => panic!("Index {} is out of the bounds of memory region {} at line {}", index, name, line),
+            None => panic!("Unknown memory region {} at line {}", name, line)
+        }
+    }
+}
+
+// Maps a standard Quil gate to moara gates. Returns None for the gates that are applied through their matrix.
+fn get_standard_gates(name:&str, parameters:&[f64], qubits:&[u8], dagger:bool) -> Option<Vec<Gate>> {
+    let on_one = |qubit:u8| Control { target: qubit, state: String::from("1") };
+    let sign = if dagger { -1.0 } else { 1.0 };
+    let with_theta = |mut gate:Gate, theta:f64| { gate.theta = Some(theta as f32); gate };
+
+    let single = |moara_name:&str| Gate::new(moara_name, vec![qubits[0]], Vec::new());
+    let controlled = |moara_name:&str| Gate::new(moara_name, vec![qubits[1]], vec![on_one(qubits[0])]);
+    let double = |moara_name:&str| Gate::new(moara_name, vec![qubits[0], qubits[1]], Vec::new());
+
+    let gate = match name {
+        "I" => single("identity"),
+        "X" => single("pauli-x"),
+        "Y" => single("pauli-y"),
+        "Z" => single("pauli-z"),
+        "H" => single("hadamard"),
+        "S" => single(if dagger { "s-dagger" } else { "s" }),
+        "T" => single(if dagger { "t-dagger" } else { "t" }),
+        "PHASE" => with_theta(single("p"), sign*parameters[0]),
+        "RX" => with_theta(single("rx-theta"), sign*parameters[0]),
+        "RY" => with_theta(single("ry-theta"), sign*parameters[0]),
+        "RZ" => with_theta(single("rz-theta"), sign*parameters[0]),
+        "CNOT" => controlled("pauli-x"),
+        "CZ" => controlled("pauli-z"),
+        "CPHASE" => with_theta(controlled("p"), sign*parameters[0]),
+        "SWAP" => double("swap"),
+        "ISWAP" if !dagger => double("iswap"),
+        "PSWAP" => with_theta(double("swap-theta"), sign*parameters[0]),
+        // XY(theta) has cos(theta/2) and i sin(theta/2) in the middle block, moara xy has cos and -i sin of its angle
+        "XY" => with_theta(double("xy"), -sign*parameters[0]/2.0),
+        "CCNOT" => Gate::new("pauli-x", vec![qubits[2]], vec![on_one(qubits[0]), on_one(qubits[1])]),
+        "CSWAP" => Gate::new("swap", vec![qubits[1], qubits[2]], vec![on_one(qubits[0])]),
+        _ => return None
+    };
+    Some(vec![gate])
+}
+
+// Matrices of the standard gates without a moara equivalent. The first qubit is the most significant bit of the row index.
+fn get_standard_matrix(name:&str, parameters:&[f64]) -> Vec<Complex64> {
+    let mut matrix = vec![Complex64::new(0.0, 0.0); 16];
+    for index in 0..4 {
+        matrix[index*5] = Complex64::new(1.0, 0.0);
+    }
+
+    match name {
+        "CPHASE00" => matrix[0] = Complex64::from_polar(1.0, parameters[0]),
+        "CPHASE01" => matrix[5] = Complex64::from_polar(1.0, parameters[0]),
+        "CPHASE10" => matrix[10] = Complex64::from_polar(1.0, parameters[0]),
+        "ISWAP" => {
+            matrix[5] = Complex64::new(0.0, 0.0);
+            matrix[10] = Complex64::new(0.0, 0.0);
+            matrix[6] = Complex64::new(0.0, 1.0);
+            matrix[9] = Complex64::new(0.0, 1.0);
+        },
+        unknown_gate => panic!("Unknown gate {}", unknown_gate)
+    }
+    matrix
+}
+
+fn get_dagger_if(matrix:Vec<Complex64>, dagger:bool) -> Vec<Complex64> {
+    if !dagger {
+        return matrix;
+    }
+    let size = (matrix.len() as f64).sqrt() as usize;
+    (0..matrix.len()).map(|index| matrix[(index % size)*size + index/size].conj()).collect()
+}
+
+fn get_indentation(line:&str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+// Splits off the first word, which ends at whitespace or at '('
+fn split_word(text:&str) -> (&str, &str) {
+    let text = text.trim_start();
+    let end = text.find(|c:char| c.is_whitespace() || c == '(').unwrap_or(text.len());
+    (&text[..end], &text[end..])
+}
+
+// Splits off the parenthesized parameter list, if there is one
+fn split_parameters(text:&str, line:usize) -> (Vec<String>, &str) {
+    if !text.starts_with('(') {
+        return (Vec::new(), text);
+    }
+
+    let mut depth = 0;
+    for (position, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return (split_top_level(&text[1..position], ','), &text[position+1..]);
+                }
+            },
+            _ => {}
+        }
+    }
+    panic!("Unbalanced parentheses at line {}", line);
+}
+
+fn split_top_level(text:&str, separator:char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut part = String::new();
+    for c in text.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if c == separator && depth == 0 => {
+                parts.push(part.trim().to_string());
+                part.clear();
+                continue;
+            },
+            _ => {}
+        }
+        part.push(c);
+    }
+    if !part.trim().is_empty() {
+        parts.push(part.trim().to_string());
+    }
+    parts
+}
+
+// name or name[index]
+fn parse_memory_reference(text:&str, line:usize) -> (&str, Option<usize>) {
+    match text.find('[') {
+        Some(position) if text.ends_with(']') => match text[position+1..text.len()-1].trim().parse::<usize>() {
+            Ok(index) => (&text[..position], Some(index)),
+            Err(_) => panic!("Invalid index in {} at line {}", text, line)
+        },
+        Some(_) => panic!("Expected ']' in {} at line {}", text, line),
+        None => (text, None)
+    }
+}
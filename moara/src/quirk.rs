@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use serde_json::Value;
+use super::circuit::Circuit;
+use super::circuit::Control;
+use super::circuit::Gate;
+use super::circuit::Step;
+use super::expression;
+
+// Parses a Quirk circuit, given either as its JSON ({"cols":[...]}) or as a Quirk URL (...#circuit=...). Returns the circuit and the number of qubits.
+// The top wire is qubit 0. Every column becomes a step, or several steps when gates of the column share controls.
+// Measurements get classical bits in order of columns, then wires. Display widgets are ignored.
+pub fn parse(source:&str) -> (Circuit, u8) {
+    let source = source.trim();
+    let json = match source.find("circuit=") {
+        Some(position) if !source.starts_with('{') => decode_url(&source[position + "circuit=".len()..]),
+        _ => source.to_string()
+    };
+    let circuit:Value = serde_json::from_str(&json).unwrap_or_else(|err| panic!("Invalid Quirk JSON: {}", err));
+
+    let columns = match circuit["cols"].as_array() {
+        Some(columns) => columns,
+        None => panic!("The Quirk circuit has no cols")
+    };
+    let initial_states = match circuit["init"].as_array() {
+        Some(initial_states) => initial_states.to_vec(),
+        None => Vec::new()
+    };
+
+    let wire_count = columns.iter().map(|column| match column.as_array() {
+        Some(entries) => entries.iter().rposition(|entry| entry != 1).map_or(0, |position| position + 1),
+        None => panic!("Every column needs to be a list of gates")
+    }).chain([initial_states.len()]).max().unwrap_or(0);
+    if wire_count > u8::MAX as usize {
+        panic!("At most {} qubits are supported. The circuit uses {}", u8::MAX, wire_count);
+    }
+
+    let mut parser = Parser { steps: Circuit::from_gates(get_initial_gates(&initial_states)).steps, bit_count: 0, unsupported: Vec::new() };
+    for column in columns {
+        parser.add_column(column.as_array().unwrap());
+    }
+    if !parser.unsupported.is_empty() {
+        panic!("The circuit uses Quirk gates that are not supported: {}", parser.unsupported.join(", "));
+    }
+
+    (Circuit { definitions: Vec::new(), steps: parser.steps }, wire_count as u8)
+}
+
+// |0> becomes 1, +, -, i or -i with X, H and S gates
+fn get_initial_gates(initial_states:&[Value]) -> Vec<Gate> {
+    let mut gates = Vec::new();
+    for (wire, state) in initial_states.iter().enumerate() {
+        let names:&[&str] = match state {
+            Value::Number(number) if number.as_u64() == Some(0) => &[],
+            Value::Number(number) if number.as_u64() == Some(1) => &["pauli-x"],
+            Value::String(state) => match state.as_str() {
+                "+" => &["hadamard"],
+                "-" => &["pauli-x", "hadamard"],
+                "i" => &["hadamard", "s"],
+                "-i" => &["hadamard", "s-dagger"],
+                _ => panic!("Unknown initial state {} of qubit {}", state, wire)
+            },
+            _ => panic!("Unknown initial state {} of qubit {}", state, wire)
+        };
+        gates.extend(names.iter().map(|name| Gate::new(name, vec![wire as u8], Vec::new())));
+    }
+    gates
+}
+
+struct Parser {
+    steps: Vec<Step>,
+    bit_count: u8,
+    unsupported: Vec<String>,
+}
+
+impl Parser {
+    fn add_column(&mut self, entries:&[Value]) {
+        let mut controls = Vec::new();
+        let mut swaps = Vec::new();
+        let mut gates = Vec::new();
+
+        for (wire, entry) in entries.iter().enumerate() {
+            let wire = wire as u8;
+            let (id, argument) = match entry {
+                Value::Number(number) if number.as_u64() == Some(1) => continue,
+                Value::String(id) => (id.as_str(), None),
+                Value::Object(gate) => match gate.get("id").and_then(|id| id.as_str()) {
+                    Some(id) => (id, gate.get("arg").and_then(|argument| argument.as_str())),
+                    None => panic!("Gate without an id: {}", entry)
+                },
+                _ => panic!("Unknown Quirk entry {}", entry)
+            };
+
+            if let Some(state) = get_control_state(id) {
+                controls.push(Control { target: wire, state: String::from(state) });
+                continue;
+            }
+
+            match id {
+                "…" => {},
+                "Swap" => swaps.push(wire),
+                "Measure" => {
+                    let mut gate = Gate::new("measure-z", vec![wire], Vec::new());
+                    gate.bit = Some(self.bit_count);
+                    self.bit_count += 1;
+                    gates.push(gate);
+                },
+                _ if is_display(id) => {},
+                _ => match get_gate(id, argument, wire) {
+                    Some(gate) => gates.push(gate),
+                    None => {
+                        let name = match argument {
+                            Some(argument) => format!("{}({})", id, argument),
+                            None => id.to_string()
+                        };
+                        if !self.unsupported.contains(&name) {
+                            self.unsupported.push(name);
+                        }
+                    }
+                }
+            }
+        }
+
+        match swaps.len() {
+            0 => {},
+            2 => gates.push(Gate::new("swap", swaps, Vec::new())),
+            count => panic!("A column needs exactly two Swap gates. Found {}", count)
+        }
+        if !controls.is_empty() && gates.iter().any(|gate| gate.name == "measure-z") {
+            panic!("Measurements cannot be controlled");
+        }
+
+        // gates sharing the controls of the column cannot be in the same step, so each starts a new one
+        let mut is_new_step = true;
+        for mut gate in gates {
+            if is_new_step {
+                self.steps.push(Step { index: self.steps.len() as u16, gates: Vec::new() });
+            }
+            gate.controls.extend(controls.iter().cloned());
+            self.steps.last_mut().unwrap().gates.push(gate);
+            is_new_step = !controls.is_empty();
+        }
+    }
+}
+
+// Control, anti-control and the X and Y axis controls. Anti-controls condition on the +1 eigenstate of their axis
+fn get_control_state(id:&str) -> Option<&'static str> {
+    match id {
+        "•" => Some("1"),
+        "◦" => Some("0"),
+        "⊕" => Some("+"),
+        "⊖" => Some("-"),
+        "(/)" => Some("+i"),
+        "⊗" => Some("-i"),
+        _ => None
+    }
+}
+
+// Widgets that show the state without changing it
+fn is_display(id:&str) -> bool {
+    id == "Bloch" || ["Amps", "Chance", "Density", "Sample"].iter().any(|prefix| id.starts_with(prefix))
+}
+
+// Maps the fixed Pauli gates, their quarter, eighth and smaller turns, and formula gates whose argument does not depend on time
+fn get_gate(id:&str, argument:Option<&str>, wire:u8) -> Option<Gate> {
+    let with_root = |name:&str, root:String| {
+        let mut gate = Gate::new(name, vec![wire], Vec::new());
+        gate.root = Some(root);
+        gate
+    };
+
+    let names:HashMap<&str, (&str, &str)> = HashMap::from([("X", ("pauli-x", "pauli-x-root")), ("Y", ("pauli-y", "pauli-y-root")), ("Z", ("pauli-z", "pauli-z-root"))]);
+    if id == "H" {
+        return Some(Gate::new("hadamard", vec![wire], Vec::new()));
+    }
+    if let Some((pauli, power)) = id.split_once('^') {
+        let (_, root_name) = names.get(pauli)?;
+        if power == "ft" {
+            // P^f is the root 1/f of P, so f = 0 is the infinite root, the identity
+            let exponent = evaluate_argument(argument?)?;
+            return Some(with_root(root_name, (1.0/exponent).to_string()));
+        }
+
+        let (is_dagger, fraction) = match power.strip_prefix('-') {
+            Some(fraction) => (true, fraction),
+            None => (false, power)
+        };
+        let exponent = match fraction {
+            "½" => 1,
+            "¼" => 2,
+            "⅛" => 3,
+            "⅟₁₆" => 4,
+            "⅟₃₂" => 5,
+            "⅟₆₄" => 6,
+            "⅟₁₂₈" => 7,
+            _ => return None
+        };
+        let root = if exponent == 1 { String::from("2") } else { format!("2^{}", exponent) };
+        return Some(with_root(&(if is_dagger { format!("{}-dagger", root_name) } else { root_name.to_string() }), root));
+    }
+    if let Some((name, _)) = names.get(id) {
+        return Some(Gate::new(name, vec![wire], Vec::new()));
+    }
+
+    let name = match id {
+        "Rxft" => "rx-theta",
+        "Ryft" => "ry-theta",
+        "Rzft" => "rz-theta",
+        _ => return None
+    };
+    let mut gate = Gate::new(name, vec![wire], Vec::new());
+    gate.theta = Some(evaluate_argument(argument?)? as f32);
+    Some(gate)
+}
+
+// None when the formula depends on the time t, which is animated by Quirk
+fn evaluate_argument(argument:&str) -> Option<f64> {
+    let argument = argument.replace('π', "pi");
+    let uses_time = argument.split(|c:char| !c.is_alphanumeric() && c != '_').any(|word| word == "t");
+    if uses_time {
+        return None;
+    }
+    Some(expression::parse(&argument).evaluate(&HashMap::new()))
+}
+
+// Decodes the %XX escapes of a URL fragment
+fn decode_url(text:&str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::new();
+    let mut position = 0;
+    while position < bytes.len() {
+        let escaped = if bytes[position] == b'%' { text.get(position + 1..position + 3) } else { None };
+        match escaped.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) => {
+                decoded.push(byte);
+                position += 3;
+            },
+            None => {
+                decoded.push(bytes[position]);
+                position += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| panic!("The Quirk URL is not valid UTF-8"))
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::fs;
+use std::panic;
+use std::path::Path;
+use num_complex::Complex32;
+use super::circuit::Circuit;
+use super::circuit::Control;
+use super::circuit::Gate;
+use super::circuit::Step;
+use super::definitions;
+use super::engine;
+use super::expression;
+use super::measurement;
+use super::qasm;
+
+// The statevector grows with the qubits used, up to this many qubits
+pub const MAX_QUBIT_COUNT: u8 = 24;
+
+pub const HELP: &str = "Gates are applied as soon as they are typed:
+  h 0, cx 0 1, rz 2 0.3, ccx 0 1 2     OpenQASM qelib1.inc gates: qubits first, then the angles
+  ry-theta 1 theta=pi/4                moara gates, with theta=, phi=, lambda=, root=, controls=0,1 and bit=
+  measure 0 [bit]                      measures the qubit in the Z basis into the bit, the qubit index by default
+Commands:
+  probs          probabilities of the measured bits, or of all the qubits before any measurement
+  amps           nonzero amplitudes
+  sample N       counts of N samples
+  undo           removes the last gate
+  reset          removes all the gates
+  circuit        prints the circuit as JSON
+  save FILE      writes the circuit as JSON
+  load FILE      replaces the circuit with a JSON or OpenQASM circuit
+  help, quit";
+
+// An interactive session: a circuit built gate by gate and the statevector after its last gate
+pub struct Session {
+    gates:Vec<Gate>,
+    qubit_count:u8,
+    statevector:Vec<Complex32>,
+    measurements:HashMap<u8,u8>,
+}
+
+impl Session {
+    pub fn new(qubit_count:u8) -> Session {
+        if qubit_count > MAX_QUBIT_COUNT {
+            panic!("The session is limited to {} qubits. Received {} qubits", MAX_QUBIT_COUNT, qubit_count);
+        }
+        let mut session = Session { gates: Vec::new(), qubit_count: 0, statevector: vec![C!(1)], measurements: HashMap::new() };
+        session.grow(qubit_count);
+        session
+    }
+
+    pub fn get_qubit_count(&self) -> u8 {
+        self.qubit_count
+    }
+
+    pub fn get_statevector(&self) -> &[Complex32] {
+        &self.statevector
+    }
+
+    // Runs a gate or a command and returns its output. The errors of the gate or of the command leave the session as it was
+    pub fn execute(&mut self, line:&str) -> Result<String, String> {
+        let words:Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            return Ok(String::new());
+        }
+
+        // a failing line can have grown the session or applied some of the gates of a qelib1.inc gate
+        let gates = self.gates.clone();
+        let qubit_count = self.qubit_count;
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| self.run(&words)));
+        result.map_err(|error| {
+            self.gates = gates;
+            self.qubit_count = qubit_count;
+            self.replay();
+            super::get_panic_message(error.as_ref())
+        })
+    }
+
+    fn run(&mut self, words:&[&str]) -> String {
+        match words {
+            ["help"] => String::from(HELP),
+            ["probs"] => {
+                let probabilities = measurement::get_probabilities(&self.statevector, self.measurements.clone(), self.qubit_count);
+                format_results(&probabilities, |probability| *probability > 1e-7, |probability| format!("{:.6}", probability))
+            },
+            ["amps"] => format_results(&self.statevector, |amplitude| amplitude.norm_sqr() > 1e-14, |amplitude| format!("{:.6}{:+.6}i", amplitude.re, amplitude.im)),
+            ["sample", shots] => {
+                let shots:u32 = shots.parse().unwrap_or_else(|_| panic!("Invalid number of shots {}", shots));
+                let counts = measurement::measure(self.statevector.to_vec(), shots, self.measurements.clone(), self.qubit_count, None);
+                format_results(&counts, |count| *count > 0, |count| count.to_string())
+            },
+            ["undo"] => {
+                if self.gates.pop().is_none() {
+                    panic!("There is no gate to undo");
+                }
+                self.replay();
+                String::new()
+            },
+            ["reset"] => {
+                self.gates.clear();
+                self.replay();
+                String::new()
+            },
+            ["circuit"] => self.get_serialized_circuit(),
+            ["save", filename] => {
+                fs::write(filename, self.get_serialized_circuit()).unwrap_or_else(|err| panic!("Cannot write {}: {}", filename, err));
+                format!("Saved {} gates to {}", self.gates.len(), filename)
+            },
+            ["load", filename] => {
+                // the gates go to a new session, so a circuit that fails to load leaves the current one
+                let (gates, qubit_count) = load(filename);
+                let mut session = Session::new(qubit_count);
+                for gate in gates {
+                    session.apply(gate);
+                }
+                *self = session;
+                format!("Loaded {} gates on {} qubits", self.gates.len(), self.qubit_count)
+            },
+            [name, arguments @ ..] => {
+                for gate in parse_gate(name, arguments) {
+                    self.apply(gate);
+                }
+                String::new()
+            },
+            [] => String::new()
+        }
+    }
+
+    fn apply(&mut self, gate:Gate) {
+        let qubits = gate.get_qubits();
+        if let Some(max_qubit) = qubits.iter().max() {
+            if *max_qubit >= MAX_QUBIT_COUNT {
+                panic!("The session is limited to {} qubits. Received the qubit {}", MAX_QUBIT_COUNT, max_qubit);
+            }
+            self.grow(max_qubit + 1);
+        }
+
+        let step = Step { index: self.gates.len() as u16, gates: vec![gate.clone()] };
+        engine::apply_step(&mut self.statevector, &mut self.measurements, step, self.qubit_count);
+        self.gates.push(gate);
+    }
+
+    // New qubits start in |0⟩ and are added after the others, so they are the least significant bits of the statevector index
+    fn grow(&mut self, qubit_count:u8) {
+        if qubit_count <= self.qubit_count {
+            return;
+        }
+        let shift = qubit_count - self.qubit_count;
+        let mut statevector = vec![C!(0); 1 << qubit_count];
+        for (index, amplitude) in self.statevector.iter().enumerate() {
+            statevector[index << shift] = *amplitude;
+        }
+        self.statevector = statevector;
+        self.qubit_count = qubit_count;
+    }
+
+    // Runs the gates again from |0…0⟩, keeping the number of qubits
+    fn replay(&mut self) {
+        self.statevector = vec![C!(0); 1 << self.qubit_count];
+        self.statevector[0] = C!(1);
+        self.measurements.clear();
+
+        let steps = self.gates.iter().enumerate().map(|(index, gate)| Step { index: index as u16, gates: vec![gate.clone()] }).collect();
+        let circuit = Circuit { definitions: Vec::new(), steps };
+        engine::run_steps(&mut self.statevector, &mut self.measurements, self.qubit_count, circuit, None, &mut |_, _, _| {});
+    }
+
+    fn get_serialized_circuit(&self) -> String {
+        serde_json::to_string_pretty(&Circuit::from_gates(self.gates.clone())).unwrap()
+    }
+}
+
+// An OpenQASM qelib1.inc gate, with its qubits and then its angles, or a moara gate with its targets and then its named parameters
+fn parse_gate(name:&str, arguments:&[&str]) -> Vec<Gate> {
+    if let Some((parameter_count, qubit_count)) = qasm::get_qelib_signature(name) {
+        if arguments.len() != qubit_count + parameter_count {
+            panic!("{} takes {} qubits and {} angles. Received {} arguments", name, qubit_count, parameter_count, arguments.len());
+        }
+        let qubits:Vec<u8> = arguments[..qubit_count].iter().map(|qubit| parse_qubit(qubit)).collect();
+        let parameters:Vec<f64> = arguments[qubit_count..].iter().map(|parameter| parse_angle(parameter) as f64).collect();
+        return qasm::get_qelib_gates(name, &parameters, &qubits);
+    }
+
+    let name = if name == "measure" { "measure-z" } else { name };
+    let mut gate = Gate::new(name, Vec::new(), Vec::new());
+    for argument in arguments {
+        match argument.split_once('=') {
+            None if gate.name.starts_with("measure-") && gate.targets.len() == 1 => gate.bit = Some(parse_qubit(argument)),
+            None => gate.targets.push(parse_qubit(argument)),
+            Some(("theta", value)) => gate.theta = Some(parse_angle(value)),
+            Some(("phi", value)) => gate.phi = Some(parse_angle(value)),
+            Some(("lambda", value)) => gate.lambda = Some(parse_angle(value)),
+            Some(("root", value)) => gate.root = Some(value.to_string()),
+            Some(("bit", value)) => gate.bit = Some(parse_qubit(value)),
+            Some(("controls", value)) => gate.controls = value.split(',').map(|qubit| Control { target: parse_qubit(qubit), state: String::from("1") }).collect(),
+            Some((parameter, _)) => panic!("Unknown parameter {}. Use theta, phi, lambda, root, controls or bit", parameter)
+        }
+    }
+    if gate.targets.is_empty() {
+        panic!("The gate {} has no qubits", name);
+    }
+    vec![gate]
+}
+
+fn parse_qubit(text:&str) -> u8 {
+    text.parse().unwrap_or_else(|_| panic!("Invalid qubit {}", text))
+}
+
+fn parse_angle(text:&str) -> f32 {
+    expression::parse(text).evaluate(&HashMap::new()) as f32
+}
+
+// The gates of a JSON or OpenQASM circuit, in the order of their steps, and the number of qubits declared by OpenQASM circuits
+fn load(filename:&str) -> (Vec<Gate>, u8) {
+    let contents = fs::read_to_string(filename).unwrap_or_else(|err| panic!("Cannot read {}: {}", filename, err));
+    let (circuit, qubit_count) = if Path::new(filename).extension().is_some_and(|extension| extension == "qasm") {
+        qasm::parse(&contents)
+    } else {
+        let circuit:Circuit = serde_json::from_str(&contents).unwrap_or_else(|err| panic!("Invalid circuit {}: {}", filename, err));
+        (definitions::expand(circuit), 1)
+    };
+
+    let mut steps = circuit.steps;
+    steps.sort_by_key(|step| step.index);
+    (steps.into_iter().flat_map(|step| step.gates).collect(), qubit_count)
+}
+
+// One line per result, keyed by the bitstring of its index
+fn format_results<T>(results:&[T], filter:impl Fn(&T) -> bool, format:impl Fn(&T) -> String) -> String {
+    let bit_count = results.len().trailing_zeros() as usize;
+    let lines:Vec<String> = results.iter().enumerate()
+        .filter(|(_, value)| filter(value))
+        .map(|(index, value)| format!("{:0width$b}: {}", index, format(value), width = bit_count))
+        .collect();
+    lines.join("\n")
+}
@@ -1,9 +1,28 @@
+use std::collections::HashMap;
 use num_complex::Complex32;
 use super::circuit::Circuit;
+use super::definitions;
+use super::density;
+use super::entanglement;
+use super::entanglement::Entanglement;
 use super::engine;
+use super::initial_state::InitialState;
+use super::checkpoint::Checkpoint;
+use super::checkpoint::Checkpointer;
+use super::stepper;
+use super::stepper::Snapshot;
+use super::stepper::Stepper;
 use super::measurement;
+use super::stabilizer;
+use super::mps;
+use super::mps::MatrixProductState;
+use super::sparse;
+use super::analysis;
+use super::analysis::CircuitAnalysis;
+use super::qasm_export;
+use super::diagram;
 
-pub fn simulate(serialized_circuit:String, shots:u32, endianess:Option<String>, qubit_count:Option<u8>) -> Vec<u32> {
+pub fn simulate(serialized_circuit:String, shots:u32, endianess:Option<String>, qubit_count:Option<u8>, seed:Option<u64>, initial_state:Option<InitialState>) -> Vec<u32> {
     let (circuit, count) = deserialize(serialized_circuit, qubit_count);
 
     if count == 0 {
@@ -14,70 +33,397 @@ pub fn simulate(serialized_circuit:String, shots:u32, endianess:Option<String>,
         return vec![0; 1<<count];
     }
 
-    let (final_statevector, measurements) = engine::get_final_statevector(count, circuit);
+    let initial_statevector = get_initial_statevector(initial_state, &endianess, count);
+    let (final_statevector, measurements) = engine::get_final_statevector_from(initial_statevector, count, circuit);
 
-    let endianess:String = match endianess {
-      Some(endianess) => endianess,
-      None => String::from("bigendian"),
-    };
+    sample_final_state(final_statevector, measurements, count, shots, endianess, seed)
+}
+
+pub fn simulate_stabilizer(serialized_circuit:String, shots:u32, endianess:Option<String>, qubit_count:Option<u8>, seed:Option<u64>) -> HashMap<String, u32> {
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    if count == 0 || shots == 0 {
+        return HashMap::new();
+    }
 
-    if endianess == String::from("bigendian") {
-      measurement::measure(final_statevector, shots, measurements, count)
-    } else if endianess == String::from("littleendian") {
-      let reordered_state_vector = reorder_state_vector(final_statevector, count);
-      measurement::measure(reordered_state_vector, shots, measurements, count)
+    let (results, measurements) = stabilizer::simulate(count as usize, circuit, shots, seed);
+    let counts = stabilizer::get_bitstring_counts(results, &measurements);
+
+    if is_little_endian(endianess) {
+      reverse_bitstrings(counts)
     } else {
-      panic!("endianess can be either: 'bigendian' or 'littleendian'")
+      counts
     }
 }
 
-pub fn get_statevector(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>) -> Vec<Complex32> {
+pub fn simulate_sparse(serialized_circuit:String, shots:u32, endianess:Option<String>, qubit_count:Option<u8>, seed:Option<u64>) -> HashMap<String, u32> {
+    let little_endian = is_little_endian(endianess);
     let (circuit, count) = deserialize(serialized_circuit, qubit_count);
 
-    if count == 0 {
-        return vec![];
+    if count == 0 || shots == 0 {
+        return HashMap::new();
     }
 
-    let (final_statevector, _) = engine::get_final_statevector(count, circuit);
+    let (statevector, measurements) = sparse::get_final_statevector(count, circuit);
+    let counts = sparse::measure(&statevector, shots, &measurements, count, seed);
 
-    let endianess:String = match endianess {
-      Some(endianess) => endianess,
-      None => String::from("bigendian"),
-    };
+    if little_endian {
+      reverse_bitstrings(counts)
+    } else {
+      counts
+    }
+}
+
+pub fn get_sparse_probabilities(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>) -> HashMap<String, f32> {
+    let little_endian = is_little_endian(endianess);
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    if count == 0 {
+        return HashMap::new();
+    }
+
+    let (statevector, measurements) = sparse::get_final_statevector(count, circuit);
+    let probabilities = sparse::get_probabilities(&statevector, &measurements, count);
 
-    if endianess == String::from("bigendian") {
-      final_statevector
-    } else if endianess == String::from("littleendian") {
-      reorder_state_vector(final_statevector, count)
+    if little_endian {
+      reverse_bitstrings(probabilities)
     } else {
-      panic!("endianess can be either: 'bigendian' or 'littleendian'")
-    } 
+      probabilities
+    }
+}
+
+pub fn simulate_mps(serialized_circuit:String, shots:u32, endianess:Option<String>, qubit_count:Option<u8>, max_bond_dimension:Option<usize>, cutoff:Option<f64>, seed:Option<u64>) -> (HashMap<String, u32>, f64) {
+    let little_endian = is_little_endian(endianess);
+    let (mut state, measurements) = get_matrix_product_state(serialized_circuit, qubit_count, max_bond_dimension, cutoff);
+
+    let mut counts = HashMap::new();
+    if state.qubit_count() == 0 {
+      return (counts, 0.0);
+    }
+
+    let mut rng = measurement::get_rng(seed);
+    for _ in 0..shots {
+      let outcomes = state.sample(&mut rng);
+      *counts.entry(measurement::get_bitstring(&outcomes, &measurements)).or_insert(0) += 1;
+    }
+
+    if little_endian {
+      counts = reverse_bitstrings(counts);
+    }
+
+    (counts, state.truncation_error())
+}
+
+pub fn get_mps_bitstring_probabilities(serialized_circuit:String, bitstrings:Vec<String>, endianess:Option<String>, qubit_count:Option<u8>, max_bond_dimension:Option<usize>, cutoff:Option<f64>) -> Vec<f32> {
+    let little_endian = is_little_endian(endianess);
+    let (state, _) = get_matrix_product_state(serialized_circuit, qubit_count, max_bond_dimension, cutoff);
+
+    bitstrings.iter().map(|bitstring| {
+      let mut bits:Vec<bool> = bitstring.chars().map(|c| c == '1').collect();
+      if bits.len() != state.qubit_count() {
+        panic!("The bitstring {} must have one bit for each of the {} qubits", bitstring, state.qubit_count());
+      }
+      if little_endian {
+        bits.reverse();
+      }
+      state.probability(&bits)
+    }).collect()
+}
+
+pub fn get_matrix_product_state(serialized_circuit:String, qubit_count:Option<u8>, max_bond_dimension:Option<usize>, cutoff:Option<f64>) -> (MatrixProductState, HashMap<u8,u8>) {
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    let max_bond_dimension = max_bond_dimension.unwrap_or(mps::DEFAULT_MAX_BOND_DIMENSION);
+    let cutoff = cutoff.unwrap_or(mps::DEFAULT_CUTOFF);
+
+    mps::get_final_state(count as usize, circuit, max_bond_dimension, cutoff)
+}
+
+pub fn get_statevector(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<Complex32> {
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    if count == 0 {
+        return vec![];
+    }
+
+    let initial_statevector = get_initial_statevector(initial_state, &endianess, count);
+    let (final_statevector, _) = engine::get_final_statevector_from(initial_statevector, count, circuit);
+
+    order_final_statevector(final_statevector, count, endianess)
 }
 
-pub fn get_probabilities(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>) -> Vec<f32> {
+pub fn get_probabilities(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<f32> {
     let (circuit, count) = deserialize(serialized_circuit, qubit_count);
     
     if count == 0 {
         return vec![];
     }
 
-    let (statevector, measurements) = engine::get_final_statevector(count, circuit);
+    let initial_statevector = get_initial_statevector(initial_state, &endianess, count);
+    let (statevector, measurements) = engine::get_final_statevector_from(initial_statevector, count, circuit);
 
-    let endianess:String = match endianess {
-      Some(endianess) => endianess,
-      None => String::from("bigendian"),
-    };
+    get_final_state_probabilities(statevector, measurements, count, endianess)
+}
+
+// The amplitudes of the given bitstrings only, which are read in the order of the endianess
+pub fn get_amplitudes(serialized_circuit:String, bitstrings:Vec<String>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<Complex32> {
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    let initial_statevector = get_initial_statevector(initial_state, &endianess, count);
+    let (statevector, _) = engine::get_final_statevector_from(initial_statevector, count, circuit);
+
+    let little_endian = is_little_endian(endianess);
+    bitstrings.iter().map(|bitstring| statevector[get_bitstring_index(bitstring, count, little_endian)]).collect()
+}
+
+pub fn get_bitstring_probabilities(serialized_circuit:String, bitstrings:Vec<String>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<f32> {
+    get_amplitudes(serialized_circuit, bitstrings, endianess, qubit_count, initial_state).iter().map(|amplitude| amplitude.norm_sqr()).collect()
+}
+
+// The marginal probabilities of the given qubits. With little endian the first of the qubits is the least significant bit of the index
+pub fn get_marginal_probabilities(serialized_circuit:String, qubits:Vec<u8>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<f32> {
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    let initial_statevector = get_initial_statevector(initial_state, &endianess, count);
+    let (statevector, _) = engine::get_final_statevector_from(initial_statevector, count, circuit);
+
+    get_final_state_marginal_probabilities(statevector, count, qubits, endianess)
+}
+
+// The density matrix of the given qubits with the other qubits traced out. With little endian the first of the qubits is the least significant bit of the matrix indices
+pub fn get_reduced_density_matrix(serialized_circuit:String, qubits:Vec<u8>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<Complex32> {
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    let initial_statevector = get_initial_statevector(initial_state, &endianess, count);
+    let (statevector, _) = engine::get_final_statevector_from(initial_statevector, count, circuit);
+
+    let mut qubits = qubits;
+    if is_little_endian(endianess) {
+        qubits.reverse();
+    }
+    density::get_reduced_density_matrix(&statevector, count, &qubits)
+}
+
+// The Schmidt coefficients and the entropies of the final statevector across the bipartition into the given qubits and the others
+pub fn get_entanglement(serialized_circuit:String, qubits:Vec<u8>, renyi_order:f64, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Entanglement {
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    let initial_statevector = get_initial_statevector(initial_state, &None, count);
+    let (statevector, _) = engine::get_final_statevector_from(initial_statevector, count, circuit);
+
+    entanglement::get_entanglement(&statevector, count, &qubits, renyi_order)
+}
+
+// The entanglement across the bipartition after each step, keyed by the step index
+pub fn get_entanglement_profile(serialized_circuit:String, qubits:Vec<u8>, renyi_order:f64, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<(u16, Entanglement)> {
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    let mut statevector = get_initial_statevector(initial_state, &None, count);
+    let mut measurements = HashMap::new();
+    let mut profile = Vec::new();
+    engine::run_steps(&mut statevector, &mut measurements, count, circuit, None, &mut |statevector, _, step_index| {
+        profile.push((step_index, entanglement::get_entanglement(statevector, count, &qubits, renyi_order)));
+    });
+
+    profile
+}
+
+// A stepper over the circuit, whose statevectors and probabilities are in the order of the endianess
+pub fn get_stepper(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Stepper {
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    let initial_statevector = get_initial_statevector(initial_state, &endianess, count);
+    Stepper::new(initial_statevector, count, circuit, endianess)
+}
 
-    if endianess == String::from("bigendian") {
-      measurement::get_probabilities(statevector, measurements, count)
-    } else if endianess == String::from("littleendian") {
-      let reordered_state_vector = reorder_state_vector(statevector, count);
-      measurement::get_probabilities(reordered_state_vector, measurements, count)
+// The statevector after each step, with the index of the step
+pub fn get_step_statevectors(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<(u16, Vec<Complex32>)> {
+    let mut stepper = get_stepper(serialized_circuit, endianess, qubit_count, initial_state);
+
+    let mut statevectors = Vec::new();
+    while let Some(step_index) = stepper.step() {
+        statevectors.push((step_index, stepper.get_statevector()));
+    }
+    statevectors
+}
+
+// The probabilities of the measured bits after each step, with the index of the step
+pub fn get_step_probabilities(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> Vec<(u16, Vec<f32>)> {
+    let mut stepper = get_stepper(serialized_circuit, endianess, qubit_count, initial_state);
+
+    let mut probabilities = Vec::new();
+    while let Some(step_index) = stepper.step() {
+        probabilities.push((step_index, stepper.get_probabilities()));
+    }
+    probabilities
+}
+
+pub fn get_statevector_with_snapshots(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> (Vec<Complex32>, Vec<Snapshot>) {
+    get_stepper(serialized_circuit, endianess, qubit_count, initial_state).finish()
+}
+
+pub fn get_probabilities_with_snapshots(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>) -> (Vec<f32>, Vec<Snapshot>) {
+    let mut stepper = get_stepper(serialized_circuit, endianess, qubit_count, initial_state);
+    while stepper.step().is_some() {}
+
+    let probabilities = stepper.get_probabilities();
+    (probabilities, stepper.get_snapshots().to_vec())
+}
+
+// The final statevector in the order of the engine, with the measurements, the qubit count and the snapshots taken by the circuit
+pub fn get_final_state(serialized_circuit:String, endianess:&Option<String>, qubit_count:Option<u8>, initial_state:Option<InitialState>, checkpointer:Option<&mut Checkpointer>) -> (Vec<Complex32>, HashMap<u8,u8>, u8, Vec<Snapshot>) {
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    let mut statevector = get_initial_statevector(initial_state, endianess, count);
+    let mut measurements = HashMap::new();
+    let snapshots = run_steps(&mut statevector, &mut measurements, count, circuit, None, endianess, checkpointer);
+
+    (statevector, measurements, count, snapshots)
+}
+
+// Continues the run from the step after the checkpoint. Only the snapshots of the following steps are returned
+pub fn resume(checkpoint:Checkpoint, endianess:&Option<String>, checkpointer:Option<&mut Checkpointer>) -> (Vec<Complex32>, HashMap<u8,u8>, u8, Vec<Snapshot>) {
+    let Checkpoint { qubit_count, step_index, mut measurements, circuit, mut statevector, .. } = checkpoint;
+    let (circuit, count) = deserialize(circuit, Some(qubit_count));
+
+    let snapshots = run_steps(&mut statevector, &mut measurements, count, circuit, Some(step_index), endianess, checkpointer);
+
+    (statevector, measurements, count, snapshots)
+}
+
+// The statevector and the measurements at the end of the circuit, and the qubit count, for the runs that write checkpoints.
+// The functions below turn them into the results of simulate, get_probabilities and get_statevector.
+pub fn sample_final_state(statevector:Vec<Complex32>, measurements:HashMap<u8,u8>, qubit_count:u8, shots:u32, endianess:Option<String>, seed:Option<u64>) -> Vec<u32> {
+    if qubit_count == 0 {
+        return vec![];
+    }
+
+    if shots == 0 {
+        return vec![0; 1<<qubit_count];
+    }
+
+    if is_little_endian(endianess) {
+      measurement::measure(reorder_state_vector(statevector, qubit_count), shots, measurements, qubit_count, seed)
+    } else {
+      measurement::measure(statevector, shots, measurements, qubit_count, seed)
+    }
+}
+
+pub fn get_final_state_probabilities(statevector:Vec<Complex32>, measurements:HashMap<u8,u8>, qubit_count:u8, endianess:Option<String>) -> Vec<f32> {
+    if qubit_count == 0 {
+        return vec![];
+    }
+
+    if is_little_endian(endianess) {
+      measurement::get_probabilities(&reorder_state_vector(statevector, qubit_count), measurements, qubit_count)
+    } else {
+      measurement::get_probabilities(&statevector, measurements, qubit_count)
+    }
+}
+
+pub fn get_final_state_marginal_probabilities(statevector:Vec<Complex32>, qubit_count:u8, mut qubits:Vec<u8>, endianess:Option<String>) -> Vec<f32> {
+    if is_little_endian(endianess) {
+        qubits.reverse();
+    }
+    measurement::get_marginal_probabilities(&statevector, qubit_count, &qubits)
+}
+
+pub fn order_final_statevector(statevector:Vec<Complex32>, qubit_count:u8, endianess:Option<String>) -> Vec<Complex32> {
+    if qubit_count == 0 {
+        return vec![];
+    }
+
+    if is_little_endian(endianess) {
+      reorder_state_vector(statevector, qubit_count)
     } else {
-      panic!("endianess can be either: 'bigendian' or 'littleendian'")
+      statevector
     }
 }
 
+fn run_steps(statevector:&mut Vec<Complex32>, measurements:&mut HashMap<u8,u8>, qubit_count:u8, circuit:Circuit, after_index:Option<u16>,
+             endianess:&Option<String>, mut checkpointer:Option<&mut Checkpointer>) -> Vec<Snapshot> {
+    if let Some(checkpointer) = checkpointer.as_mut() {
+        checkpointer.set_circuit(serde_json::to_string(&circuit).unwrap());
+    }
+    let mut snapshot_labels = stepper::get_snapshot_labels(&circuit);
+    let mut snapshots = Vec::new();
+
+    engine::run_steps(statevector, measurements, qubit_count, circuit, after_index, &mut |statevector, measurements, index| {
+        for label in snapshot_labels.remove(&index).unwrap_or_default() {
+            snapshots.push(Snapshot::new(label, index, statevector, measurements, qubit_count, endianess));
+        }
+        if let Some(checkpointer) = checkpointer.as_mut() {
+            checkpointer.after_step(statevector, measurements, qubit_count, index);
+        }
+    });
+
+    snapshots
+}
+
+// The initial state is given in the order of the chosen endianess, so for little endian its statevector is reordered like the results
+fn get_initial_statevector(initial_state:Option<InitialState>, endianess:&Option<String>, qubit_count:u8) -> Vec<Complex32> {
+    match initial_state {
+        Some(initial_state) if is_little_endian(endianess.clone()) => reorder_state_vector(initial_state.get_statevector(qubit_count), qubit_count),
+        Some(initial_state) => initial_state.get_statevector(qubit_count),
+        None => {
+            let mut statevector = vec![C!(0); 1<<qubit_count];
+            statevector[0] = C!(1);
+            statevector
+        }
+    }
+}
+
+pub fn get_qubit_count(serialized_circuit:String, qubit_count:Option<u8>) -> u8 {
+    deserialize(serialized_circuit, qubit_count).1
+}
+
+pub fn analyze(serialized_circuit:String, qubit_count:Option<u8>, shots:u32) -> CircuitAnalysis {
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    analysis::analyze(&circuit, count, shots)
+}
+
+pub fn export_qasm(serialized_circuit:String, qubit_count:Option<u8>, version:String) -> String {
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    qasm_export::export(circuit, count, &version)
+}
+
+pub fn draw(serialized_circuit:String, qubit_count:Option<u8>, format:String, width:Option<usize>) -> String {
+    let (circuit, count) = deserialize(serialized_circuit, qubit_count);
+
+    diagram::draw(&circuit, count, &format, width)
+}
+
+fn is_little_endian(endianess:Option<String>) -> bool {
+    match endianess.as_deref() {
+      None | Some("bigendian") => false,
+      Some("littleendian") => true,
+      Some(_) => panic!("endianess can be either: 'bigendian' or 'littleendian'")
+    }
+}
+
+// The statevector index of the bitstring, whose first bit is qubit 0 with big endian and the last qubit with little endian
+fn get_bitstring_index(bitstring:&str, qubit_count:u8, little_endian:bool) -> usize {
+    if bitstring.len() != qubit_count as usize {
+        panic!("The bitstring {} must have one bit for each of the {} qubits", bitstring, qubit_count);
+    }
+    if !bitstring.chars().all(|c| c == '0' || c == '1') {
+        panic!("Invalid bitstring {}. Use only 0 and 1", bitstring);
+    }
+
+    let index = usize::from_str_radix(bitstring, 2).unwrap();
+    if little_endian {
+        get_reversed_qbits_state(qubit_count, index)
+    } else {
+        index
+    }
+}
+
+fn reverse_bitstrings<T>(results:HashMap<String, T>) -> HashMap<String, T> {
+    results.into_iter().map(|(bitstring, value)| (bitstring.chars().rev().collect(), value)).collect()
+}
+
 fn reorder_state_vector(mut statevector:Vec<Complex32>, qubit_count:u8) -> Vec<Complex32> {
   let length = statevector.len();
   for i in 0..length {
@@ -113,7 +459,7 @@ fn get_reversed_qbits_state(mut qbits: u8, mut state: usize) -> usize
 }
 
 fn deserialize(serialized_circuit:String, qubit_count:Option<u8>) -> (Circuit, u8) {
-    let circuit: Circuit = serde_json::from_str(&serialized_circuit).unwrap();
+    let circuit: Circuit = definitions::expand(serde_json::from_str(&serialized_circuit).unwrap());
 
     let count = match qubit_count {
         Some(working_qubit_count) => working_qubit_count,
@@ -0,0 +1,229 @@
+use std::cmp::max;
+use std::cmp::min;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use num_complex::Complex32;
+use rand::Rng;
+use super::circuit::Circuit;
+use super::circuit::Gate;
+use super::circuit::Control;
+use super::engine;
+use super::gate_mapper;
+use super::measurement;
+
+const MEASUREMENT_X: &str = "measure-x";
+const MEASUREMENT_Y: &str = "measure-y";
+const MEASUREMENT_Z: &str = "measure-z";
+
+pub const MAX_QUBIT_COUNT: u8 = 64;
+
+// amplitudes with a smaller squared norm are dropped from the state
+const PRUNING_THRESHOLD: f32 = 1e-14;
+
+// Statevector that only stores the nonzero amplitudes, keyed by the index of the basis state
+pub type SparseStatevector = HashMap<usize, Complex32>;
+
+pub fn get_final_statevector(qubit_count:u8, circuit:Circuit) -> (SparseStatevector, HashMap<u8,u8>) {
+    if qubit_count > MAX_QUBIT_COUNT {
+        panic!("The sparse method supports at most {} qubits. Received {}", MAX_QUBIT_COUNT, qubit_count);
+    }
+
+    let mut measurements = HashMap::new();
+
+    let mut ordered_steps = circuit.steps;
+    ordered_steps.sort_by_key(|step| step.index);
+
+    let mut statevector = SparseStatevector::new();
+    statevector.insert(0, C!(1));
+
+    for step in ordered_steps {
+
+        let mut afected_qubits = HashSet::new();
+
+        for gate in step.gates {
+
+            if matches!(gate.name.as_str(), "barrier" | "snapshot") {
+                continue;
+            }
+
+            for target in &gate.targets {
+                if measurements.contains_key(target) {
+                    panic!("The qubit {} has been measured. Cannot add gates at step {} after measurement", target, step.index);
+                }
+
+                if !afected_qubits.insert(*target) {
+                    panic!("The qubit {} is mentioned twice in step {}", target, step.index);
+                }
+            }
+
+            for control in &gate.controls {
+                if !afected_qubits.insert(control.target) {
+                    panic!("The qubit {} is mentioned twice in step {}", control.target, step.index);
+                }
+            }
+
+            if gate.targets.is_empty() && gate.gates.is_empty() {
+                panic!("No targets and no aggregated gates provided for gate {} at step {}", gate.name, step.index);
+            }
+
+            if gate.name != "qft" && gate.name != "qft-dagger" && gate.targets.len() > 2 {
+                panic!("Too many targets for gate {} at step {}", gate.name, step.index);
+            }
+
+            if gate.name == "aggregate" {
+                for aggregated_gate in &gate.gates {
+                    let regular_gate = Gate {
+                        name: aggregated_gate.name.clone(),
+                        targets: aggregated_gate.targets.to_vec(),
+                        controls: gate.controls.to_vec(),
+                        phi: aggregated_gate.phi,
+                        theta: aggregated_gate.theta,
+                        lambda: aggregated_gate.lambda,
+                        root: aggregated_gate.root.clone(),
+                        bit: None,
+                        gates: Vec::new(),
+                        dagger: false,
+                        arguments: HashMap::new(),
+                        label: None
+                    };
+                    let single_qubit_operator = gate_mapper::get_single_qubit_operator(&regular_gate);
+                    apply_operator(&single_qubit_operator, &mut statevector, &regular_gate.targets, &regular_gate.controls, qubit_count);
+                }
+            } else if gate.name == "qft" {
+                engine::apply_qft_gate(&mut |operator, target, controls| apply_operator(&operator, &mut statevector, &[target], &controls, qubit_count), gate.targets.to_vec(), gate.controls.to_vec());
+            } else if gate.name == "qft-dagger" {
+                engine::apply_qft_dagger_gate(&mut |operator, target, controls| apply_operator(&operator, &mut statevector, &[target], &controls, qubit_count), gate.targets.to_vec(), gate.controls.to_vec());
+            } else if gate.targets.len() == 2 {
+                let double_target_operator = gate_mapper::get_double_target_operator(&gate);
+                apply_operator(&double_target_operator, &mut statevector, &gate.targets, &gate.controls, qubit_count);
+            } else {
+                let target = gate.targets[0];
+                if gate.name == MEASUREMENT_X || gate.name == MEASUREMENT_Y || gate.name == MEASUREMENT_Z {
+                    let bit = match gate.bit { Some(bit) => bit, None => target };
+                    if bit >= qubit_count {
+                        panic!("Measurement bit cannot be larger than the qubit count - 1 ({}). Received {} for qubit {}", qubit_count-1, bit, target);
+                    }
+                    measurements.insert(target, bit);
+                    if gate.name == MEASUREMENT_Z {
+                        continue;
+                    }
+                }
+
+                let single_qubit_operator = gate_mapper::get_single_qubit_operator(&gate);
+                apply_operator(&single_qubit_operator, &mut statevector, &gate.targets, &gate.controls, qubit_count);
+            }
+        }
+    }
+
+    (statevector, measurements)
+}
+
+pub fn measure(statevector:&SparseStatevector, shots:u32, measurements:&HashMap<u8,u8>, qubit_count:u8, seed:Option<u64>) -> HashMap<String, u32> {
+    let mut indexes:Vec<&usize> = statevector.keys().collect();
+    indexes.sort_unstable();
+
+    let mut cumulative_probabilities = Vec::with_capacity(indexes.len());
+    let mut running_sum = 0f64;
+    for index in &indexes {
+        running_sum += statevector[*index].norm_sqr() as f64;
+        cumulative_probabilities.push(running_sum);
+    }
+
+    let mut rng = measurement::get_rng(seed);
+    let mut samples = HashMap::new();
+    for _ in 0..shots {
+        let sample = rng.gen::<f64>() * running_sum;
+        let position = cumulative_probabilities.partition_point(|probability| *probability <= sample);
+        *samples.entry(*indexes[min(position, indexes.len() - 1)]).or_insert(0) += 1;
+    }
+
+    let mut results = HashMap::new();
+    for (index, count) in samples {
+        *results.entry(get_bitstring(index, measurements, qubit_count)).or_insert(0) += count;
+    }
+    results
+}
+
+pub fn get_probabilities(statevector:&SparseStatevector, measurements:&HashMap<u8,u8>, qubit_count:u8) -> HashMap<String, f32> {
+    let mut probabilities = HashMap::new();
+    for (index, amplitude) in statevector {
+        *probabilities.entry(get_bitstring(*index, measurements, qubit_count)).or_insert(0f32) += amplitude.norm_sqr();
+    }
+    probabilities
+}
+
+fn get_bitstring(index:usize, measurements:&HashMap<u8,u8>, qubit_count:u8) -> String {
+    let outcomes:Vec<bool> = (0..qubit_count).map(|qubit| index & (1 << (qubit_count - qubit - 1)) != 0).collect();
+    measurement::get_bitstring(&outcomes, measurements)
+}
+
+// Applies a single qubit (2x2) or double target (4x4) operator. The amplitudes are grouped by the
+// index bits that the operator and the controls in X/Y basis states do not touch, and each group is updated
+// with the same projector formula as the dense engine.
+fn apply_operator(operator:&[Complex32], statevector: &mut SparseStatevector, targets:&[u8], controls:&[Control], qubit_count:u8) {
+    let layout = engine::get_control_layout(targets, controls, qubit_count);
+
+    let target_offsets:Vec<usize> = if targets.len() == 1 {
+        vec![0, 1 << (qubit_count - targets[0] - 1)]
+    } else {
+        // the first qubit of the operator is the one with the highest index
+        let first_bit = 1 << (qubit_count - max(targets[0], targets[1]) - 1);
+        let last_bit = 1 << (qubit_count - min(targets[0], targets[1]) - 1);
+        vec![0, last_bit, first_bit, first_bit | last_bit]
+    };
+    let dimension = target_offsets.len();
+
+    let fixed_mask = layout.fixed_bits.iter().fold(0, |mask, bit| mask | (1 << bit));
+    let group_mask = target_offsets[dimension-1] | layout.superposed.iter().fold(0, |mask, (offset, _)| mask | offset);
+    let control_mask = fixed_mask & !group_mask;
+
+    let groups:HashSet<usize> = statevector.keys()
+        .filter(|index| *index & control_mask == layout.set_bits)
+        .map(|index| index & !group_mask)
+        .collect();
+
+    let superposed = layout.superposed.len() > 1;
+    let mut amplitudes = vec![C!(0); layout.superposed.len()*dimension];
+    let mut projected = vec![C!(0); dimension];
+
+    for base in groups {
+        for (c, (offset, _)) in layout.superposed.iter().enumerate() {
+            for (t, target_offset) in target_offsets.iter().enumerate() {
+                amplitudes[c*dimension + t] = *statevector.get(&(base | offset | target_offset)).unwrap_or(&C!(0));
+            }
+        }
+
+        if superposed {
+            // project the controls on their states: U_c = I + P⊗(U - I)
+            for t in 0..dimension {
+                projected[t] = layout.superposed.iter().enumerate()
+                    .fold(C!(0), |sum, (c, (_, amplitude))| sum + amplitude.conj()*amplitudes[c*dimension + t]);
+            }
+            let difference:Vec<Complex32> = (0..dimension)
+                .map(|row| (0..dimension).fold(C!(0), |sum, column| sum + operator[row*dimension + column]*projected[column]) - projected[row])
+                .collect();
+            for (c, (_, amplitude)) in layout.superposed.iter().enumerate() {
+                for t in 0..dimension {
+                    amplitudes[c*dimension + t] += amplitude*difference[t];
+                }
+            }
+        } else {
+            projected.copy_from_slice(&amplitudes[..dimension]);
+            for row in 0..dimension {
+                amplitudes[row] = (0..dimension).fold(C!(0), |sum, column| sum + operator[row*dimension + column]*projected[column]);
+            }
+        }
+
+        for (c, (offset, _)) in layout.superposed.iter().enumerate() {
+            for (t, target_offset) in target_offsets.iter().enumerate() {
+                let index = base | offset | target_offset;
+                let amplitude = amplitudes[c*dimension + t];
+                if amplitude.norm_sqr() > PRUNING_THRESHOLD {
+                    statevector.insert(index, amplitude);
+                } else {
+                    statevector.remove(&index);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use rand::Rng;
+use rand::rngs::StdRng;
+use super::circuit::Circuit;
+use super::circuit::Gate;
+use super::measurement;
+
+const MEASUREMENT_X: &str = "measure-x";
+const MEASUREMENT_Y: &str = "measure-y";
+const MEASUREMENT_Z: &str = "measure-z";
+
+// Stabilizer tableau as described by Aaronson and Gottesman (https://arxiv.org/abs/quant-ph/0406196).
+// Rows 0..n are the destabilizers, rows n..2n the stabilizers and row 2n is scratch space.
+// The x and z bits of every row are packed in u64 words.
+#[derive(Clone)]
+pub struct Tableau {
+    qubit_count: usize,
+    x: Vec<Vec<u64>>,
+    z: Vec<Vec<u64>>,
+    r: Vec<bool>,
+}
+
+impl Tableau {
+    pub fn new(qubit_count:usize) -> Tableau {
+        let words = qubit_count.div_ceil(64);
+        let mut x = vec![vec![0u64; words]; 2*qubit_count + 1];
+        let mut z = vec![vec![0u64; words]; 2*qubit_count + 1];
+
+        for i in 0..qubit_count {
+            x[i][i/64] |= 1 << (i%64);
+            z[i + qubit_count][i/64] |= 1 << (i%64);
+        }
+
+        Tableau { qubit_count, x, z, r: vec![false; 2*qubit_count + 1] }
+    }
+
+    pub fn hadamard(&mut self, qubit:usize) {
+        let (word, mask) = (qubit/64, 1u64 << (qubit%64));
+        for i in 0..2*self.qubit_count {
+            let x = self.x[i][word] & mask;
+            let z = self.z[i][word] & mask;
+            if x != 0 && z != 0 {
+                self.r[i] = !self.r[i];
+            }
+            self.x[i][word] = (self.x[i][word] & !mask) | z;
+            self.z[i][word] = (self.z[i][word] & !mask) | x;
+        }
+    }
+
+    pub fn s(&mut self, qubit:usize) {
+        let (word, mask) = (qubit/64, 1u64 << (qubit%64));
+        for i in 0..2*self.qubit_count {
+            let x = self.x[i][word] & mask;
+            let z = self.z[i][word] & mask;
+            if x != 0 && z != 0 {
+                self.r[i] = !self.r[i];
+            }
+            self.z[i][word] ^= x;
+        }
+    }
+
+    pub fn s_dagger(&mut self, qubit:usize) {
+        let (word, mask) = (qubit/64, 1u64 << (qubit%64));
+        for i in 0..2*self.qubit_count {
+            let x = self.x[i][word] & mask;
+            let z = self.z[i][word] & mask;
+            if x != 0 && z == 0 {
+                self.r[i] = !self.r[i];
+            }
+            self.z[i][word] ^= x;
+        }
+    }
+
+    pub fn pauli_x(&mut self, qubit:usize) {
+        let (word, mask) = (qubit/64, 1u64 << (qubit%64));
+        for i in 0..2*self.qubit_count {
+            if self.z[i][word] & mask != 0 {
+                self.r[i] = !self.r[i];
+            }
+        }
+    }
+
+    pub fn pauli_y(&mut self, qubit:usize) {
+        let (word, mask) = (qubit/64, 1u64 << (qubit%64));
+        for i in 0..2*self.qubit_count {
+            if (self.x[i][word] ^ self.z[i][word]) & mask != 0 {
+                self.r[i] = !self.r[i];
+            }
+        }
+    }
+
+    pub fn pauli_z(&mut self, qubit:usize) {
+        let (word, mask) = (qubit/64, 1u64 << (qubit%64));
+        for i in 0..2*self.qubit_count {
+            if self.x[i][word] & mask != 0 {
+                self.r[i] = !self.r[i];
+            }
+        }
+    }
+
+    pub fn cnot(&mut self, control:usize, target:usize) {
+        let (control_word, control_mask) = (control/64, 1u64 << (control%64));
+        let (target_word, target_mask) = (target/64, 1u64 << (target%64));
+        for i in 0..2*self.qubit_count {
+            let xc = self.x[i][control_word] & control_mask != 0;
+            let zc = self.z[i][control_word] & control_mask != 0;
+            let xt = self.x[i][target_word] & target_mask != 0;
+            let zt = self.z[i][target_word] & target_mask != 0;
+
+            if xc && zt && (xt == zc) {
+                self.r[i] = !self.r[i];
+            }
+            if xc {
+                self.x[i][target_word] ^= target_mask;
+            }
+            if zt {
+                self.z[i][control_word] ^= control_mask;
+            }
+        }
+    }
+
+    pub fn cz(&mut self, control:usize, target:usize) {
+        self.hadamard(target);
+        self.cnot(control, target);
+        self.hadamard(target);
+    }
+
+    pub fn cy(&mut self, control:usize, target:usize) {
+        self.s_dagger(target);
+        self.cnot(control, target);
+        self.s(target);
+    }
+
+    pub fn measure(&mut self, qubit:usize, rng:&mut StdRng) -> bool {
+        let n = self.qubit_count;
+        let (word, mask) = (qubit/64, 1u64 << (qubit%64));
+
+        let random_row = (n..2*n).find(|&p| self.x[p][word] & mask != 0);
+
+        match random_row {
+            Some(p) => {
+                for i in 0..2*n {
+                    if i != p && self.x[i][word] & mask != 0 {
+                        self.rowsum(i, p);
+                    }
+                }
+
+                self.x[p-n] = self.x[p].clone();
+                self.z[p-n] = self.z[p].clone();
+                self.r[p-n] = self.r[p];
+
+                for w in 0..self.x[p].len() {
+                    self.x[p][w] = 0;
+                    self.z[p][w] = 0;
+                }
+                self.z[p][word] = mask;
+
+                let outcome = rng.gen::<bool>();
+                self.r[p] = outcome;
+                outcome
+            },
+            None => {
+                let scratch = 2*n;
+                for w in 0..self.x[scratch].len() {
+                    self.x[scratch][w] = 0;
+                    self.z[scratch][w] = 0;
+                }
+                self.r[scratch] = false;
+
+                for i in 0..n {
+                    if self.x[i][word] & mask != 0 {
+                        self.rowsum(scratch, i + n);
+                    }
+                }
+                self.r[scratch]
+            }
+        }
+    }
+
+    // Multiplies row h by row i, keeping track of the phase of the product
+    fn rowsum(&mut self, h:usize, i:usize) {
+        let mut phase:i64 = 2 * (self.r[h] as i64) + 2 * (self.r[i] as i64);
+
+        for w in 0..self.x[h].len() {
+            let x1 = self.x[i][w];
+            let z1 = self.z[i][w];
+            let x2 = self.x[h][w];
+            let z2 = self.z[h][w];
+
+            let y1 = x1 & z1;
+            let only_x1 = x1 & !z1;
+            let only_z1 = !x1 & z1;
+
+            let plus = (y1 & z2 & !x2) | (only_x1 & z2 & x2) | (only_z1 & x2 & !z2);
+            let minus = (y1 & x2 & !z2) | (only_x1 & z2 & !x2) | (only_z1 & x2 & z2);
+
+            phase += plus.count_ones() as i64 - minus.count_ones() as i64;
+
+            self.x[h][w] = x2 ^ x1;
+            self.z[h][w] = z2 ^ z1;
+        }
+
+        self.r[h] = phase.rem_euclid(4) == 2;
+    }
+}
+
+pub fn simulate(qubit_count:usize, circuit:Circuit, shots:u32, seed:Option<u64>) -> (HashMap<Vec<bool>, u32>, HashMap<u8,u8>) {
+    let (tableau, measurements) = get_final_tableau(qubit_count, circuit);
+
+    let mut measured_qubits:Vec<usize> = measurements.keys().map(|qubit| *qubit as usize).collect();
+    if measured_qubits.is_empty() {
+        measured_qubits = (0..qubit_count).collect();
+    }
+    measured_qubits.sort_unstable();
+
+    let mut rng = measurement::get_rng(seed);
+    let mut results = HashMap::new();
+    for _ in 0..shots {
+        let mut shot_tableau = tableau.clone();
+        let mut outcomes = vec![false; qubit_count];
+        for qubit in &measured_qubits {
+            outcomes[*qubit] = shot_tableau.measure(*qubit, &mut rng);
+        }
+        *results.entry(outcomes).or_insert(0) += 1;
+    }
+
+    (results, measurements)
+}
+
+pub fn get_final_tableau(qubit_count:usize, circuit:Circuit) -> (Tableau, HashMap<u8,u8>) {
+    let mut measurements = HashMap::new();
+
+    let mut ordered_steps = circuit.steps;
+    ordered_steps.sort_by_key(|step| step.index);
+
+    let mut tableau = Tableau::new(qubit_count);
+
+    for step in ordered_steps {
+
+        let mut afected_qubits = HashSet::new();
+
+        for gate in step.gates {
+
+            if matches!(gate.name.as_str(), "barrier" | "snapshot") {
+                continue;
+            }
+
+            if gate.targets.is_empty() {
+                panic!("No targets provided for gate {} at step {}", gate.name, step.index);
+            }
+
+            for qubit in gate.targets.iter().chain(gate.controls.iter().map(|control| &control.target)) {
+                if measurements.contains_key(qubit) {
+                    panic!("The qubit {} has been measured. Cannot add gates at step {} after measurement", qubit, step.index);
+                }
+
+                if !afected_qubits.insert(*qubit) {
+                    panic!("The qubit {} is mentioned twice in step {}", qubit, step.index);
+                }
+            }
+
+            if gate.name == MEASUREMENT_X || gate.name == MEASUREMENT_Y || gate.name == MEASUREMENT_Z {
+                let target = gate.targets[0];
+                let bit = match gate.bit { Some(bit) => bit, None => target };
+                if bit as usize >= qubit_count {
+                    panic!("Measurement bit cannot be larger than the qubit count - 1 ({}). Received {} for qubit {}", qubit_count-1, bit, target);
+                }
+                measurements.insert(target, bit);
+
+                if gate.name == MEASUREMENT_X {
+                    tableau.hadamard(target as usize);
+                } else if gate.name == MEASUREMENT_Y {
+                    tableau.s_dagger(target as usize);
+                    tableau.hadamard(target as usize);
+                }
+                continue;
+            }
+
+            apply_gate(&mut tableau, &gate, step.index);
+        }
+    }
+
+    (tableau, measurements)
+}
+
+pub fn is_clifford_gate(gate:&Gate) -> bool {
+    let name:&str = gate.name.as_ref();
+
+    match gate.controls.len() {
+        0 => matches!(name, "identity" | "hadamard" | "s" | "s-dagger" | "pauli-x" | "pauli-y" | "pauli-z" | "swap"
+                            | "barrier" | "snapshot" | MEASUREMENT_X | MEASUREMENT_Y | MEASUREMENT_Z),
+        1 => matches!(name, "identity" | "pauli-x" | "pauli-y" | "pauli-z"),
+        _ => false
+    }
+}
+
+fn apply_gate(tableau:&mut Tableau, gate:&Gate, step_index:u16) {
+    if !is_clifford_gate(gate) {
+        panic!("The gate {} at step {} is not a Clifford gate and cannot be simulated with the stabilizer method", gate.name, step_index);
+    }
+
+    let target_count = if gate.name == "swap" { 2 } else { 1 };
+    if gate.targets.len() != target_count {
+        panic!("The gate {} at step {} takes {} targets. Received {}", gate.name, step_index, target_count, gate.targets.len());
+    }
+
+    if gate.name == "swap" {
+        let (first, second) = (gate.targets[0] as usize, gate.targets[1] as usize);
+        tableau.cnot(first, second);
+        tableau.cnot(second, first);
+        tableau.cnot(first, second);
+        return;
+    }
+
+    let target = gate.targets[0] as usize;
+    let name:&str = gate.name.as_ref();
+
+    if gate.controls.is_empty() {
+        match name {
+            "hadamard" => tableau.hadamard(target),
+            "s" => tableau.s(target),
+            "s-dagger" => tableau.s_dagger(target),
+            "pauli-x" => tableau.pauli_x(target),
+            "pauli-y" => tableau.pauli_y(target),
+            "pauli-z" => tableau.pauli_z(target),
+            _ => {}
+        }
+        return;
+    }
+
+    let control = gate.controls[0].target as usize;
+    let state:&str = gate.controls[0].state.as_ref();
+
+    // rotate the control so that its state becomes |1⟩
+    match state {
+        "1" => {},
+        "0" => tableau.pauli_x(control),
+        "+" => { tableau.hadamard(control); tableau.pauli_x(control); },
+        "-" => tableau.hadamard(control),
+        "+i" => { tableau.s_dagger(control); tableau.hadamard(control); tableau.pauli_x(control); },
+        "-i" => { tableau.s_dagger(control); tableau.hadamard(control); },
+        unknown_state => panic!("Unknown state {} step {}", unknown_state, step_index)
+    }
+
+    match name {
+        "pauli-x" => tableau.cnot(control, target),
+        "pauli-y" => tableau.cy(control, target),
+        "pauli-z" => tableau.cz(control, target),
+        _ => {}
+    }
+
+    match state {
+        "0" => tableau.pauli_x(control),
+        "+" => { tableau.pauli_x(control); tableau.hadamard(control); },
+        "-" => tableau.hadamard(control),
+        "+i" => { tableau.pauli_x(control); tableau.hadamard(control); tableau.s(control); },
+        "-i" => { tableau.hadamard(control); tableau.s(control); },
+        _ => {}
+    }
+}
+
+pub fn get_bitstring_counts(results:HashMap<Vec<bool>, u32>, measurements:&HashMap<u8,u8>) -> HashMap<String, u32> {
+    let mut counts = HashMap::new();
+    for (outcomes, count) in results {
+        let bitstring = measurement::get_bitstring(&outcomes, measurements);
+        *counts.entry(bitstring).or_insert(0) += count;
+    }
+    counts
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::vec::IntoIter;
+use num_complex::Complex32;
+use super::circuit::Circuit;
+use super::circuit::Step;
+use super::engine;
+use super::measurement;
+use super::simulator;
+
+// The state recorded by a snapshot pseudo-gate, {"name": "snapshot", "label": "..."}, after the step that holds it.
+// The statevector and the probabilities of the measured bits are in the order of the endianess.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub label:String,
+    pub step_index:u16,
+    pub statevector:Vec<Complex32>,
+    pub probabilities:Vec<f32>,
+}
+
+impl Snapshot {
+    pub(crate) fn new(label:String, step_index:u16, statevector:&[Complex32], measurements:&HashMap<u8,u8>, qubit_count:u8, endianess:&Option<String>) -> Snapshot {
+        // the probabilities are read from the ordered statevector, like get_final_state_probabilities does
+        let statevector = simulator::order_final_statevector(statevector.to_vec(), qubit_count, endianess.clone());
+        let probabilities = measurement::get_probabilities(&statevector, measurements.clone(), qubit_count);
+
+        Snapshot { label, step_index, statevector, probabilities }
+    }
+}
+
+// The labels of the snapshots of every step
+pub(crate) fn get_snapshot_labels(circuit:&Circuit) -> HashMap<u16, Vec<String>> {
+    let mut labels:HashMap<u16, Vec<String>> = HashMap::new();
+    for step in &circuit.steps {
+        for gate in step.gates.iter().filter(|gate| gate.name == "snapshot") {
+            let label = match &gate.label {
+                Some(label) => label.clone(),
+                None => panic!("The snapshot of step {} has no label", step.index)
+            };
+            labels.entry(step.index).or_default().push(label);
+        }
+    }
+    labels
+}
+
+// Runs a circuit one step at a time, in the order of the step indexes, so the state can be looked at between the steps
+pub struct Stepper {
+    statevector:Vec<Complex32>,
+    measurements:HashMap<u8,u8>,
+    qubit_count:u8,
+    endianess:Option<String>,
+    steps:IntoIter<Step>,
+    snapshot_labels:HashMap<u16, Vec<String>>,
+    snapshots:Vec<Snapshot>,
+}
+
+impl Stepper {
+    pub(crate) fn new(statevector:Vec<Complex32>, qubit_count:u8, circuit:Circuit, endianess:Option<String>) -> Stepper {
+        let snapshot_labels = get_snapshot_labels(&circuit);
+        let mut steps = circuit.steps;
+        steps.sort_by_key(|step| step.index);
+
+        Stepper {
+            statevector,
+            measurements: HashMap::new(),
+            qubit_count,
+            endianess,
+            steps: steps.into_iter(),
+            snapshot_labels,
+            snapshots: Vec::new(),
+        }
+    }
+
+    // Applies the next step and returns its index, or None when all the steps have been applied
+    pub fn step(&mut self) -> Option<u16> {
+        let step = self.steps.next()?;
+        let index = step.index;
+        engine::apply_step(&mut self.statevector, &mut self.measurements, step, self.qubit_count);
+
+        if let Some(labels) = self.snapshot_labels.remove(&index) {
+            for label in labels {
+                self.snapshots.push(Snapshot::new(label, index, &self.statevector, &self.measurements, self.qubit_count, &self.endianess));
+            }
+        }
+        Some(index)
+    }
+
+    pub fn get_statevector(&self) -> Vec<Complex32> {
+        simulator::order_final_statevector(self.statevector.to_vec(), self.qubit_count, self.endianess.clone())
+    }
+
+    // The probabilities of the bits measured so far, or of all the qubits before the first measurement
+    pub fn get_probabilities(&self) -> Vec<f32> {
+        simulator::get_final_state_probabilities(self.statevector.to_vec(), self.measurements.clone(), self.qubit_count, self.endianess.clone())
+    }
+
+    pub fn get_measurements(&self) -> &HashMap<u8,u8> {
+        &self.measurements
+    }
+
+    pub fn get_snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    // Applies the remaining steps and returns the final statevector, in the order of the endianess, with the snapshots
+    pub fn finish(mut self) -> (Vec<Complex32>, Vec<Snapshot>) {
+        while self.step().is_some() {}
+        (self.get_statevector(), self.snapshots)
+    }
+}
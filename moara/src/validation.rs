@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use serde_json::Value;
+use super::circuit::Angle;
+use super::circuit::Circuit;
+use super::circuit::Definition;
+
+pub const SCHEMA: &str = include_str!("../circuit.schema.json");
+
+// Checks a moara JSON circuit against the schema of the format, then checks what the schema cannot express: gate names, the parameters
+// every gate needs, roots, qubits mentioned twice in a step, snapshots with qubits and gates after a measurement. Returns all the problems, each with its location.
+pub fn validate(serialized_circuit:&str) -> Vec<String> {
+    let value:Value = match serde_json::from_str(serialized_circuit) {
+        Ok(value) => value,
+        Err(err) => return vec![format!("Invalid JSON: {}", err)]
+    };
+    let schema:Value = serde_json::from_str(SCHEMA).unwrap();
+
+    let mut problems = Vec::new();
+    check_schema(&value, &schema, &schema, "", &mut problems);
+
+    match serde_json::from_value::<Circuit>(value) {
+        Ok(circuit) => check_circuit(&circuit, &mut problems),
+        Err(err) if problems.is_empty() => problems.push(format!("Invalid circuit: {}", err)),
+        Err(_) => {}
+    }
+    problems
+}
+
+// Supports the keywords used by the schema of the format: $ref, anyOf, enum, const, if, then, else, type, minimum, maximum, properties,
+// required, additionalProperties and items. Locations are JSON pointers
+fn check_schema(value:&Value, schema:&Value, root:&Value, path:&str, problems:&mut Vec<String>) {
+    let location = if path.is_empty() { "/" } else { path };
+
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.trim_start_matches("#/$defs/");
+        check_schema(value, &root["$defs"][name], root, path, problems);
+        return;
+    }
+
+    if let Some(options) = schema["anyOf"].as_array() {
+        let matches_any = options.iter().any(|option| {
+            let mut option_problems = Vec::new();
+            check_schema(value, option, root, path, &mut option_problems);
+            option_problems.is_empty()
+        });
+        if !matches_any {
+            problems.push(format!("{}: {} does not have any of the allowed types", location, value));
+        }
+        return;
+    }
+
+    if let Some(allowed) = schema["enum"].as_array() {
+        if !allowed.contains(value) {
+            let names:Vec<String> = allowed.iter().map(|allowed| allowed.to_string()).collect();
+            problems.push(format!("{}: {} is not one of {}", location, value, names.join(", ")));
+        }
+        return;
+    }
+
+    if !schema["const"].is_null() && value != &schema["const"] {
+        problems.push(format!("{}: expected {}. Found {}", location, schema["const"], value));
+        return;
+    }
+
+    // the condition only selects the branch, its own problems are not reported
+    if schema["if"].is_object() {
+        let mut condition_problems = Vec::new();
+        check_schema(value, &schema["if"], root, path, &mut condition_problems);
+        let branch = if condition_problems.is_empty() { &schema["then"] } else { &schema["else"] };
+        if branch.is_object() {
+            check_schema(value, branch, root, path, problems);
+        }
+    }
+
+    let types:Vec<&str> = match &schema["type"] {
+        Value::String(name) => vec![name.as_str()],
+        Value::Array(names) => names.iter().filter_map(|name| name.as_str()).collect(),
+        _ => Vec::new()
+    };
+    if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+        problems.push(format!("{}: expected {}. Found {}", location, types.join(" or "), value));
+        return;
+    }
+
+    if let Some(number) = value.as_f64() {
+        if schema["minimum"].as_f64().is_some_and(|minimum| number < minimum) || schema["maximum"].as_f64().is_some_and(|maximum| number > maximum) {
+            problems.push(format!("{}: {} is out of range [{}, {}]", location, number, schema["minimum"], schema["maximum"]));
+        }
+    }
+
+    if let Value::Object(object) = value {
+        if let Some(required) = schema["required"].as_array() {
+            for name in required.iter().filter_map(|name| name.as_str()) {
+                if !object.contains_key(name) {
+                    problems.push(format!("{}: missing {}", location, name));
+                }
+            }
+        }
+        for (name, property) in object {
+            let property_path = format!("{}/{}", path, name);
+            match &schema["properties"][name] {
+                Value::Null => match &schema["additionalProperties"] {
+                    Value::Bool(false) => problems.push(format!("{}: unknown property {}", location, name)),
+                    Value::Object(_) => check_schema(property, &schema["additionalProperties"], root, &property_path, problems),
+                    _ => {}
+                },
+                property_schema => check_schema(property, property_schema, root, &property_path, problems)
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(_)) = (value, schema["items"].as_object()) {
+        for (index, item) in items.iter().enumerate() {
+            check_schema(item, &schema["items"], root, &format!("{}/{}", path, index), problems);
+        }
+    }
+}
+
+fn has_type(value:&Value, name:&str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_u64() || value.is_i64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => false
+    }
+}
+
+fn check_circuit(circuit:&Circuit, problems:&mut Vec<String>) {
+    let definitions:HashMap<&str, &Definition> = circuit.definitions.iter().map(|definition| (definition.name.as_str(), definition)).collect();
+    for definition in &circuit.definitions {
+        check_definition(definition, &definitions, problems);
+    }
+
+    let mut ordered_steps:Vec<_> = circuit.steps.iter().collect();
+    ordered_steps.sort_by_key(|step| step.index);
+
+    let mut measured_qubits = HashMap::new();
+    for step in ordered_steps {
+        let mut used_qubits = HashSet::new();
+        let mut step_measurements = Vec::new();
+
+        for (position, gate) in step.gates.iter().enumerate() {
+            let location = format!("step {} gate {} ({})", step.index, position, gate.name);
+            if gate.name == "barrier" {
+                continue;
+            }
+            if gate.name == "snapshot" {
+                check_snapshot(!gate.targets.is_empty() || !gate.controls.is_empty(), &location, problems);
+                continue;
+            }
+
+            for qubit in gate.targets.iter().chain(gate.controls.iter().map(|control| &control.target)) {
+                if !used_qubits.insert(*qubit) {
+                    problems.push(format!("{}: the qubit {} is mentioned twice in the step", location, qubit));
+                }
+                if let Some(measurement_step) = measured_qubits.get(qubit) {
+                    problems.push(format!("{}: the qubit {} is used after its measurement at step {}", location, qubit, measurement_step));
+                }
+            }
+
+            match definitions.get(gate.name.as_str()) {
+                Some(definition) => check_invocation(definition, gate.targets.len(), gate.arguments.keys(), &location, problems),
+                None if gate.name == "aggregate" => {
+                    if gate.gates.is_empty() {
+                        problems.push(format!("{}: aggregate has no gates", location));
+                    }
+                    for (index, aggregated_gate) in gate.gates.iter().enumerate() {
+                        let aggregated_location = format!("{} aggregated gate {} ({})", location, index, aggregated_gate.name);
+                        let parameters = [aggregated_gate.phi.is_some(), aggregated_gate.theta.is_some(), aggregated_gate.lambda.is_some()];
+                        check_gate(&aggregated_gate.name, aggregated_gate.targets.len(), parameters, &aggregated_gate.root, &aggregated_location, problems);
+                    }
+                },
+                None => {
+                    if !gate.arguments.is_empty() {
+                        problems.push(format!("{}: arguments are supported only by defined gates", location));
+                    }
+                    let parameters = [gate.phi.is_some(), gate.theta.is_some(), gate.lambda.is_some()];
+                    check_gate(&gate.name, gate.targets.len(), parameters, &gate.root, &location, problems);
+                }
+            }
+
+            if gate.name.starts_with("measure-") {
+                step_measurements.extend(gate.targets.iter().cloned());
+            }
+        }
+
+        for qubit in step_measurements {
+            measured_qubits.insert(qubit, step.index);
+        }
+    }
+}
+
+// The schema requires the label
+fn check_snapshot(has_qubits:bool, location:&str, problems:&mut Vec<String>) {
+    if has_qubits {
+        problems.push(format!("{}: snapshots record the whole state and take no qubits", location));
+    }
+}
+
+fn check_definition(definition:&Definition, definitions:&HashMap<&str, &Definition>, problems:&mut Vec<String>) {
+    for step in &definition.steps {
+        let mut used_qubits = HashSet::new();
+
+        for (position, gate) in step.gates.iter().enumerate() {
+            let location = format!("definition {} step {} gate {} ({})", definition.name, step.index, position, gate.name);
+
+            for qubit in gate.targets.iter().chain(gate.controls.iter().map(|control| &control.target)) {
+                if *qubit >= definition.qubits {
+                    problems.push(format!("{}: the qubit {} is not one of the {} qubits of the definition", location, qubit, definition.qubits));
+                }
+                if gate.name != "barrier" && !used_qubits.insert(*qubit) {
+                    problems.push(format!("{}: the qubit {} is mentioned twice in the step", location, qubit));
+                }
+            }
+
+            for angle in gate.phi.iter().chain(gate.theta.iter()).chain(gate.lambda.iter()).chain(gate.arguments.values()) {
+                if let Angle::Expression(text) = angle {
+                    let names = get_names(text);
+                    for name in names.iter().filter(|name| !definition.parameters.contains(name)) {
+                        problems.push(format!("{}: unknown parameter {} in {}", location, name, text));
+                    }
+                }
+            }
+
+            match definitions.get(gate.name.as_str()) {
+                Some(invoked) => check_invocation(invoked, gate.targets.len(), gate.arguments.keys(), &location, problems),
+                None if gate.name.starts_with("measure-") => problems.push(format!("{}: measurements are not supported in definitions", location)),
+                None if gate.name == "barrier" => {},
+                None if gate.name == "snapshot" => check_snapshot(!gate.targets.is_empty() || !gate.controls.is_empty(), &location, problems),
+                None => {
+                    let parameters = [gate.phi.is_some(), gate.theta.is_some(), gate.lambda.is_some()];
+                    check_gate(&gate.name, gate.targets.len(), parameters, &gate.root, &location, problems);
+                }
+            }
+        }
+    }
+}
+
+fn check_invocation<'a>(definition:&Definition, target_count:usize, arguments:impl Iterator<Item = &'a String>, location:&str, problems:&mut Vec<String>) {
+    if target_count != definition.qubits as usize {
+        problems.push(format!("{}: the definition acts on {} qubits. Found {} targets", location, definition.qubits, target_count));
+    }
+
+    let arguments:Vec<&String> = arguments.collect();
+    for parameter in definition.parameters.iter().filter(|parameter| !arguments.contains(parameter)) {
+        problems.push(format!("{}: no argument for the parameter {}", location, parameter));
+    }
+    for argument in arguments.iter().filter(|argument| !definition.parameters.contains(argument)) {
+        problems.push(format!("{}: the definition has no parameter {}", location, argument));
+    }
+}
+
+// parameters holds whether phi, theta and lambda are given
+fn check_gate(name:&str, target_count:usize, parameters:[bool; 3], root:&Option<String>, location:&str, problems:&mut Vec<String>) {
+    let (expected_target_count, needed) = match get_signature(name) {
+        Some(signature) => signature,
+        None => {
+            problems.push(format!("{}: unknown gate {}", location, name));
+            return;
+        }
+    };
+
+    if expected_target_count == 0 && target_count == 0 {
+        problems.push(format!("{}: {} needs at least one target", location, name));
+    } else if expected_target_count != 0 && target_count != expected_target_count {
+        problems.push(format!("{}: {} needs {} targets. Found {}", location, name, expected_target_count, target_count));
+    }
+
+    for parameter in needed {
+        let is_given = match *parameter {
+            "phi" => parameters[0],
+            "theta" => parameters[1],
+            "lambda" => parameters[2],
+            _ => root.is_some()
+        };
+        if !is_given {
+            problems.push(format!("{}: {} needs a value for {}", location, name, parameter));
+        }
+    }
+
+    if let Some(root) = root {
+        if !is_valid_root(root) {
+            problems.push(format!("{}: invalid root {}. Use k, 2^k, 1/k or 1/2^k", location, root));
+        }
+    }
+}
+
+// The number of targets, with 0 for any number, and the parameters of every gate known to the engine
+fn get_signature(name:&str) -> Option<(usize, &'static [&'static str])> {
+    let signature:(usize, &'static [&'static str]) = match name {
+        "identity" | "pauli-x" | "pauli-y" | "pauli-z" | "c" | "c-dagger" | "hadamard" | "hadamard-xy" | "hadamard-yz" | "hadamard-zx"
+            | "t" | "t-dagger" | "s" | "s-dagger" | "v" | "v-dagger" | "h" | "h-dagger" | "measure-x" | "measure-y" | "measure-z" => (1, &[]),
+        "u3" => (1, &["phi", "theta", "lambda"]),
+        "u2" => (1, &["phi", "lambda"]),
+        "u1" => (1, &["lambda"]),
+        "p" | "rx-theta" | "ry-theta" | "rz-theta" => (1, &["theta"]),
+        "pauli-x-root" | "pauli-x-root-dagger" | "pauli-y-root" | "pauli-y-root-dagger" | "pauli-z-root" | "pauli-z-root-dagger" => (1, &["root"]),
+        "swap" | "iswap" | "fswap" | "sqrt-swap" | "sqrt-swap-dagger" | "berkeley" | "berkeley-dagger" | "ecp" | "ecp-dagger"
+            | "magic" | "magic-dagger" | "molmer-sorensen" | "molmer-sorensen-dagger" | "w" => (2, &[]),
+        "a" => (2, &["theta", "phi"]),
+        "cross-resonance" | "cross-resonance-dagger" | "givens" | "swap-theta" | "xx" | "yy" | "zz" | "xy" => (2, &["theta"]),
+        "swap-root" | "swap-root-dagger" => (2, &["root"]),
+        "qft" | "qft-dagger" => (0, &[]),
+        _ => return None
+    };
+    Some(signature)
+}
+
+// The forms read by gate_mapper::get_value_from_root
+fn is_valid_root(root:&str) -> bool {
+    let value = root.strip_prefix("1/").unwrap_or(root);
+    let value = value.strip_prefix("2^").unwrap_or(value);
+    value.parse::<f32>().is_ok()
+}
+
+// The parameter names used in an expression, without pi, the imaginary unit and function names
+fn get_names(text:&str) -> Vec<String> {
+    let mut names = Vec::new();
+    let chars:Vec<char> = text.chars().collect();
+    let mut position = 0;
+    while position < chars.len() {
+        let starts_name = (chars[position].is_alphabetic() || chars[position] == '_' || chars[position] == '%')
+            && (position == 0 || !chars[position - 1].is_ascii_digit() && chars[position - 1] != '.');
+        if !starts_name {
+            position += 1;
+            continue;
+        }
+
+        let start = position;
+        while position < chars.len() && (chars[position].is_alphanumeric() || chars[position] == '_' || chars[position] == '%') {
+            position += 1;
+        }
+        let name:String = chars[start..position].iter().collect::<String>().trim_start_matches('%').to_string();
+        let is_function = chars[position..].iter().find(|c| !c.is_whitespace()) == Some(&'(');
+        if !is_function && name != "pi" && name != "i" && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
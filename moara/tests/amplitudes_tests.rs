@@ -0,0 +1,41 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::simulator;
+
+static CIRCUIT: &str = "{\"steps\": [
+    {\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}, {\"name\": \"ry-theta\", \"targets\": [1], \"theta\": 0.9}, {\"name\": \"pauli-x\", \"targets\": [2]}]},
+    {\"index\": 1, \"gates\": [{\"name\": \"s\", \"targets\": [0]}, {\"name\": \"pauli-x\", \"targets\": [2], \"controls\": [{\"target\": 1, \"state\": \"1\"}]}]}
+]}";
+
+#[test]
+fn amplitudes_are_the_entries_of_the_statevector() {
+    let statevector = simulator::get_statevector(CIRCUIT.to_string(), None, None, None);
+    let bitstrings:Vec<String> = ["000", "001", "101", "110", "111"].iter().map(|bitstring| bitstring.to_string()).collect();
+
+    let amplitudes = simulator::get_amplitudes(CIRCUIT.to_string(), bitstrings.clone(), None, None, None);
+    for (bitstring, amplitude) in bitstrings.iter().zip(&amplitudes) {
+        assert!((amplitude - statevector[usize::from_str_radix(bitstring, 2).unwrap()]).norm() < 1e-6);
+    }
+
+    let probabilities = simulator::get_bitstring_probabilities(CIRCUIT.to_string(), bitstrings, None, None, None);
+    for (probability, amplitude) in probabilities.iter().zip(&amplitudes) {
+        assert!((probability - amplitude.norm_sqr()).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn bitstrings_follow_the_endianess() {
+    let big_endian = simulator::get_amplitudes(CIRCUIT.to_string(), vec!["110".to_string(), "001".to_string()], None, None, None);
+    let little_endian = simulator::get_amplitudes(CIRCUIT.to_string(), vec!["011".to_string(), "100".to_string()], Some("littleendian".to_string()), None, None);
+
+    assert!((big_endian[0] - little_endian[0]).norm() < 1e-6);
+    assert!((big_endian[1] - little_endian[1]).norm() < 1e-6);
+    assert!(big_endian[0].norm() > 0.1 && big_endian[1].norm() > 0.1);
+}
+
+#[test]
+#[should_panic(expected = "The bitstring 0101 must have one bit for each of the 3 qubits")]
+fn bitstrings_must_cover_all_the_qubits() {
+    simulator::get_bitstring_probabilities(CIRCUIT.to_string(), vec!["0101".to_string()], None, None, None);
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::simulator;
+
+#[test]
+fn analysis_counts_gates_and_depth() {
+    let serialized = "{
+        \"steps\": [
+          { \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] }, { \"name\": \"hadamard\", \"targets\": [ 1 ] } ] },
+          { \"index\": 3, \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 2 ], \"controls\": [ { \"target\": 0, \"state\": \"1\" } ] } ] },
+          { \"index\": 4, \"gates\": [ { \"name\": \"barrier\", \"targets\": [ 0, 1, 2 ] } ] },
+          { \"index\": 7, \"gates\": [ { \"name\": \"swap\", \"targets\": [ 1, 2 ], \"controls\": [ { \"target\": 0, \"state\": \"1\" } ] } ] }
+        ]
+      }";
+
+    let analysis = simulator::analyze(serialized.to_string(), Some(10), 100);
+
+    assert_eq!(10, analysis.qubit_count);
+    assert_eq!(3, analysis.depth);
+    assert_eq!(4, analysis.gate_count);
+    assert_eq!(Some(&2), analysis.gate_counts.get("hadamard"));
+    assert_eq!(None, analysis.gate_counts.get("barrier"));
+    assert_eq!(1, analysis.two_qubit_gate_count);
+    assert_eq!(1, analysis.multi_qubit_gate_count);
+    assert!(!analysis.is_clifford);
+    assert_eq!(8192.0, analysis.statevector_memory_f32);
+    assert_eq!(16384.0, analysis.statevector_memory_f64);
+    assert!(analysis.estimated_runtime > 0.0);
+}
+
+#[test]
+fn analysis_detects_clifford_circuits() {
+    let serialized = "{
+        \"steps\": [
+          { \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] } ] },
+          { \"index\": 1, \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 1 ], \"controls\": [ { \"target\": 0, \"state\": \"1\" } ] } ] },
+          { \"index\": 2, \"gates\": [ { \"name\": \"measure-z\", \"targets\": [ 0 ] }, { \"name\": \"measure-z\", \"targets\": [ 1 ] } ] }
+        ]
+      }";
+
+    let analysis = simulator::analyze(serialized.to_string(), None, 1024);
+
+    assert_eq!(2, analysis.qubit_count);
+    assert_eq!(3, analysis.depth);
+    assert!(analysis.is_clifford);
+    assert!(analysis.to_string().contains("clifford only: yes"));
+}
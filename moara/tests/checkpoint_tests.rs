@@ -0,0 +1,118 @@
+#[cfg(test)]
+extern crate moara;
+
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::BufReader;
+use num_complex::Complex32;
+use moara::checkpoint::Checkpoint;
+use moara::checkpoint::Checkpointer;
+use moara::simulator;
+
+static CIRCUIT: &str = "{\"steps\": [
+    {\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}, {\"name\": \"ry-theta\", \"targets\": [2], \"theta\": 0.4}]},
+    {\"index\": 1, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}]},
+    {\"index\": 3, \"gates\": [{\"name\": \"measure-z\", \"targets\": [0], \"bit\": 1}]},
+    {\"index\": 4, \"gates\": [{\"name\": \"t\", \"targets\": [1]}, {\"name\": \"rx-theta\", \"targets\": [2], \"theta\": 1.3}]},
+    {\"index\": 6, \"gates\": [{\"name\": \"swap\", \"targets\": [1, 2]}]}
+]}";
+
+#[test]
+fn checkpoints_round_trip() {
+    let mut measurements = HashMap::new();
+    measurements.insert(2, 0);
+    measurements.insert(0, 1);
+    let checkpoint = Checkpoint {
+        qubit_count: 2,
+        step_index: 41,
+        measurements,
+        circuit: String::from("{\"steps\": []}"),
+        metadata: String::from("{\"command\": \"sample\"}"),
+        statevector: vec![Complex32::new(0.6, 0.0), Complex32::new(0.0, -0.8), Complex32::new(0.0, 0.0), Complex32::new(0.0, 0.0)],
+    };
+
+    let mut bytes = Vec::new();
+    checkpoint.write(&mut bytes).unwrap();
+    let read = Checkpoint::read(&mut bytes.as_slice());
+
+    assert_eq!(b"MOARACKP", &bytes[0..8]);
+    assert_eq!(checkpoint.qubit_count, read.qubit_count);
+    assert_eq!(checkpoint.step_index, read.step_index);
+    assert_eq!(checkpoint.measurements, read.measurements);
+    assert_eq!(checkpoint.circuit, read.circuit);
+    assert_eq!(checkpoint.metadata, read.metadata);
+    assert_eq!(checkpoint.statevector, read.statevector);
+}
+
+#[test]
+fn resumed_runs_end_in_the_same_state() {
+    let path = std::env::temp_dir().join(format!("moara_checkpoint_{}.bin", std::process::id()));
+    let mut checkpointer = Checkpointer::new(path.clone(), Some(3), None, String::from("stage"));
+
+    let (expected, expected_measurements, _, _) = simulator::get_final_state(CIRCUIT.to_string(), &None, None, None, Some(&mut checkpointer));
+    let checkpoint = Checkpoint::read(&mut BufReader::new(File::open(&path).unwrap()));
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(3, checkpoint.step_index);
+    assert_eq!("stage", checkpoint.metadata);
+
+    let (statevector, measurements, count, _) = simulator::resume(checkpoint, &None, None);
+
+    assert_eq!(3, count);
+    assert_eq!(expected_measurements, measurements);
+    for (amplitude, expected_amplitude) in statevector.iter().zip(expected) {
+        assert!((amplitude - expected_amplitude).norm() < 1e-6);
+    }
+}
+
+#[test]
+#[should_panic(expected = "The checkpoint is corrupted: its checksum does not match")]
+fn corrupted_checkpoints_are_rejected() {
+    let checkpoint = Checkpoint {
+        qubit_count: 1,
+        step_index: 0,
+        measurements: HashMap::new(),
+        circuit: String::from("{\"steps\": []}"),
+        metadata: String::new(),
+        statevector: vec![Complex32::new(1.0, 0.0), Complex32::new(0.0, 0.0)],
+    };
+    let mut bytes = Vec::new();
+    checkpoint.write(&mut bytes).unwrap();
+
+    let length = bytes.len();
+    bytes[length - 8] ^= 1;
+
+    Checkpoint::read(&mut bytes.as_slice());
+}
+
+#[test]
+#[should_panic(expected = "The checkpoint is truncated")]
+fn truncated_checkpoints_are_rejected() {
+    let checkpoint = Checkpoint {
+        qubit_count: 1,
+        step_index: 0,
+        measurements: HashMap::new(),
+        circuit: String::from("{\"steps\": []}"),
+        metadata: String::new(),
+        statevector: vec![Complex32::new(1.0, 0.0), Complex32::new(0.0, 0.0)],
+    };
+    let mut bytes = Vec::new();
+    checkpoint.write(&mut bytes).unwrap();
+
+    bytes.truncate(bytes.len() - 6);
+
+    Checkpoint::read(&mut bytes.as_slice());
+}
+
+#[test]
+fn failed_checkpoints_do_not_stop_the_simulation() {
+    let path = std::env::temp_dir().join(format!("moara_missing_{}", std::process::id())).join("checkpoint.bin");
+    let mut checkpointer = Checkpointer::new(path.clone(), Some(1), None, String::new());
+
+    let (statevector, _, count, _) = simulator::get_final_state(CIRCUIT.to_string(), &None, None, None, Some(&mut checkpointer));
+
+    assert_eq!(3, count);
+    assert_eq!(8, statevector.len());
+    assert!(!path.exists());
+}
@@ -0,0 +1,98 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::simulator;
+
+#[test]
+fn gate_families_and_controls_match_json_circuit() {
+    let cirq_json = r#"{
+      "cirq_type": "Circuit",
+      "moments": [
+        { "cirq_type": "Moment", "operations": [
+          { "cirq_type": "GateOperation", "gate": { "cirq_type": "HPowGate", "exponent": 1.0, "global_shift": 0.0 },
+            "qubits": [ { "cirq_type": "GridQubit", "row": 0, "col": 0 } ] },
+          { "cirq_type": "GateOperation", "gate": { "cirq_type": "Rx", "rads": 0.5 },
+            "qubits": [ { "cirq_type": "GridQubit", "row": 0, "col": 1 } ] },
+          { "cirq_type": "GateOperation", "gate": { "cirq_type": "_PauliY", "exponent": 1.0, "global_shift": 0.0 },
+            "qubits": [ { "cirq_type": "GridQubit", "row": 1, "col": 0 } ] }
+        ] },
+        { "cirq_type": "Moment", "operations": [
+          { "cirq_type": "ControlledOperation",
+            "controls": [ { "cirq_type": "GridQubit", "row": 1, "col": 0 } ],
+            "control_values": [ [ 0 ] ],
+            "sub_operation": { "cirq_type": "GateOperation", "gate": { "cirq_type": "CXPowGate", "exponent": 1.0, "global_shift": 0.0 },
+              "qubits": [ { "cirq_type": "GridQubit", "row": 0, "col": 0 }, { "cirq_type": "GridQubit", "row": 0, "col": 1 } ] } }
+        ] },
+        { "cirq_type": "Moment", "operations": [
+          { "cirq_type": "GateOperation",
+            "gate": { "cirq_type": "ControlledGate", "sub_gate": { "cirq_type": "ZPowGate", "exponent": 0.5, "global_shift": 0.0 },
+              "num_controls": 1, "control_values": [ [ 1 ] ], "control_qid_shape": [ 2 ] },
+            "qubits": [ { "cirq_type": "GridQubit", "row": 0, "col": 0 }, { "cirq_type": "GridQubit", "row": 1, "col": 0 } ] }
+        ] },
+        { "cirq_type": "Moment", "operations": [
+          { "cirq_type": "GateOperation", "gate": { "cirq_type": "ISwapPowGate", "exponent": 0.5, "global_shift": 0.0 },
+            "qubits": [ { "cirq_type": "GridQubit", "row": 0, "col": 1 }, { "cirq_type": "GridQubit", "row": 1, "col": 0 } ] }
+        ] }
+      ]
+    }"#;
+    let serialized = "{
+        \"steps\": [
+          { \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] }, { \"name\": \"rx-theta\", \"targets\": [ 1 ], \"theta\": 0.5 }, { \"name\": \"pauli-y\", \"targets\": [ 2 ] } ] },
+          { \"index\": 1, \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 1 ], \"controls\": [ { \"target\": 2, \"state\": \"0\" }, { \"target\": 0, \"state\": \"1\" } ] } ] },
+          { \"index\": 2, \"gates\": [ { \"name\": \"s\", \"targets\": [ 2 ], \"controls\": [ { \"target\": 0, \"state\": \"1\" } ] } ] },
+          { \"index\": 3, \"gates\": [ { \"name\": \"xy\", \"targets\": [ 1, 2 ], \"theta\": -0.7853982 } ] }
+        ]
+      }";
+
+    let (converted, qubit_count) = moara::convert_cirq(cirq_json.to_string());
+    assert_eq!(3, qubit_count);
+
+    let statevector = simulator::get_statevector(converted, None, Some(qubit_count), None);
+    let expected = simulator::get_statevector(serialized.to_string(), None, Some(3), None);
+
+    for (amplitude, expected_amplitude) in statevector.iter().zip(expected) {
+        assert!((amplitude - expected_amplitude).norm() < 1e-5);
+    }
+}
+
+#[test]
+fn measurement_keys_are_mapped_to_classical_bits() {
+    let cirq_json = r#"{
+      "cirq_type": "Circuit",
+      "moments": [
+        { "cirq_type": "Moment", "operations": [
+          { "cirq_type": "GateOperation", "gate": { "cirq_type": "XPowGate", "exponent": 1.0, "global_shift": 0.0 },
+            "qubits": [ { "cirq_type": "LineQubit", "x": 2 } ] }
+        ] },
+        { "cirq_type": "Moment", "operations": [
+          { "cirq_type": "GateOperation", "gate": { "cirq_type": "MeasurementGate", "num_qubits": 1, "key": "b", "invert_mask": [], "qid_shape": [ 2 ] },
+            "qubits": [ { "cirq_type": "LineQubit", "x": 2 } ] },
+          { "cirq_type": "GateOperation", "gate": { "cirq_type": "MeasurementGate", "num_qubits": 2, "key": "a", "invert_mask": [], "qid_shape": [ 2, 2 ] },
+            "qubits": [ { "cirq_type": "LineQubit", "x": 0 }, { "cirq_type": "LineQubit", "x": 1 } ] }
+        ] }
+      ]
+    }"#;
+
+    let (converted, qubit_count) = moara::convert_cirq(cirq_json.to_string());
+    let probabilities = simulator::get_probabilities(converted, None, Some(qubit_count), None);
+
+    let mut expected = vec![0.0; 8];
+    expected[0b100] = 1.0;
+    assert_eq!(expected, probabilities.iter().map(|probability| probability.round()).collect::<Vec<f32>>());
+}
+
+#[test]
+#[should_panic(expected = "Symbolic values are not supported")]
+fn symbolic_exponent_panics() {
+    let cirq_json = r#"{
+      "cirq_type": "Circuit",
+      "moments": [
+        { "cirq_type": "Moment", "operations": [
+          { "cirq_type": "GateOperation", "gate": { "cirq_type": "XPowGate", "exponent": { "cirq_type": "sympy.Symbol", "name": "t" }, "global_shift": 0.0 },
+            "qubits": [ { "cirq_type": "LineQubit", "x": 0 } ] }
+        ] }
+      ]
+    }"#;
+
+    moara::convert_cirq(cirq_json.to_string());
+}
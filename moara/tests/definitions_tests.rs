@@ -0,0 +1,83 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::simulator;
+
+#[test]
+fn nested_definitions_with_controls_and_dagger_are_expanded() {
+    let circuit = r#"{
+        "definitions": [
+            {"name": "rotate", "qubits": 1, "parameters": ["angle"], "steps": [
+                {"index": 0, "gates": [{"name": "ry-theta", "targets": [0], "theta": "angle/2"}]},
+                {"index": 1, "gates": [{"name": "t", "targets": [0]}]}
+            ]},
+            {"name": "entangle", "qubits": 2, "parameters": ["theta"], "steps": [
+                {"index": 0, "gates": [{"name": "hadamard", "targets": [0]}]},
+                {"index": 1, "gates": [{"name": "rotate", "targets": [1], "controls": [{"target": 0, "state": "1"}], "arguments": {"angle": "2*theta"}}]},
+                {"index": 2, "gates": [{"name": "iswap", "targets": [0, 1]}]}
+            ]}
+        ],
+        "steps": [
+            {"index": 0, "gates": [{"name": "hadamard", "targets": [2]}, {"name": "ry-theta", "targets": [1], "theta": 0.4}, {"name": "ry-theta", "targets": [0], "theta": 1.1}]},
+            {"index": 1, "gates": [{"name": "entangle", "targets": [1, 0], "controls": [{"target": 2, "state": "1"}], "arguments": {"theta": "pi/3"}, "dagger": true}]}
+        ]
+    }"#;
+    let expected = r#"{"steps": [
+        {"index": 0, "gates": [{"name": "hadamard", "targets": [2]}, {"name": "ry-theta", "targets": [1], "theta": 0.4}, {"name": "ry-theta", "targets": [0], "theta": 1.1}]},
+        {"index": 1, "gates": [{"name": "xy", "targets": [1, 0], "theta": 1.5707963, "controls": [{"target": 2, "state": "1"}]}]},
+        {"index": 2, "gates": [{"name": "t-dagger", "targets": [0], "controls": [{"target": 1, "state": "1"}, {"target": 2, "state": "1"}]}]},
+        {"index": 3, "gates": [{"name": "ry-theta", "targets": [0], "theta": -1.0471976, "controls": [{"target": 1, "state": "1"}, {"target": 2, "state": "1"}]}]},
+        {"index": 4, "gates": [{"name": "hadamard", "targets": [1], "controls": [{"target": 2, "state": "1"}]}]}
+    ]}"#;
+
+    let statevector = simulator::get_statevector(circuit.to_string(), None, Some(3), None);
+    let expected_statevector = simulator::get_statevector(expected.to_string(), None, Some(3), None);
+
+    for (amplitude, expected_amplitude) in statevector.iter().zip(expected_statevector) {
+        assert!((amplitude - expected_amplitude).norm() < 1e-5);
+    }
+}
+
+#[test]
+fn dagger_of_a_definition_undoes_it() {
+    let circuit = r#"{
+        "definitions": [
+            {"name": "prepare", "qubits": 2, "parameters": ["a", "b"], "steps": [
+                {"index": 0, "gates": [{"name": "u3", "targets": [0], "theta": "a", "phi": "b", "lambda": "a*b"}, {"name": "u2", "targets": [1], "phi": "b", "lambda": 0.3}]},
+                {"index": 1, "gates": [{"name": "pauli-y-root", "targets": [0], "root": "1/2^3"}, {"name": "s", "targets": [1], "dagger": true}]},
+                {"index": 2, "gates": [{"name": "sqrt-swap", "targets": [0, 1]}]},
+                {"index": 3, "gates": [{"name": "iswap", "targets": [1, 0]}]},
+                {"index": 4, "gates": [{"name": "givens", "targets": [0, 1], "theta": "b - a"}]}
+            ]}
+        ],
+        "steps": [
+            {"index": 0, "gates": [{"name": "prepare", "targets": [0, 1], "arguments": {"a": 0.7, "b": "pi/5"}}]},
+            {"index": 1, "gates": [{"name": "prepare", "targets": [0, 1], "arguments": {"a": 0.7, "b": "pi/5"}, "dagger": true}]}
+        ]
+    }"#;
+
+    let statevector = simulator::get_statevector(circuit.to_string(), None, Some(2), None);
+
+    assert!((statevector[0].norm() - 1.0).abs() < 1e-5);
+}
+
+#[test]
+#[should_panic(expected = "step 1 gate outer > step 0 gate inner > step 0 gate rz-theta: unknown parameter phi in phi/2")]
+fn errors_report_the_path_through_definitions() {
+    let circuit = r#"{
+        "definitions": [
+            {"name": "inner", "qubits": 1, "parameters": ["theta"], "steps": [
+                {"index": 0, "gates": [{"name": "rz-theta", "targets": [0], "theta": "phi/2"}]}
+            ]},
+            {"name": "outer", "qubits": 2, "steps": [
+                {"index": 0, "gates": [{"name": "inner", "targets": [1], "arguments": {"theta": 1}}]}
+            ]}
+        ],
+        "steps": [
+            {"index": 0, "gates": [{"name": "hadamard", "targets": [0]}]},
+            {"index": 1, "gates": [{"name": "outer", "targets": [0, 1]}]}
+        ]
+    }"#;
+
+    simulator::get_statevector(circuit.to_string(), None, Some(2), None);
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+extern crate moara;
+
+use num_complex::Complex32;
+use moara::density;
+use moara::simulator;
+
+static CIRCUIT: &str = "{\"steps\": [
+    {\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}, {\"name\": \"pauli-x\", \"targets\": [2]}]},
+    {\"index\": 1, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}]}
+]}";
+
+fn assert_matrix(matrix:&[Complex32], expected:&[f32]) {
+    assert_eq!(matrix.len(), expected.len());
+    for (value, expected_value) in matrix.iter().zip(expected) {
+        assert!((value.re - expected_value).abs() < 1e-5 && value.im.abs() < 1e-5, "{:?} != {:?}", matrix, expected);
+    }
+}
+
+#[test]
+fn entangled_qubits_keep_their_coherence_and_the_others_are_mixed() {
+    let matrix = simulator::get_reduced_density_matrix(CIRCUIT.to_string(), vec![0, 1], None, None, None);
+    assert_matrix(&matrix, &[
+        0.5, 0.0, 0.0, 0.5,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.5, 0.0, 0.0, 0.5,
+    ]);
+
+    let matrix = simulator::get_reduced_density_matrix(CIRCUIT.to_string(), vec![1], None, None, None);
+    assert_matrix(&matrix, &[0.5, 0.0, 0.0, 0.5]);
+
+    let matrix = simulator::get_reduced_density_matrix(CIRCUIT.to_string(), vec![0, 1, 2], None, None, None);
+    let statevector = simulator::get_statevector(CIRCUIT.to_string(), None, None, None);
+    for (index, value) in matrix.iter().enumerate() {
+        assert!((value - statevector[index/8]*statevector[index%8].conj()).norm() < 1e-5);
+    }
+}
+
+#[test]
+fn the_kept_qubits_follow_the_endianess() {
+    let matrix = simulator::get_reduced_density_matrix(CIRCUIT.to_string(), vec![0, 2], None, None, None);
+    assert_matrix(&matrix, &[
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.5, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.5,
+    ]);
+
+    let matrix = simulator::get_reduced_density_matrix(CIRCUIT.to_string(), vec![0, 2], Some("littleendian".to_string()), None, None);
+    assert_matrix(&matrix, &[
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.5, 0.0,
+        0.0, 0.0, 0.0, 0.5,
+    ]);
+
+    // reversing the kept qubits transposes their bits
+    let reversed = simulator::get_reduced_density_matrix(CIRCUIT.to_string(), vec![2, 0], None, None, None);
+    assert_matrix(&reversed, &[
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.5, 0.0,
+        0.0, 0.0, 0.0, 0.5,
+    ]);
+}
+
+#[test]
+#[should_panic(expected = "The qubit 1 is given twice")]
+fn repeated_qubits_are_rejected() {
+    let statevector = vec![Complex32::new(1.0, 0.0), Complex32::new(0.0, 0.0), Complex32::new(0.0, 0.0), Complex32::new(0.0, 0.0)];
+
+    density::get_reduced_density_matrix(&statevector, 2, &[1, 1]);
+}
@@ -0,0 +1,125 @@
+#[cfg(test)]
+extern crate moara;
+
+static BELL: &str = "{\"steps\": [
+    {\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}]},
+    {\"index\": 1, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}]},
+    {\"index\": 2, \"gates\": [{\"name\": \"measure-z\", \"targets\": [0]}, {\"name\": \"measure-x\", \"targets\": [1], \"bit\": 3}]}
+]}";
+
+#[test]
+fn circuits_are_drawn_with_a_wire_per_qubit_and_a_column_per_step() {
+    let diagram = moara::draw(BELL.to_string(), None, String::from("text"), None);
+
+    let expected = [
+        "       0      1        2",
+        "     ┌───┐         ┌──────┐",
+        "q0: ─┤ H ├────●────┤ M→c0 ├──",
+        "     └───┘    │    └──────┘",
+        "            ┌─┴─┐  ┌───────┐",
+        "q1: ────────┤ X ├──┤ Mx→c3 ├─",
+        "            └───┘  └───────┘",
+    ];
+    assert_eq!(expected.join("\n"), diagram);
+}
+
+#[test]
+fn controls_show_their_state_and_angles_are_abbreviated() {
+    let circuit = "{\"steps\": [
+        {\"index\": 0, \"gates\": [{\"name\": \"rz-theta\", \"targets\": [2], \"theta\": 0.7853982, \"controls\": [{\"target\": 0, \"state\": \"0\"}, {\"target\": 1, \"state\": \"+\"}]}]},
+        {\"index\": 1, \"gates\": [{\"name\": \"swap\", \"targets\": [0, 2], \"controls\": [{\"target\": 1, \"state\": \"-i\"}]}]},
+        {\"index\": 2, \"gates\": [{\"name\": \"u3\", \"targets\": [1], \"theta\": 0.3, \"phi\": -1.5707964, \"lambda\": 3.1415927}]},
+        {\"index\": 4, \"gates\": [{\"name\": \"qft\", \"targets\": [0, 2]}]}
+    ]}";
+    let diagram = moara::draw(circuit.to_string(), None, String::from("text"), None);
+    let wires:Vec<&str> = diagram.lines().skip(2).step_by(3).collect();
+
+    assert_eq!(vec![
+        "q0: ──────○───────×──────────────────────┤ 0 QFT ├─",
+        "q1: ──────⊕───────⊘──┤ U3(0.3,-π/2,π) ├──┤       ├─",
+        "q2: ─┤ RZ(π/4) ├──×──────────────────────┤   1   ├─",
+    ], wires);
+}
+
+#[test]
+fn wide_circuits_are_wrapped() {
+    let gates:Vec<String> = (0..30).map(|index| format!("{{\"index\": {}, \"gates\": [{{\"name\": \"ry-theta\", \"targets\": [{}], \"theta\": 0.5}}]}}", index, index % 2)).collect();
+    let circuit = format!("{{\"steps\": [{}]}}", gates.join(","));
+
+    let diagram = moara::draw(circuit.clone(), None, String::from("text"), Some(60));
+    let blocks:Vec<&str> = diagram.split("\n\n").collect();
+    assert!(blocks.len() > 1);
+    assert!(diagram.lines().all(|line| line.chars().count() <= 60));
+    assert!(blocks.iter().all(|block| block.lines().count() == 7));
+
+    let unwrapped = moara::draw(circuit, None, String::from("text"), None);
+    assert_eq!(30, unwrapped.matches("RY(0.5)").count());
+    assert_eq!(7, unwrapped.lines().count());
+}
+
+#[test]
+fn latex_diagrams_use_quantikz() {
+    let circuit = "{\"steps\": [
+        {\"index\": 0, \"gates\": [{\"name\": \"qft\", \"targets\": [1, 2]}, {\"name\": \"t-dagger\", \"targets\": [0]}]},
+        {\"index\": 1, \"gates\": [{\"name\": \"aggregate\", \"gates\": [{\"name\": \"hadamard\", \"targets\": [1]}, {\"name\": \"rx-theta\", \"targets\": [2], \"theta\": 1.5707964}], \"controls\": [{\"target\": 0, \"state\": \"0\"}]}]},
+        {\"index\": 2, \"gates\": [{\"name\": \"pauli-z\", \"targets\": [0], \"controls\": [{\"target\": 2, \"state\": \"-i\"}]}]},
+        {\"index\": 3, \"gates\": [{\"name\": \"measure-y\", \"targets\": [1], \"bit\": 0}, {\"name\": \"snapshot\", \"label\": \"end_state\"}]}
+    ]}";
+    let latex = moara::draw(circuit.to_string(), None, String::from("latex"), None);
+
+    let expected = [
+        "\\begin{quantikz}",
+        "\\lstick{$q_{0}$} & \\gate{\\mathrm{T}^\\dagger} & \\octrl{2} & \\gate{\\mathrm{Z}}\\vqw{2} & \\qw & \\qw \\slice{end\\_state} & \\qw \\\\",
+        "\\lstick{$q_{1}$} & \\gate[wires=2]{\\mathrm{QFT}} & \\gate{\\mathrm{H}} \\gategroup[wires=2,steps=1,style={dashed,rounded corners}]{} & \\qw & \\meter{\\ensuremath{Y \\rightarrow c_{0}}} & \\qw & \\qw \\\\",
+        "\\lstick{$q_{2}$} & \\qw & \\gate{\\mathrm{RX}(\\pi/2)} & \\gate[style={circle,inner sep=1pt}]{-i} & \\qw & \\qw & \\qw",
+        "\\end{quantikz}",
+    ];
+    assert_eq!(expected.join("\n"), latex);
+}
+
+#[test]
+fn aggregated_gates_are_grouped_without_a_line() {
+    let circuit = "{\"steps\": [
+        {\"index\": 0, \"gates\": [{\"name\": \"aggregate\", \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}, {\"name\": \"pauli-x\", \"targets\": [2]}]}]},
+        {\"index\": 1, \"gates\": [{\"name\": \"pauli-z\", \"targets\": [1]}]}
+    ]}";
+    let diagram = moara::draw(circuit.to_string(), None, String::from("text"), None);
+
+    let expected = [
+        "        0       1",
+        "    ┌ ┌───┐ ┐",
+        "q0: ──┤ H ├─────────",
+        "    ┆ └───┘ ┆",
+        "    ┆       ┆ ┌───┐",
+        "q1: ──────────┤ Z ├─",
+        "    ┆       ┆ └───┘",
+        "    ┆ ┌───┐ ┆",
+        "q2: ──┤ X ├─────────",
+        "    └ └───┘ ┘",
+    ];
+    assert_eq!(expected.join("\n"), diagram);
+
+    let svg = moara::draw(circuit.to_string(), None, String::from("svg"), None);
+    assert_eq!(1, svg.matches("fill=\"none\" stroke=\"gray\" stroke-dasharray=\"4 2\"").count());
+    // the three wires and no line between the aggregated gates
+    assert_eq!(3, svg.matches("<line").count());
+}
+
+#[test]
+fn svg_diagrams_are_deterministic() {
+    let svg = moara::draw(BELL.to_string(), None, String::from("svg"), None);
+
+    assert_eq!(svg, moara::draw(BELL.to_string(), None, String::from("svg"), None));
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>"));
+    assert_eq!(1, svg.matches("<circle").count());
+    assert_eq!(2, svg.matches(" A 12 12 0 0 1 ").count());
+    assert!(svg.contains(">c3</text>"));
+    assert!(svg.contains(">X</text>"));
+}
+
+#[test]
+#[should_panic(expected = "Unknown diagram format png. Use 'text', 'svg' or 'latex'")]
+fn unknown_formats_are_rejected() {
+    moara::draw(BELL.to_string(), None, String::from("png"), None);
+}
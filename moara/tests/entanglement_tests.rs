@@ -0,0 +1,128 @@
+#[cfg(test)]
+extern crate moara;
+
+use std::f64::consts::FRAC_1_SQRT_2;
+use std::time::Instant;
+use num_complex::{Complex32, Complex64};
+use moara::entanglement;
+use moara::linalg;
+use moara::simulator;
+
+#[test]
+fn a_bell_pair_has_one_bit_of_entanglement() {
+    let circuit = "{\"steps\": [
+        {\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}, {\"name\": \"hadamard\", \"targets\": [2]}]},
+        {\"index\": 1, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}]}
+    ]}";
+
+    let entanglement = simulator::get_entanglement(circuit.to_string(), vec![0], 2.0, None, None);
+    assert_eq!(entanglement.schmidt_coefficients.len(), 2);
+    for coefficient in &entanglement.schmidt_coefficients {
+        assert!((coefficient - FRAC_1_SQRT_2).abs() < 1e-6);
+    }
+    assert!((entanglement.von_neumann_entropy - 1.0).abs() < 1e-6);
+    assert!((entanglement.renyi_entropy - 1.0).abs() < 1e-6);
+
+    // the qubit in the |+⟩ state is not entangled with the pair
+    let entanglement = simulator::get_entanglement(circuit.to_string(), vec![0, 1], 2.0, None, None);
+    assert_eq!(entanglement.schmidt_coefficients.len(), 1);
+    assert_eq!(entanglement.von_neumann_entropy, 0.0);
+}
+
+#[test]
+fn renyi_entropies_decrease_with_their_order() {
+    let coefficients = [0.8f64.sqrt(), 0.15f64.sqrt(), 0.05f64.sqrt()];
+
+    let von_neumann_entropy = -(0.8*0.8f64.log2() + 0.15*0.15f64.log2() + 0.05*0.05f64.log2());
+    assert!((entanglement::get_von_neumann_entropy(&coefficients) - von_neumann_entropy).abs() < 1e-9);
+    assert!((entanglement::get_renyi_entropy(&coefficients, 1.0) - von_neumann_entropy).abs() < 1e-9);
+
+    assert!((entanglement::get_renyi_entropy(&coefficients, 0.0) - 3f64.log2()).abs() < 1e-9);
+    assert!((entanglement::get_renyi_entropy(&coefficients, 2.0) + (0.64f64 + 0.0225 + 0.0025).log2()).abs() < 1e-9);
+    assert!((entanglement::get_renyi_entropy(&coefficients, f64::INFINITY) + 0.8f64.log2()).abs() < 1e-9);
+
+    let orders = [0.0, 0.5, 1.0, 2.0, 3.0, f64::INFINITY];
+    for pair in orders.windows(2) {
+        assert!(entanglement::get_renyi_entropy(&coefficients, pair[0]) >= entanglement::get_renyi_entropy(&coefficients, pair[1]));
+    }
+}
+
+#[test]
+fn the_profile_follows_the_entanglement_step_by_step() {
+    let circuit = "{\"steps\": [
+        {\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}]},
+        {\"index\": 1, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}]},
+        {\"index\": 2, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [2], \"controls\": [{\"target\": 1, \"state\": \"1\"}]}]},
+        {\"index\": 3, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [2], \"controls\": [{\"target\": 1, \"state\": \"1\"}]}]},
+        {\"index\": 4, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}]}
+    ]}";
+
+    let profile = simulator::get_entanglement_profile(circuit.to_string(), vec![2, 1], 2.0, None, None);
+
+    let steps:Vec<u16> = profile.iter().map(|(step_index, _)| *step_index).collect();
+    assert_eq!(steps, vec![0, 1, 2, 3, 4]);
+    let entropies:Vec<f64> = profile.iter().map(|(_, entanglement)| entanglement.von_neumann_entropy).collect();
+    for (entropy, expected) in entropies.iter().zip(&[0.0, 1.0, 1.0, 1.0, 0.0]) {
+        assert!((entropy - expected).abs() < 1e-6, "{:?}", entropies);
+    }
+}
+
+#[test]
+fn schmidt_coefficients_are_the_singular_values_of_the_reshaped_state() {
+    let unnormalized:Vec<Complex32> = (0..32).map(|i| Complex32::new(((i*7 % 11) as f32 - 5.0)/10.0, ((i*5 % 13) as f32 - 6.0)/10.0)).collect();
+    let norm = unnormalized.iter().map(|amplitude| amplitude.norm_sqr()).sum::<f32>().sqrt();
+    let statevector:Vec<Complex32> = unnormalized.iter().map(|amplitude| amplitude/norm).collect();
+
+    // rows are the states of qubits 0, 2 and 3, columns those of qubits 1 and 4, qubit 0 being the most significant bit
+    let matrix:Vec<Complex64> = (0..8).flat_map(|row:usize| (0..4).map(move |column:usize| (row, column))).map(|(row, column)| {
+        let index = (row >> 2 & 1) << 4 | (column >> 1 & 1) << 3 | (row >> 1 & 1) << 2 | (row & 1) << 1 | (column & 1);
+        Complex64::new(statevector[index].re as f64, statevector[index].im as f64)
+    }).collect();
+    let (_, expected, _) = linalg::svd(&matrix, 8, 4);
+
+    let coefficients = entanglement::get_schmidt_coefficients(&statevector, 5, &[0, 2, 3]);
+
+    assert_eq!(expected.len(), coefficients.len());
+    for (coefficient, expected_coefficient) in coefficients.iter().zip(&expected) {
+        assert!((coefficient - expected_coefficient).abs() < 1e-6, "{:?} {:?}", coefficients, expected);
+    }
+}
+
+#[test]
+fn low_rank_cuts_keep_only_their_nonzero_coefficients() {
+    // a GHZ state on 12 qubits has two Schmidt coefficients across any cut, the other 62 eigenvalues are zero
+    let mut statevector = vec![Complex32::new(0.0, 0.0); 1 << 12];
+    statevector[0] = Complex32::new(FRAC_1_SQRT_2 as f32, 0.0);
+    statevector[(1 << 12) - 1] = Complex32::new(0.0, FRAC_1_SQRT_2 as f32);
+
+    let coefficients = entanglement::get_schmidt_coefficients(&statevector, 12, &[0, 2, 4, 6, 8, 10]);
+    assert_eq!(2, coefficients.len());
+    for coefficient in &coefficients {
+        assert!((coefficient - FRAC_1_SQRT_2).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn a_cut_through_twenty_qubits_is_fast() {
+    let unnormalized:Vec<Complex32> = (0..1usize << 20).map(|i| Complex32::new(((i*7919 % 1013) as f32 - 506.0)/1013.0, ((i*104729 % 997) as f32 - 498.0)/997.0)).collect();
+    let norm = unnormalized.iter().map(|amplitude| amplitude.norm_sqr() as f64).sum::<f64>().sqrt() as f32;
+    let statevector:Vec<Complex32> = unnormalized.iter().map(|amplitude| amplitude/norm).collect();
+
+    let start = Instant::now();
+    let coefficients = entanglement::get_schmidt_coefficients(&statevector, 20, &(0..10).collect::<Vec<u8>>());
+    let elapsed = start.elapsed();
+
+    assert_eq!(1024, coefficients.len());
+    assert!((coefficients.iter().map(|coefficient| coefficient*coefficient).sum::<f64>() - 1.0).abs() < 1e-4);
+    assert!(coefficients.windows(2).all(|pair| pair[0] >= pair[1]));
+    // unoptimized builds are much slower, a release build takes about 2 seconds
+    let limit = if cfg!(debug_assertions) { 180 } else { 20 };
+    assert!(elapsed.as_secs() < limit, "{:?}", elapsed);
+}
+
+#[test]
+#[should_panic(expected = "The smaller side of the cut can have at most 12 qubits. Received 13 and 13 qubits")]
+fn oversized_cuts_are_rejected() {
+    // the cut is checked before the statevector is read
+    entanglement::get_schmidt_coefficients(&[], 26, &(0..13).collect::<Vec<u8>>());
+}
@@ -0,0 +1,62 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::initial_state::InitialState;
+use moara::simulator;
+
+#[test]
+fn bitstrings_and_product_states_follow_the_endianess() {
+    let circuit = "{\"steps\": [{\"index\": 0, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1]}]}]}";
+    let get_statevector = |text:&str, endianess:&str| simulator::get_statevector(circuit.to_string(), Some(endianess.to_string()), Some(3), Some(InitialState::parse(text)));
+
+    let statevector = get_statevector("100", "bigendian");
+    assert!((statevector[0b110].re - 1.0).abs() < 1e-5);
+
+    let statevector = get_statevector("001", "littleendian");
+    assert!((statevector[0b011].re - 1.0).abs() < 1e-5);
+
+    let statevector = get_statevector("+,0,-i", "bigendian");
+    let expected = [(0b010, 0.5, 0.0), (0b011, 0.0, -0.5), (0b110, 0.5, 0.0), (0b111, 0.0, -0.5)];
+    for (index, re, im) in expected.iter() {
+        assert!((statevector[*index].re - re).abs() < 1e-5 && (statevector[*index].im - im).abs() < 1e-5);
+    }
+    assert!((statevector.iter().map(|amplitude| amplitude.norm_sqr()).sum::<f32>() - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn a_stage_resumes_from_the_statevector_of_the_previous_stage() {
+    let first_stage = "{\"steps\": [{\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}, {\"name\": \"ry-theta\", \"targets\": [1], \"theta\": 0.7}]}]}";
+    let second_stage = "{\"steps\": [{\"index\": 0, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}]}, {\"index\": 1, \"gates\": [{\"name\": \"t\", \"targets\": [0]}]}]}";
+    let whole_circuit = "{\"steps\": [{\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}, {\"name\": \"ry-theta\", \"targets\": [1], \"theta\": 0.7}]}, {\"index\": 1, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}]}, {\"index\": 2, \"gates\": [{\"name\": \"t\", \"targets\": [0]}]}]}";
+
+    let intermediate = simulator::get_statevector(first_stage.to_string(), None, Some(2), None);
+    let mut saved = Vec::new();
+    moara::npy::write_complex(&intermediate, false, &mut saved).unwrap();
+
+    let statevector = simulator::get_statevector(second_stage.to_string(), None, Some(2), Some(InitialState::load(&saved)));
+    let expected = simulator::get_statevector(whole_circuit.to_string(), None, Some(2), None);
+
+    for (amplitude, expected_amplitude) in statevector.iter().zip(expected) {
+        assert!((amplitude - expected_amplitude).norm() < 1e-5);
+    }
+
+    let amplitudes = "{\"amplitudes\": [[0.6, 0.0], [0.0, 0.8]]}";
+    let probabilities = simulator::get_probabilities("{\"steps\": []}".to_string(), None, Some(1), Some(InitialState::load(amplitudes.as_bytes())));
+    assert!((probabilities[0] - 0.36).abs() < 1e-5 && (probabilities[1] - 0.64).abs() < 1e-5);
+}
+
+#[test]
+#[should_panic(expected = "The initial statevector is not normalized. The sum of the squared amplitudes is 0.5")]
+fn unnormalized_statevectors_are_rejected() {
+    let initial_state = InitialState::load(b"[[0.5, 0.0], [0.0, 0.5]]");
+
+    simulator::get_statevector("{\"steps\": []}".to_string(), None, Some(1), Some(initial_state));
+}
+
+#[test]
+fn text_is_loaded_as_json_or_parsed_as_a_state() {
+    assert_eq!(InitialState::Bitstring("01".to_string()), InitialState::from_text("01"));
+    assert_eq!(InitialState::Product(vec!["+".to_string(), "-i".to_string()]), InitialState::from_text("+,-i"));
+    assert_eq!(InitialState::Statevector(vec![num_complex::Complex32::new(0.0, 1.0)]), InitialState::from_text(" [[0.0, 1.0]]"));
+    assert_eq!(InitialState::from_text("{\"amplitudes\": [[1.0, 0.0]]}"), InitialState::load(b"[[1.0, 0.0]]"));
+}
@@ -0,0 +1,52 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::simulator;
+
+static CIRCUIT: &str = "{\"steps\": [
+    {\"index\": 0, \"gates\": [{\"name\": \"ry-theta\", \"targets\": [0], \"theta\": 0.3}, {\"name\": \"ry-theta\", \"targets\": [1], \"theta\": 1.1}, {\"name\": \"ry-theta\", \"targets\": [2], \"theta\": 2.2}, {\"name\": \"hadamard\", \"targets\": [3]}]},
+    {\"index\": 1, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [2], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}, {\"name\": \"ry-theta\", \"targets\": [1], \"theta\": 0.8, \"controls\": [{\"target\": 3, \"state\": \"1\"}]}]},
+    {\"index\": 2, \"gates\": [{\"name\": \"t\", \"targets\": [2]}, {\"name\": \"pauli-x\", \"targets\": [0], \"controls\": [{\"target\": 1, \"state\": \"1\"}]}]}
+]}";
+
+// Sums the probabilities of all the bitstrings, keeping the bits of the given qubits with the first one as the most significant bit
+fn get_expected_marginals(probabilities:&[f32], qubits:&[usize]) -> Vec<f32> {
+    let mut marginals = vec![0.0; 1 << qubits.len()];
+    for (index, probability) in probabilities.iter().enumerate() {
+        let marginal_index = qubits.iter().fold(0, |marginal_index, qubit| (marginal_index << 1) | ((index >> (3 - qubit)) & 1));
+        marginals[marginal_index] += probability;
+    }
+    marginals
+}
+
+#[test]
+fn marginals_sum_the_probabilities_of_the_other_qubits() {
+    let probabilities = simulator::get_probabilities(CIRCUIT.to_string(), None, Some(4), None);
+
+    for qubits in [vec![0u8], vec![2, 1], vec![3, 0, 2], vec![0, 1, 2, 3]].iter() {
+        let marginals = simulator::get_marginal_probabilities(CIRCUIT.to_string(), qubits.clone(), None, Some(4), None);
+        let expected = get_expected_marginals(&probabilities, &qubits.iter().map(|qubit| *qubit as usize).collect::<Vec<usize>>());
+
+        assert_eq!(marginals.len(), 1 << qubits.len());
+        for (marginal, expected_marginal) in marginals.iter().zip(expected) {
+            assert!((marginal - expected_marginal).abs() < 1e-5);
+        }
+    }
+}
+
+#[test]
+fn with_little_endian_the_first_qubit_is_the_least_significant_bit() {
+    let big_endian = simulator::get_marginal_probabilities(CIRCUIT.to_string(), vec![1, 2], None, Some(4), None);
+    let little_endian = simulator::get_marginal_probabilities(CIRCUIT.to_string(), vec![1, 2], Some("littleendian".to_string()), Some(4), None);
+
+    for (index, probability) in big_endian.iter().enumerate() {
+        let reversed_index = ((index & 1) << 1) | (index >> 1);
+        assert!((probability - little_endian[reversed_index]).abs() < 1e-6);
+    }
+}
+
+#[test]
+#[should_panic(expected = "The qubit 4 is not one of the 4 qubits of the circuit")]
+fn qubits_outside_the_circuit_are_rejected() {
+    simulator::get_marginal_probabilities(CIRCUIT.to_string(), vec![1, 4], None, Some(4), None);
+}
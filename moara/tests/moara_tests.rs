@@ -1,13 +1,13 @@
 #[cfg(test)]
 use std::process::Command;
 
-static PATH_TO_EXE: &str = "target/debug/moara.exe";
+static PATH_TO_EXE: &str = env!("CARGO_BIN_EXE_moara");
 static FAILURE_MESSAGE: &str = "failed to execute process";
 
 #[test]
 fn entanglement_circuit_works() {
     let output = Command::new(PATH_TO_EXE)
-                .args(&["sample","tests/entanglement_2.json", "-s", "1000"])
+                .args(&["sample","tests/entanglement_2.json", "-s", "1000", "--output-format", "legacy"])
                 .output()
                 .expect(FAILURE_MESSAGE);
 
@@ -26,7 +26,7 @@ fn entanglement_circuit_works() {
 #[test]
 fn gate_space_two_qubit_space_gate_works() {
     let output = Command::new(PATH_TO_EXE)
-                .args(&["sample","tests/gate_space_two_qubit_space_gate.json", "-s", "1000"])
+                .args(&["sample","tests/gate_space_two_qubit_space_gate.json", "-s", "1000", "--output-format", "legacy"])
                 .output()
                 .expect(FAILURE_MESSAGE);
 
@@ -48,7 +48,7 @@ fn gate_space_two_qubit_space_gate_works() {
 #[test]
 fn gate_space_two_qubit_works() {
     let output = Command::new(PATH_TO_EXE)
-                .args(&["sample","tests/gate_space_two_qubit.json", "-s", "1000"])
+                .args(&["sample","tests/gate_space_two_qubit.json", "-s", "1000", "--output-format", "legacy"])
                 .output()
                 .expect(FAILURE_MESSAGE);
 
@@ -67,6 +67,70 @@ fn gate_space_two_qubit_works() {
     }
 }
 
+#[test]
+fn json_output_has_the_settings_of_the_run() {
+    let run = || Command::new(PATH_TO_EXE)
+                .args(["sample","tests/entanglement_2.json", "-s", "1000", "--seed", "42"])
+                .output()
+                .expect(FAILURE_MESSAGE);
+    let output = run();
+
+    assert!(output.status.success());
+    let data: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(2, data["qubit_count"]);
+    assert_eq!("bigendian", data["endianess"]);
+    assert_eq!(1000, data["shots"]);
+    assert_eq!(42, data["seed"]);
+    assert_eq!(2, data["measured_bits"]);
+    assert!(data["runtime"].is_f64());
+    let counts = data["counts"].as_object().unwrap();
+    assert_eq!(vec!["00", "11"], counts.keys().collect::<Vec<_>>());
+    assert_eq!(1000, counts.values().map(|count| count.as_u64().unwrap()).sum::<u64>());
+
+    let rerun: serde_json::Value = serde_json::from_slice(&run().stdout).unwrap();
+    assert_eq!(data["counts"], rerun["counts"]);
+}
+
+#[test]
+fn output_formats_given_as_the_circuit_format_are_pointed_to_output_format() {
+    let output = Command::new(PATH_TO_EXE)
+                .args(["probabilities","tests/entanglement_2.json", "--format", "npy"])
+                .output()
+                .expect(FAILURE_MESSAGE);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("npy is an output format. Use --output-format npy."));
+}
+
+#[test]
+fn repl_sessions_over_the_qubit_limit_are_reported_without_a_panic() {
+    let output = Command::new(PATH_TO_EXE)
+                .args(["repl", "-q", "30"])
+                .output()
+                .expect(FAILURE_MESSAGE);
+
+    assert!(!output.status.success());
+    assert_eq!("The session is limited to 24 qubits. Received 30 qubits", String::from_utf8_lossy(&output.stdout).trim());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("panicked"));
+}
+
+#[test]
+fn runs_without_a_seed_report_the_seed_they_used() {
+    let run = |extra_args:&[&str]| {
+        let output = Command::new(PATH_TO_EXE)
+                .args(["sample","tests/entanglement_2.json", "-s", "1000"])
+                .args(extra_args)
+                .output()
+                .expect(FAILURE_MESSAGE);
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+    let data = run(&[]);
+
+    let seed = data["seed"].as_u64().unwrap().to_string();
+    assert_eq!(data["counts"], run(&["--seed", &seed])["counts"]);
+}
+
 fn get_vector_from_string(vector_as_string:String) -> Vec<u32> {
     let data: Vec<u32> = vector_as_string.trim_start_matches('[')
                                          .trim_end_matches(']')
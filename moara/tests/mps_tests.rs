@@ -0,0 +1,65 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::gates;
+use moara::simulator;
+
+#[test]
+fn bitstring_probabilities_match_statevector() {
+    let serialized = "{
+        \"steps\": [
+          { \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] }, { \"name\": \"ry-theta\", \"targets\": [ 2 ], \"theta\": 0.8 } ] },
+          { \"index\": 1, \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 3 ], \"controls\": [ { \"target\": 0, \"state\": \"1\" } ] } ] },
+          { \"index\": 2, \"gates\": [ { \"name\": \"iswap\", \"targets\": [ 3, 1 ] } ] },
+          { \"index\": 3, \"gates\": [ { \"name\": \"rx-theta\", \"targets\": [ 1 ], \"theta\": 1.1, \"controls\": [ { \"target\": 2, \"state\": \"+\" } ] } ] }
+        ]
+      }";
+
+    let probabilities = simulator::get_probabilities(serialized.to_string(), None, None, None);
+    let bitstrings:Vec<String> = (0..16).map(|i| format!("{:04b}", i)).collect();
+    let mps_probabilities = simulator::get_mps_bitstring_probabilities(serialized.to_string(), bitstrings, None, None, None, None);
+
+    for (probability, mps_probability) in probabilities.iter().zip(mps_probabilities.iter()) {
+        assert!((probability - mps_probability).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn ghz_state_on_many_qubits_works() {
+    let mut steps = vec![String::from("{ \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] } ] }")];
+    for i in 1..60 {
+        steps.push(format!("{{ \"index\": {}, \"gates\": [ {{ \"name\": \"pauli-x\", \"targets\": [ {} ], \"controls\": [ {{ \"target\": 0, \"state\": \"1\" }} ] }} ] }}", i, i));
+    }
+    let serialized = format!("{{ \"steps\": [ {} ] }}", steps.join(", "));
+
+    let (counts, truncation_error) = simulator::simulate_mps(serialized.clone(), 1000u32, None, None, Some(2), None, None);
+    assert_eq!(2, counts.len());
+    assert!(aprox_equals(500, counts[&"0".repeat(60)], 0.1));
+    assert!(aprox_equals(500, counts[&"1".repeat(60)], 0.1));
+    assert!(truncation_error < 1e-10);
+
+    let (mut state, _) = simulator::get_matrix_product_state(serialized, None, Some(2), None);
+    assert!(state.expectation_value(gates::pauli_z(), 30).abs() < 1e-5);
+    assert!((state.expectation_value(gates::identity(), 59) - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn truncation_error_is_reported() {
+    let serialized = "{
+        \"steps\": [
+          { \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] } ] },
+          { \"index\": 1, \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 1 ], \"controls\": [ { \"target\": 0, \"state\": \"1\" } ] } ] }
+        ]
+      }";
+
+    let (_, truncation_error) = simulator::simulate_mps(serialized.to_string(), 10u32, None, None, Some(1), None, None);
+    assert!((truncation_error - 0.5).abs() < 1e-5);
+}
+
+pub fn aprox_equals(a:u32, b:u32, fraction:f32) -> bool
+{
+    let diff = (b as i32 - a as i32).abs();
+    let sum = b+a;
+
+    (diff as f32) <= (sum as f32) * fraction
+}
@@ -0,0 +1,52 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::npy;
+use num_complex::Complex32;
+use moara::simulator;
+
+#[test]
+fn statevector_round_trips_in_both_precisions() {
+    let circuit = "{\"steps\": [{\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}, {\"name\": \"ry-theta\", \"targets\": [1], \"theta\": 0.3}]}, {\"index\": 1, \"gates\": [{\"name\": \"s\", \"targets\": [0]}]}]}";
+    let statevector = simulator::get_statevector(circuit.to_string(), None, Some(2), None);
+
+    for double_precision in [false, true].iter() {
+        let mut bytes = Vec::new();
+        npy::write_complex(&statevector, *double_precision, &mut bytes).unwrap();
+
+        assert_eq!(0, (bytes.len() - statevector.len() * if *double_precision { 16 } else { 8 }) % 64);
+        assert_eq!(statevector, npy::read_complex(&bytes));
+    }
+}
+
+#[test]
+fn arrays_saved_by_numpy_are_read() {
+    // numpy.save of numpy.array([0.6, 0.8], dtype=numpy.float32)
+    let mut bytes = b"\x93NUMPY\x01\x00\x76\x00".to_vec();
+    let header = "{'descr': '<f4', 'fortran_order': False, 'shape': (2,), }";
+    bytes.extend(header.as_bytes());
+    bytes.extend(" ".repeat(128 - 10 - header.len() - 1).as_bytes());
+    bytes.push(b'\n');
+    bytes.extend(&0.6f32.to_le_bytes());
+    bytes.extend(&0.8f32.to_le_bytes());
+
+    let statevector = npy::read_complex(&bytes);
+
+    assert_eq!(vec![Complex32::new(0.6, 0.0), Complex32::new(0.8, 0.0)], statevector);
+
+    let mut probabilities = Vec::new();
+    npy::write_float(&[0.36, 0.64], false, &mut probabilities).unwrap();
+    assert_eq!(bytes.len(), probabilities.len());
+    assert!(String::from_utf8_lossy(&probabilities).contains("{'descr': '<f4', 'fortran_order': False, 'shape': (2,), }"));
+}
+
+#[test]
+#[should_panic(expected = "Only one dimensional arrays are supported. Found the shape (2, 2)")]
+fn matrices_are_rejected() {
+    let mut bytes = Vec::new();
+    npy::write_float(&[0.0; 4], false, &mut bytes).unwrap();
+    let position = bytes.windows(7).position(|window| window == b"(4,), }").unwrap();
+    bytes.splice(position..position + 7, b"(2, 2)}".iter().cloned());
+
+    npy::read_complex(&bytes);
+}
@@ -0,0 +1,60 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::simulator;
+use num_complex::Complex32;
+
+static CIRCUIT: &str = "{
+    \"steps\": [
+      { \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] }, { \"name\": \"ry-theta\", \"targets\": [ 1 ], \"theta\": 0.5 }, { \"name\": \"u3\", \"targets\": [ 3 ], \"theta\": 1.2, \"phi\": 0.4, \"lambda\": -0.3 } ] },
+      { \"index\": 1, \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 2 ], \"controls\": [ { \"target\": 0, \"state\": \"0\" }, { \"target\": 1, \"state\": \"+i\" } ] } ] },
+      { \"index\": 2, \"gates\": [ { \"name\": \"iswap\", \"targets\": [ 2, 0 ], \"controls\": [ { \"target\": 1, \"state\": \"-\" } ] } ] },
+      { \"index\": 3, \"gates\": [ { \"name\": \"aggregate\", \"gates\": [ { \"name\": \"pauli-x-root-dagger\", \"targets\": [ 0 ], \"root\": \"2\" }, { \"name\": \"v\", \"targets\": [ 2 ] } ], \"controls\": [ { \"target\": 3, \"state\": \"-i\" } ] } ] },
+      { \"index\": 4, \"gates\": [ { \"name\": \"qft\", \"targets\": [ 0, 1, 2 ], \"controls\": [ { \"target\": 3, \"state\": \"+\" } ] } ] },
+      { \"index\": 5, \"gates\": [ { \"name\": \"cross-resonance\", \"targets\": [ 3, 1 ], \"theta\": 0.7 } ] },
+      { \"index\": 6, \"gates\": [ { \"name\": \"pauli-z-root\", \"targets\": [ 1 ], \"root\": \"1/2^3\", \"controls\": [ { \"target\": 0, \"state\": \"1\" }, { \"target\": 2, \"state\": \"1\" }, { \"target\": 3, \"state\": \"0\" } ] } ] },
+      { \"index\": 7, \"gates\": [ { \"name\": \"qft-dagger\", \"targets\": [ 3, 1 ] }, { \"name\": \"barrier\", \"targets\": [ 0, 2 ] } ] }
+    ]
+  }";
+
+#[test]
+fn exported_qasm2_reproduces_statevector() {
+    let qasm = moara::export_qasm(CIRCUIT.to_string(), None, String::from("qasm2"));
+    let (converted, qubit_count) = moara::convert_qasm(qasm);
+
+    let expected = simulator::get_statevector(CIRCUIT.to_string(), None, None, None);
+    let statevector = simulator::get_statevector(converted, None, Some(qubit_count), None);
+
+    // OpenQASM 2.0 drops the global phase of the uncontrolled gates
+    let overlap:Complex32 = expected.iter().zip(&statevector).map(|(a, b)| a.conj()*b).sum();
+    assert!((overlap.norm() - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn qasm3_uses_modifiers_for_controls() {
+    let qasm = moara::export_qasm(CIRCUIT.to_string(), None, String::from("qasm3"));
+
+    assert!(qasm.starts_with("OPENQASM 3.0;\ninclude \"stdgates.inc\";\n"));
+    assert!(qasm.contains("qubit[4] q;\n"));
+    assert!(qasm.contains("negctrl @ ctrl @ x q[0], q[1], q[2];\n"));
+    assert!(qasm.contains("gate iswap q0, q1 {"));
+    assert!(qasm.contains("ctrl @ iswap q[1], q[2], q[0];\n"));
+    assert!(qasm.contains("ctrl @ inv @ sx q[3], q[0];\n"));
+    assert!(qasm.contains("barrier q[0], q[2];\n"));
+}
+
+#[test]
+fn measurements_are_written_to_classical_bits() {
+    let serialized = "{
+        \"steps\": [
+          { \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] } ] },
+          { \"index\": 1, \"gates\": [ { \"name\": \"measure-z\", \"targets\": [ 0 ], \"bit\": 1 }, { \"name\": \"measure-y\", \"targets\": [ 1 ], \"bit\": 0 } ] }
+        ]
+      }";
+
+    let qasm2 = moara::export_qasm(serialized.to_string(), None, String::from("qasm2"));
+    assert!(qasm2.contains("creg c[2];\nh q[0];\nmeasure q[0] -> c[1];\nsdg q[1];\nh q[1];\nmeasure q[1] -> c[0];\n"));
+
+    let qasm3 = moara::export_qasm(serialized.to_string(), None, String::from("qasm3"));
+    assert!(qasm3.contains("bit[2] c;\nh q[0];\nc[1] = measure q[0];\nsdg q[1];\nh q[1];\nc[0] = measure q[1];\n"));
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::simulator;
+
+#[test]
+fn imported_program_matches_json_circuit() {
+    let qasm = "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        // rotation followed by an entangling gate
+        gate entangle(theta) a, b { ry(theta/2) a; cx a, b; }
+        qreg q[2];
+        qreg r[1];
+        creg c[3];
+        h q;
+        if (c==0) x r[0];
+        if (c==1) x q[0];
+        entangle(pi*0.5) q[0], q[1];
+        crz(-pi/4) q[1], r[0];
+        cswap r[0], q[0], q[1];
+        barrier q, r;
+    ";
+    let serialized = "{
+        \"steps\": [
+          { \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] }, { \"name\": \"hadamard\", \"targets\": [ 1 ] }, { \"name\": \"pauli-x\", \"targets\": [ 2 ] } ] },
+          { \"index\": 1, \"gates\": [ { \"name\": \"ry-theta\", \"targets\": [ 0 ], \"theta\": 0.7853982 } ] },
+          { \"index\": 2, \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 1 ], \"controls\": [ { \"target\": 0, \"state\": \"1\" } ] } ] },
+          { \"index\": 3, \"gates\": [ { \"name\": \"rz-theta\", \"targets\": [ 2 ], \"theta\": -0.7853982, \"controls\": [ { \"target\": 1, \"state\": \"1\" } ] } ] },
+          { \"index\": 4, \"gates\": [ { \"name\": \"swap\", \"targets\": [ 0, 1 ], \"controls\": [ { \"target\": 2, \"state\": \"1\" } ] } ] }
+        ]
+      }";
+
+    let (converted, qubit_count) = moara::convert_qasm(qasm.to_string());
+    assert_eq!(3, qubit_count);
+
+    let probabilities = simulator::get_probabilities(converted, None, Some(qubit_count), None);
+    let expected = simulator::get_probabilities(serialized.to_string(), None, Some(3), None);
+
+    assert_eq!(expected.len(), probabilities.len());
+    for (probability, expected_probability) in probabilities.iter().zip(expected) {
+        assert!((probability - expected_probability).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn measurements_are_mapped_to_classical_bits() {
+    let qasm = "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[3];
+        creg c[2];
+        x q[0];
+        rccx q[0], q[2], q[1];
+        x q[2];
+        measure q[2] -> c[0];
+        measure q[1] -> c[1];
+    ";
+
+    let (converted, qubit_count) = moara::convert_qasm(qasm.to_string());
+    let probabilities = simulator::get_probabilities(converted, None, Some(qubit_count), None);
+
+    assert_eq!(vec![0.0, 0.0, 1.0, 0.0], probabilities.iter().map(|probability| probability.round()).collect::<Vec<f32>>());
+}
+
+#[test]
+#[should_panic(expected = "depends on a measurement")]
+fn condition_on_measured_register_panics() {
+    let qasm = "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[2];
+        creg c[1];
+        h q[0];
+        measure q[0] -> c[0];
+        if (c==1) x q[1];
+    ";
+
+    moara::convert_qasm(qasm.to_string());
+}
+
+#[test]
+#[should_panic(expected = "have not been used yet")]
+fn reset_of_used_qubit_panics() {
+    let qasm = "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[1];
+        reset q[0];
+        h q[0];
+        reset q[0];
+    ";
+
+    moara::convert_qasm(qasm.to_string());
+}
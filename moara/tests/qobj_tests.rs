@@ -0,0 +1,128 @@
+#[cfg(test)]
+extern crate moara;
+
+use serde_json::Value;
+
+#[test]
+fn every_experiment_is_run() {
+    let qobj = r#"{
+        "qobj_id": "bell-and-flip",
+        "type": "QASM",
+        "schema_version": "1.3.0",
+        "header": {},
+        "config": { "shots": 100, "memory_slots": 3, "n_qubits": 3 },
+        "experiments": [
+            {
+                "header": { "name": "bell", "creg_sizes": [ [ "c", 2 ] ] },
+                "config": { "n_qubits": 2, "memory_slots": 2 },
+                "instructions": [
+                    { "name": "h", "qubits": [ 0 ] },
+                    { "name": "cx", "qubits": [ 0, 1 ] },
+                    { "name": "barrier", "qubits": [ 0, 1 ] },
+                    { "name": "measure", "qubits": [ 0, 1 ], "memory": [ 0, 1 ] }
+                ]
+            },
+            {
+                "header": { "name": "flip", "creg_sizes": [ [ "c", 3 ] ] },
+                "config": { "n_qubits": 3, "memory_slots": 3 },
+                "instructions": [
+                    { "name": "u3", "qubits": [ 2 ], "params": [ 3.141592653589793, 0.0, 3.141592653589793 ] },
+                    { "name": "mcx", "qubits": [ 2, 0, 1 ] },
+                    { "name": "measure", "qubits": [ 2 ], "memory": [ 0 ] },
+                    { "name": "measure", "qubits": [ 1 ], "memory": [ 2 ] }
+                ]
+            }
+        ]
+    }"#;
+
+    let result:Value = serde_json::from_str(&moara::run_qobj(qobj.to_string())).unwrap();
+    assert_eq!("bell-and-flip", result["qobj_id"]);
+    assert_eq!(true, result["success"]);
+
+    let results = result["results"].as_array().unwrap();
+    assert_eq!(2, results.len());
+
+    assert_eq!("bell", results[0]["header"]["name"]);
+    let bell_counts = results[0]["data"]["counts"].as_object().unwrap();
+    assert_eq!(100, bell_counts.values().map(|count| count.as_u64().unwrap()).sum::<u64>());
+    assert!(bell_counts.keys().all(|key| key == "0x0" || key == "0x3"));
+
+    assert_eq!("flip", results[1]["header"]["name"]);
+    assert_eq!(100, results[1]["data"]["counts"]["0x1"]);
+}
+
+#[test]
+fn conditions_on_unmeasured_memory_and_unitaries_are_applied() {
+    let qobj = r#"{
+        "qobj_id": "conditional",
+        "config": { "shots": 10 },
+        "experiments": [
+            {
+                "header": { "name": "conditional" },
+                "config": { "n_qubits": 2, "memory_slots": 2 },
+                "instructions": [
+                    { "name": "bfunc", "mask": "0x3", "relation": "==", "val": "0x0", "register": 2 },
+                    { "name": "x", "qubits": [ 0 ], "conditional": 2 },
+                    { "name": "bfunc", "mask": "0x3", "relation": "==", "val": "0x1", "register": 3 },
+                    { "name": "x", "qubits": [ 0 ], "conditional": 3 },
+                    { "name": "unitary", "qubits": [ 0, 1 ], "params": [ [
+                        [ [ 1.0, 0.0 ], [ 0.0, 0.0 ], [ 0.0, 0.0 ], [ 0.0, 0.0 ] ],
+                        [ [ 0.0, 0.0 ], [ 0.0, 0.0 ], [ 0.0, 0.0 ], [ 0.0, 1.0 ] ],
+                        [ [ 0.0, 0.0 ], [ 0.0, 0.0 ], [ 1.0, 0.0 ], [ 0.0, 0.0 ] ],
+                        [ [ 0.0, 0.0 ], [ 0.0, 1.0 ], [ 0.0, 0.0 ], [ 0.0, 0.0 ] ]
+                    ] ] },
+                    { "name": "measure", "qubits": [ 0, 1 ], "memory": [ 0, 1 ] }
+                ]
+            }
+        ]
+    }"#;
+
+    let result:Value = serde_json::from_str(&moara::run_qobj(qobj.to_string())).unwrap();
+
+    // the unitary is a controlled X with qubit 0 as the control, so both qubits end up in 1
+    assert_eq!(10, result["results"][0]["data"]["counts"]["0x3"]);
+}
+
+#[test]
+fn failing_experiments_do_not_stop_the_others() {
+    let qobj = r#"{
+        "qobj_id": "measured",
+        "config": { "shots": 10 },
+        "experiments": [
+            {
+                "header": { "name": "measured condition" },
+                "config": { "n_qubits": 2, "memory_slots": 1 },
+                "instructions": [
+                    { "name": "h", "qubits": [ 0 ] },
+                    { "name": "measure", "qubits": [ 0 ], "memory": [ 0 ] },
+                    { "name": "x", "qubits": [ 1 ], "conditional": { "mask": "0x1", "type": "equals", "val": "0x1" } }
+                ]
+            },
+            {
+                "header": { "name": "undeclared slot" },
+                "config": { "n_qubits": 1, "memory_slots": 1 },
+                "instructions": [ { "name": "measure", "qubits": [ 0 ], "memory": [ 40 ] } ]
+            },
+            {
+                "header": { "name": "high slot" },
+                "config": { "n_qubits": 1, "memory_slots": 71 },
+                "instructions": [
+                    { "name": "x", "qubits": [ 0 ] },
+                    { "name": "measure", "qubits": [ 0 ], "memory": [ 70 ] }
+                ]
+            }
+        ]
+    }"#;
+
+    let result:Value = serde_json::from_str(&moara::run_qobj(qobj.to_string())).unwrap();
+    assert_eq!(false, result["success"]);
+    assert_eq!("PARTIAL COMPLETED", result["status"]);
+
+    let results = result["results"].as_array().unwrap();
+    assert_eq!(false, results[0]["success"]);
+    assert!(results[0]["status"].as_str().unwrap().contains("depends on a measurement"));
+    assert_eq!(false, results[1]["success"]);
+    assert_eq!("ERROR: Invalid memory slot 40. The experiment has 1 memory slots", results[1]["status"]);
+    assert_eq!(true, results[2]["success"]);
+    assert_eq!(10, results[2]["data"]["counts"][format!("{:#x}", 1_u128 << 70)]);
+}
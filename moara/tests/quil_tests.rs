@@ -0,0 +1,82 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::simulator;
+
+#[test]
+fn defined_gates_with_modifiers_match_standard_gates() {
+    let quil = "
+        # controlled rotation written as a matrix
+        DEFGATE CROT(%theta):
+            1, 0, 0, 0
+            0, 1, 0, 0
+            0, 0, cos(%theta/2), -i*sin(%theta/2)
+            0, 0, -i*sin(%theta/2), cos(%theta/2)
+        DEFGATE TOFFOLI AS PERMUTATION:
+            0, 1, 2, 3, 4, 5, 7, 6
+        H 0
+        RY(pi/3) 1
+        CONTROLLED DAGGER CROT(2*pi/5) 0 1 2
+        DAGGER TOFFOLI 2 1 3
+        CONTROLLED CONTROLLED DAGGER PHASE(pi/4) 3 0 2
+        XY(pi/2) 0 1
+    ";
+    let expected = "
+        H 0
+        RY(pi/3) 1
+        CONTROLLED CONTROLLED RX(-2*pi/5) 0 1 2
+        CCNOT 2 1 3
+        CONTROLLED CPHASE(-pi/4) 3 0 2
+        XY(pi/2) 0 1
+    ";
+
+    let (converted, qubit_count) = moara::convert_quil(quil.to_string());
+    assert_eq!(4, qubit_count);
+
+    let (expected_converted, _) = moara::convert_quil(expected.to_string());
+    let statevector = simulator::get_statevector(converted, None, Some(qubit_count), None);
+    let expected_statevector = simulator::get_statevector(expected_converted, None, Some(qubit_count), None);
+
+    for (amplitude, expected_amplitude) in statevector.iter().zip(expected_statevector) {
+        assert!((amplitude - expected_amplitude).norm() < 1e-5);
+    }
+}
+
+#[test]
+fn measurements_are_mapped_to_memory_regions() {
+    let quil = "
+        DECLARE theta REAL[1]
+        DECLARE ro BIT[2]
+        DECLARE flag BIT
+        X 0
+        CNOT 0 2
+        MEASURE 2 flag
+        MEASURE 0 ro[1]
+        MEASURE 1 ro[0]
+        HALT
+        X 1
+    ";
+
+    let (converted, qubit_count) = moara::convert_quil(quil.to_string());
+    assert_eq!(3, qubit_count);
+
+    let probabilities = simulator::get_probabilities(converted, None, Some(qubit_count), None);
+    let mut expected = vec![0.0; 8];
+    expected[0b011] = 1.0;
+    assert_eq!(expected, probabilities.iter().map(|probability| probability.round()).collect::<Vec<f32>>());
+}
+
+#[test]
+#[should_panic(expected = "JUMP-WHEN at line 5 is not supported")]
+fn classical_control_flow_panics() {
+    let quil = "
+        DECLARE ro BIT
+        H 0
+        MEASURE 0 ro
+        JUMP-WHEN @end ro
+        X 0
+        LABEL @end
+    ";
+
+    moara::convert_quil(quil.to_string());
+}
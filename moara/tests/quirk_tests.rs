@@ -0,0 +1,54 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::simulator;
+
+#[test]
+fn roots_and_control_states_match_moara_gates() {
+    let quirk = r#"{"init":[0,"-"],"cols":[["H"],["•","Z^¼"],["X^½","◦"],["Swap","Swap"],["Z^-½",1,"⊕"],[1,"Y^-¼",1,"⊗"]]}"#;
+    let expected = r#"{"steps":[
+        {"index":0,"gates":[{"name":"pauli-x","targets":[1]}]},
+        {"index":1,"gates":[{"name":"hadamard","targets":[1]},{"name":"hadamard","targets":[0]}]},
+        {"index":2,"gates":[{"name":"t","targets":[1],"controls":[{"target":0,"state":"1"}]}]},
+        {"index":3,"gates":[{"name":"v","targets":[0],"controls":[{"target":1,"state":"0"}]}]},
+        {"index":4,"gates":[{"name":"swap","targets":[0,1]}]},
+        {"index":5,"gates":[{"name":"s-dagger","targets":[0],"controls":[{"target":2,"state":"+"}]}]},
+        {"index":6,"gates":[{"name":"pauli-y-root-dagger","root":"4","targets":[1],"controls":[{"target":3,"state":"-i"}]}]}
+    ]}"#;
+
+    let (converted, qubit_count) = moara::convert_quirk(quirk.to_string());
+    assert_eq!(4, qubit_count);
+
+    let statevector = simulator::get_statevector(converted, None, Some(qubit_count), None);
+    let expected_statevector = simulator::get_statevector(expected.to_string(), None, Some(qubit_count), None);
+
+    for (amplitude, expected_amplitude) in statevector.iter().zip(expected_statevector) {
+        assert!((amplitude - expected_amplitude).norm() < 1e-5);
+    }
+}
+
+#[test]
+fn urls_with_shared_controls_and_measurements_are_read() {
+    // {"cols":[["H"],["•","X","X"],["Measure","Measure","Measure"],["Chance3"]]}
+    let url = "https://algassert.com/quirk#circuit=%7B%22cols%22%3A%5B%5B%22H%22%5D%2C%5B%22%E2%80%A2%22%2C%22X%22%2C%22X%22%5D%2C\
+        %5B%22Measure%22%2C%22Measure%22%2C%22Measure%22%5D%2C%5B%22Chance3%22%5D%5D%7D";
+
+    let (converted, qubit_count) = moara::convert_quirk(url.to_string());
+    assert_eq!(3, qubit_count);
+
+    let probabilities = simulator::get_probabilities(converted, None, Some(qubit_count), None);
+    let mut expected = vec![0.0; 8];
+    expected[0b000] = 0.5;
+    expected[0b111] = 0.5;
+    for (probability, expected_probability) in probabilities.iter().zip(expected) {
+        assert!((probability - expected_probability).abs() < 1e-5);
+    }
+}
+
+#[test]
+#[should_panic(expected = "Quirk gates that are not supported: QFT3, X^t, Z^ft(pi t)")]
+fn unsupported_gates_are_listed() {
+    let quirk = r#"{"cols":[["QFT3"],["X^t"],["H"],["QFT3"],[{"id":"Z^ft","arg":"pi t"}]]}"#;
+
+    moara::convert_quirk(quirk.to_string());
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::repl::Session;
+use moara::simulator;
+
+#[test]
+fn typed_gates_match_the_simulator() {
+    let mut session = Session::new(1);
+    for line in ["h 0", "cx 0 1", "rz 2 pi/3", "ry-theta 1 theta=0.4 controls=2"] {
+        assert_eq!(Ok(String::new()), session.execute(line));
+    }
+
+    let circuit = "{\"steps\": [
+        {\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}]},
+        {\"index\": 1, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}]},
+        {\"index\": 2, \"gates\": [{\"name\": \"rz-theta\", \"targets\": [2], \"theta\": 1.0471976}]},
+        {\"index\": 3, \"gates\": [{\"name\": \"ry-theta\", \"targets\": [1], \"theta\": 0.4, \"controls\": [{\"target\": 2, \"state\": \"1\"}]}]}
+    ]}";
+    let expected = simulator::get_statevector(circuit.to_string(), None, None, None);
+
+    assert_eq!(3, session.get_qubit_count());
+    for (amplitude, expected_amplitude) in session.get_statevector().iter().zip(expected) {
+        assert!((amplitude - expected_amplitude).norm() < 1e-5);
+    }
+    assert_eq!(Ok(String::from("000: 0.500000\n110: 0.500000")), session.execute("probs"));
+}
+
+#[test]
+fn undo_and_errors_keep_the_previous_gates() {
+    let mut session = Session::new(2);
+    session.execute("x 0").unwrap();
+    session.execute("h 1").unwrap();
+    session.execute("undo").unwrap();
+    assert_eq!(Ok(String::from("10: 1.000000")), session.execute("probs"));
+
+    assert_eq!(Err(String::from("cx takes 2 qubits and 0 angles. Received 1 arguments")), session.execute("cx 0"));
+    assert_eq!(Err(String::from("Invalid qubit a")), session.execute("h a"));
+    assert_eq!(Ok(String::from("10: 1.000000+0.000000i")), session.execute("amps"));
+
+    session.execute("reset").unwrap();
+    assert_eq!(Err(String::from("There is no gate to undo")), session.execute("undo"));
+}
+
+#[test]
+fn saved_circuits_load_back() {
+    let path = std::env::temp_dir().join(format!("moara_repl_{}.json", std::process::id()));
+    let filename = path.to_str().unwrap();
+
+    let mut session = Session::new(1);
+    session.execute("h 0").unwrap();
+    session.execute("cx 0 2").unwrap();
+    session.execute("measure 2 0").unwrap();
+    assert_eq!(Ok(format!("Saved 3 gates to {}", filename)), session.execute(&format!("save {}", filename)));
+
+    let mut loaded = Session::new(1);
+    assert_eq!(Ok(String::from("Loaded 3 gates on 3 qubits")), loaded.execute(&format!("load {}", filename)));
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(session.execute("circuit"), loaded.execute("circuit"));
+    assert_eq!(Ok(String::from("0: 0.500000\n1: 0.500000")), loaded.execute("probs"));
+
+    // a missing file leaves the session as it was
+    assert!(loaded.execute(&format!("load {}", filename)).unwrap_err().starts_with("Cannot read"));
+    assert_eq!(3, loaded.get_qubit_count());
+}
+
+#[test]
+#[should_panic(expected = "The session is limited to 24 qubits. Received 30 qubits")]
+fn sessions_are_limited_to_24_qubits() {
+    Session::new(30);
+}
+
+#[test]
+fn failed_gates_do_not_grow_the_session() {
+    let mut session = Session::new(1);
+    session.execute("h 0").unwrap();
+
+    assert_eq!(Err(String::from("Unknown operator foo")), session.execute("foo 3"));
+    assert_eq!(1, session.get_qubit_count());
+    assert_eq!(2, session.get_statevector().len());
+
+    assert!(session.execute("ry-theta 4").is_err());
+    assert_eq!(1, session.get_qubit_count());
+    assert_eq!(Ok(String::from("0: 0.500000\n1: 0.500000")), session.execute("probs"));
+}
@@ -20,7 +20,7 @@ fn simple_circuit_works() {
         ]
       }";
 
-      let results = simulator::simulate(serialized.to_string(), 1000u32, Some("bigendian".to_string()), Some(1u8));
+      let results = simulator::simulate(serialized.to_string(), 1000u32, Some("bigendian".to_string()), Some(1u8), None, None);
       assert_eq!(2, results.len());
       assert!(aprox_equals(500, results[0], 0.1));
       assert!(aprox_equals(500, results[1], 0.1));
@@ -32,4 +32,94 @@ pub fn aprox_equals(a:u32, b:u32, fraction:f32) -> bool
     let sum = b+a;
 
     (diff as f32) <= (sum as f32) * fraction
-}
\ No newline at end of file
+}
+
+#[test]
+fn controls_on_x_basis_states_work() {
+    // qubit 0 is in |-⟩, so only the control on - flips its target, and the relative phase of qubit 0 is kept
+    let serialized = "{
+        \"steps\": [
+            {
+                \"index\": 0,
+                \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 0 ] } ]
+            },
+            {
+                \"index\": 1,
+                \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] } ]
+            },
+            {
+                \"index\": 2,
+                \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 1 ], \"controls\": [ { \"target\": 0, \"state\": \"+\" } ] } ]
+            },
+            {
+                \"index\": 3,
+                \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 2 ], \"controls\": [ { \"target\": 0, \"state\": \"-\" } ] } ]
+            }
+        ]
+    }";
+
+    let statevector = simulator::get_statevector(serialized.to_string(), Some("bigendian".to_string()), Some(3u8), None);
+    let amplitude = std::f32::consts::FRAC_1_SQRT_2;
+    let expected = [0.0, amplitude, 0.0, 0.0, 0.0, -amplitude, 0.0, 0.0];
+    assert_eq!(8, statevector.len());
+    for (value, expected_value) in statevector.iter().zip(expected.iter()) {
+        assert!((value.re - expected_value).abs() < 1e-6 && value.im.abs() < 1e-6);
+    }
+}
+
+#[test]
+fn controls_on_y_basis_states_work() {
+    // qubit 0 is in |+i⟩, so only the control on +i flips its target
+    let serialized = "{
+        \"steps\": [
+            {
+                \"index\": 0,
+                \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] } ]
+            },
+            {
+                \"index\": 1,
+                \"gates\": [ { \"name\": \"s\", \"targets\": [ 0 ] } ]
+            },
+            {
+                \"index\": 2,
+                \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 1 ], \"controls\": [ { \"target\": 0, \"state\": \"+i\" } ] } ]
+            },
+            {
+                \"index\": 3,
+                \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 2 ], \"controls\": [ { \"target\": 0, \"state\": \"-i\" } ] } ]
+            }
+        ]
+    }";
+
+    let statevector = simulator::get_statevector(serialized.to_string(), Some("bigendian".to_string()), Some(3u8), None);
+    let amplitude = std::f32::consts::FRAC_1_SQRT_2;
+    let expected = [(0.0, 0.0), (0.0, 0.0), (amplitude, 0.0), (0.0, 0.0), (0.0, 0.0), (0.0, 0.0), (0.0, amplitude), (0.0, 0.0)];
+    assert_eq!(8, statevector.len());
+    for (value, (re, im)) in statevector.iter().zip(expected.iter()) {
+        assert!((value.re - re).abs() < 1e-6 && (value.im - im).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn controls_on_mixed_bases_work() {
+    // qubit 0 is in |0⟩ = (|+⟩ + |-⟩)/√2 and qubit 1 in |1⟩, so only the |+⟩ part flips the target
+    let serialized = "{
+        \"steps\": [
+            {
+                \"index\": 0,
+                \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 1 ] } ]
+            },
+            {
+                \"index\": 1,
+                \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 2 ], \"controls\": [ { \"target\": 0, \"state\": \"+\" }, { \"target\": 1, \"state\": \"1\" } ] } ]
+            }
+        ]
+    }";
+
+    let statevector = simulator::get_statevector(serialized.to_string(), Some("bigendian".to_string()), Some(3u8), None);
+    let expected = [0.0, 0.0, 0.5, 0.5, 0.0, 0.0, -0.5, 0.5];
+    assert_eq!(8, statevector.len());
+    for (value, expected_value) in statevector.iter().zip(expected.iter()) {
+        assert!((value.re - expected_value).abs() < 1e-6 && value.im.abs() < 1e-6);
+    }
+}
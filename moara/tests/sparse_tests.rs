@@ -0,0 +1,51 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::simulator;
+
+#[test]
+fn probabilities_match_statevector() {
+    let serialized = "{
+        \"steps\": [
+          { \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] }, { \"name\": \"ry-theta\", \"targets\": [ 2 ], \"theta\": 0.8 } ] },
+          { \"index\": 1, \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 3 ], \"controls\": [ { \"target\": 0, \"state\": \"1\" }, { \"target\": 2, \"state\": \"-i\" } ] } ] },
+          { \"index\": 2, \"gates\": [ { \"name\": \"iswap\", \"targets\": [ 3, 1 ], \"controls\": [ { \"target\": 0, \"state\": \"+\" } ] } ] },
+          { \"index\": 3, \"gates\": [ { \"name\": \"qft\", \"targets\": [ 1, 2, 3 ] } ] },
+          { \"index\": 4, \"gates\": [ { \"name\": \"measure-z\", \"targets\": [ 3 ], \"bit\": 0 }, { \"name\": \"measure-x\", \"targets\": [ 0 ], \"bit\": 1 } ] }
+        ]
+      }";
+
+    let probabilities = simulator::get_probabilities(serialized.to_string(), None, None, None);
+    let sparse_probabilities = simulator::get_sparse_probabilities(serialized.to_string(), None, None);
+
+    assert_eq!(4, probabilities.len());
+    for (i, probability) in probabilities.iter().enumerate() {
+        let sparse_probability = sparse_probabilities.get(&format!("{:02b}", i)).unwrap_or(&0.0);
+        assert!((probability - sparse_probability).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn permutation_on_many_qubits_works() {
+    // |x⟩ -> |x+1⟩ on a 48 qubit register, starting from |2^47 - 1⟩ plus a superposed control
+    let qubit_count = 48;
+    let mut steps = vec![String::from("{ \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] } ] }")];
+    let setup:Vec<String> = (1..qubit_count).map(|qubit| format!("{{ \"name\": \"pauli-x\", \"targets\": [ {} ] }}", qubit)).collect();
+    steps.push(format!("{{ \"index\": 1, \"gates\": [ {} ] }}", setup.join(", ")));
+    for (index, target) in (1..qubit_count).enumerate() {
+        let controls:Vec<String> = (target+1..qubit_count).map(|qubit| format!("{{ \"target\": {}, \"state\": \"1\" }}", qubit)).collect();
+        steps.push(format!("{{ \"index\": {}, \"gates\": [ {{ \"name\": \"pauli-x\", \"targets\": [ {} ], \"controls\": [ {} ] }} ] }}", index + 2, target, controls.join(", ")));
+    }
+    let serialized = format!("{{ \"steps\": [ {} ] }}", steps.join(", "));
+
+    let probabilities = simulator::get_sparse_probabilities(serialized.clone(), None, None);
+    assert_eq!(2, probabilities.len());
+    assert!((probabilities[&format!("0{}", "0".repeat(47))] - 0.5).abs() < 1e-5);
+    assert!((probabilities[&format!("1{}", "0".repeat(47))] - 0.5).abs() < 1e-5);
+
+    let counts = simulator::simulate_sparse(serialized, 100u32, Some("littleendian".to_string()), None, None);
+    assert_eq!(100, counts.values().sum::<u32>());
+    for bitstring in counts.keys() {
+        assert!(bitstring.starts_with(&"0".repeat(47)));
+    }
+}
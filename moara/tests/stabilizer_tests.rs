@@ -0,0 +1,84 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::simulator;
+
+#[test]
+fn bell_state_works() {
+    let serialized = "{
+        \"steps\": [
+          { \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] } ] },
+          { \"index\": 1, \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 1 ], \"controls\": [ { \"target\": 0, \"state\": \"1\" } ] } ] }
+        ]
+      }";
+
+    let results = simulator::simulate_stabilizer(serialized.to_string(), 1000u32, None, None, None);
+    assert_eq!(2, results.len());
+    assert!(aprox_equals(500, results["00"], 0.1));
+    assert!(aprox_equals(500, results["11"], 0.1));
+}
+
+#[test]
+fn ghz_state_on_many_qubits_works() {
+    let mut gates = vec![String::from("{ \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] } ] }")];
+    for i in 1..200 {
+        gates.push(format!("{{ \"index\": {}, \"gates\": [ {{ \"name\": \"pauli-x\", \"targets\": [ {} ], \"controls\": [ {{ \"target\": {}, \"state\": \"1\" }} ] }} ] }}", i, i, i-1));
+    }
+    let serialized = format!("{{ \"steps\": [ {} ] }}", gates.join(", "));
+
+    let results = simulator::simulate_stabilizer(serialized, 100u32, None, None, None);
+    for bitstring in results.keys() {
+        assert_eq!(200, bitstring.len());
+        assert!(bitstring == &"0".repeat(200) || bitstring == &"1".repeat(200));
+    }
+}
+
+#[test]
+fn measurement_bits_and_phases_work() {
+    let serialized = "{
+        \"steps\": [
+          { \"index\": 0, \"gates\": [ { \"name\": \"hadamard\", \"targets\": [ 0 ] }, { \"name\": \"pauli-x\", \"targets\": [ 2 ] } ] },
+          { \"index\": 1, \"gates\": [ { \"name\": \"s\", \"targets\": [ 0 ] } ] },
+          { \"index\": 2, \"gates\": [ { \"name\": \"s\", \"targets\": [ 0 ] } ] },
+          { \"index\": 3, \"gates\": [ { \"name\": \"pauli-z\", \"targets\": [ 1 ], \"controls\": [ { \"target\": 0, \"state\": \"-\" } ] } ] },
+          { \"index\": 4, \"gates\": [ { \"name\": \"measure-x\", \"targets\": [ 0 ], \"bit\": 1 }, { \"name\": \"measure-z\", \"targets\": [ 2 ], \"bit\": 0 } ] }
+        ]
+      }";
+
+    let results = simulator::simulate_stabilizer(serialized.to_string(), 100u32, None, Some(3u8), None);
+    assert_eq!(1, results.len());
+    assert_eq!(100, results["11"]);
+}
+
+#[test]
+#[should_panic(expected = "is not a Clifford gate")]
+fn non_clifford_gate_is_rejected() {
+    let serialized = "{
+        \"steps\": [
+          { \"index\": 0, \"gates\": [ { \"name\": \"t\", \"targets\": [ 0 ] } ] }
+        ]
+      }";
+
+    simulator::simulate_stabilizer(serialized.to_string(), 10u32, None, None, None);
+}
+
+#[test]
+#[should_panic(expected = "No targets provided for gate hadamard at step 1")]
+fn gates_without_targets_are_rejected() {
+    let serialized = "{
+        \"steps\": [
+          { \"index\": 0, \"gates\": [ { \"name\": \"pauli-x\", \"targets\": [ 0 ] } ] },
+          { \"index\": 1, \"gates\": [ { \"name\": \"hadamard\" } ] }
+        ]
+      }";
+
+    simulator::simulate_stabilizer(serialized.to_string(), 10u32, None, Some(2), None);
+}
+
+pub fn aprox_equals(a:u32, b:u32, fraction:f32) -> bool
+{
+    let diff = (b as i32 - a as i32).abs();
+    let sum = b+a;
+
+    (diff as f32) <= (sum as f32) * fraction
+}
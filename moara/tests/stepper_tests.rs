@@ -0,0 +1,107 @@
+#[cfg(test)]
+extern crate moara;
+
+use moara::simulator;
+
+static CIRCUIT: &str = "{\"steps\": [
+    {\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}, {\"name\": \"ry-theta\", \"targets\": [2], \"theta\": 0.6}]},
+    {\"index\": 1, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}, {\"name\": \"snapshot\", \"label\": \"bell\"}]},
+    {\"index\": 2, \"gates\": [{\"name\": \"t\", \"targets\": [1]}, {\"name\": \"hadamard\", \"targets\": [2]}]},
+    {\"index\": 3, \"gates\": [{\"name\": \"snapshot\", \"label\": \"end\"}]}
+]}";
+
+static PREFIXES: [&str; 3] = [
+    "{\"steps\": [{\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}, {\"name\": \"ry-theta\", \"targets\": [2], \"theta\": 0.6}]}]}",
+    "{\"steps\": [{\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}, {\"name\": \"ry-theta\", \"targets\": [2], \"theta\": 0.6}]},
+        {\"index\": 1, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}]}]}",
+    "{\"steps\": [{\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}, {\"name\": \"ry-theta\", \"targets\": [2], \"theta\": 0.6}]},
+        {\"index\": 1, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}]},
+        {\"index\": 2, \"gates\": [{\"name\": \"t\", \"targets\": [1]}, {\"name\": \"hadamard\", \"targets\": [2]}]}]}",
+];
+
+#[test]
+fn each_step_gives_the_state_of_the_circuit_cut_after_it() {
+    let endianess = Some("littleendian".to_string());
+    let statevectors = simulator::get_step_statevectors(CIRCUIT.to_string(), endianess.clone(), None, None);
+    let probabilities = simulator::get_step_probabilities(CIRCUIT.to_string(), endianess.clone(), None, None);
+
+    let steps:Vec<u16> = statevectors.iter().map(|(step_index, _)| *step_index).collect();
+    assert_eq!(steps, vec![0, 1, 2, 3]);
+
+    for (index, prefix) in PREFIXES.iter().enumerate() {
+        let expected = simulator::get_statevector(prefix.to_string(), endianess.clone(), Some(3), None);
+        for (amplitude, expected_amplitude) in statevectors[index].1.iter().zip(&expected) {
+            assert!((amplitude - expected_amplitude).norm() < 1e-6);
+        }
+        for (probability, expected_amplitude) in probabilities[index].1.iter().zip(&expected) {
+            assert!((probability - expected_amplitude.norm_sqr()).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn snapshots_are_returned_with_the_final_results() {
+    let (statevector, snapshots) = simulator::get_statevector_with_snapshots(CIRCUIT.to_string(), None, None, None);
+    let (probabilities, probability_snapshots) = simulator::get_probabilities_with_snapshots(CIRCUIT.to_string(), None, None, None);
+
+    let labels:Vec<(&str, u16)> = snapshots.iter().map(|snapshot| (snapshot.label.as_str(), snapshot.step_index)).collect();
+    assert_eq!(labels, vec![("bell", 1), ("end", 3)]);
+    assert_eq!(probability_snapshots.len(), 2);
+
+    let bell = simulator::get_statevector(PREFIXES[1].to_string(), None, Some(3), None);
+    for (amplitude, expected_amplitude) in snapshots[0].statevector.iter().zip(&bell) {
+        assert!((amplitude - expected_amplitude).norm() < 1e-6);
+    }
+    for (index, amplitude) in snapshots[1].statevector.iter().enumerate() {
+        assert!((amplitude - statevector[index]).norm() < 1e-6);
+        assert!((snapshots[1].probabilities[index] - probabilities[index]).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn the_stepper_reports_the_measurements_made_so_far() {
+    let circuit = "{\"steps\": [
+        {\"index\": 0, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1]}]},
+        {\"index\": 1, \"gates\": [{\"name\": \"measure-z\", \"targets\": [1], \"bit\": 0}]},
+        {\"index\": 2, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}]}
+    ]}";
+    let mut stepper = simulator::get_stepper(circuit.to_string(), None, Some(2), None);
+
+    assert_eq!(stepper.step(), Some(0));
+    assert_eq!(stepper.get_probabilities().len(), 4);
+    assert!(stepper.get_measurements().is_empty());
+
+    assert_eq!(stepper.step(), Some(1));
+    assert_eq!(stepper.get_measurements().get(&1), Some(&0));
+    let probabilities = stepper.get_probabilities();
+    assert_eq!(probabilities.len(), 2);
+    assert!((probabilities[1] - 1.0).abs() < 1e-6);
+
+    assert_eq!(stepper.step(), Some(2));
+    assert_eq!(stepper.step(), None);
+    let (statevector, snapshots) = stepper.finish();
+    assert!((statevector[0b01].norm_sqr() - 0.5).abs() < 1e-6 && (statevector[0b11].norm_sqr() - 0.5).abs() < 1e-6);
+    assert!(snapshots.is_empty());
+}
+
+#[test]
+fn snapshots_in_definitions_keep_their_label() {
+    let circuit = "{\"definitions\": [{\"name\": \"bell\", \"qubits\": 2, \"steps\": [
+            {\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}]},
+            {\"index\": 1, \"gates\": [{\"name\": \"pauli-x\", \"targets\": [1], \"controls\": [{\"target\": 0, \"state\": \"1\"}]}, {\"name\": \"snapshot\", \"label\": \"bell\"}]}
+        ]}],
+        \"steps\": [{\"index\": 0, \"gates\": [{\"name\": \"bell\", \"targets\": [0, 1], \"controls\": [{\"target\": 2, \"state\": \"0\"}]}]}]}";
+
+    let (_, snapshots) = simulator::get_probabilities_with_snapshots(circuit.to_string(), None, Some(3), None);
+
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].label, "bell");
+    assert!((snapshots[0].probabilities[0b000] - 0.5).abs() < 1e-6 && (snapshots[0].probabilities[0b110] - 0.5).abs() < 1e-6);
+}
+
+#[test]
+#[should_panic(expected = "The snapshot of step 0 has no label")]
+fn snapshots_need_a_label() {
+    let circuit = "{\"steps\": [{\"index\": 0, \"gates\": [{\"name\": \"hadamard\", \"targets\": [0]}, {\"name\": \"snapshot\"}]}]}";
+    simulator::get_statevector_with_snapshots(circuit.to_string(), None, Some(1), None);
+}
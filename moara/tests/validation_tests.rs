@@ -0,0 +1,110 @@
+#[cfg(test)]
+extern crate moara;
+
+#[test]
+fn converted_circuits_are_valid() {
+    let qasm = "
+        OPENQASM 2.0;
+        include \"qelib1.inc\";
+        qreg q[3];
+        creg c[3];
+        h q[0];
+        cx q[0], q[1];
+        u3(0.1, 0.2, 0.3) q[2];
+        cswap q[0], q[1], q[2];
+        measure q -> c;
+    ";
+    let (converted, _) = moara::convert_qasm(qasm.to_string());
+
+    let circuit = r#"{
+        "definitions": [
+            {"name": "rotate", "qubits": 1, "parameters": ["angle"], "steps": [
+                {"index": 0, "gates": [{"name": "ry-theta", "targets": [0], "theta": "angle/2"}, {"name": "barrier", "targets": [0]}]}
+            ]}
+        ],
+        "steps": [
+            {"index": 0, "gates": [{"name": "rotate", "targets": [1], "arguments": {"angle": "pi/2"}, "controls": [{"target": 0, "state": "-i"}]}]},
+            {"index": 1, "gates": [{"name": "aggregate", "gates": [{"name": "pauli-x-root", "targets": [0], "root": "1/2^3"}, {"name": "u1", "targets": [0], "lambda": "0.5"}]}]},
+            {"index": 2, "gates": [{"name": "measure-z", "targets": [0], "bit": 1}, {"name": "qft", "targets": [1, 2]}]}
+        ]
+    }"#;
+
+    assert!(moara::validate(converted).is_empty());
+    assert!(moara::validate(circuit.to_string()).is_empty());
+}
+
+#[test]
+fn all_problems_are_reported_with_their_location() {
+    let circuit = r#"{
+        "steps": [
+            {"index": 0, "gates": [
+                {"name": "hadamart", "targets": [0]},
+                {"name": "rx-theta", "targets": [1], "thetha": 0.5},
+                {"name": "pauli-z-root", "targets": [2], "root": "1/2^x"},
+                {"name": "swap", "targets": [2, 3], "controls": [{"target": 4, "state": "+j"}]}
+            ]},
+            {"index": 1, "gates": [{"name": "measure-z", "targets": [0]}]},
+            {"index": 2, "gates": [{"name": "pauli-x", "targets": [1], "controls": [{"target": 0, "state": "1"}]}]}
+        ]
+    }"#;
+
+    let problems = moara::validate(circuit.to_string());
+
+    assert_eq!(vec![
+        "/steps/0/gates/1: unknown property thetha",
+        "/steps/0/gates/3/controls/0/state: \"+j\" is not one of \"0\", \"1\", \"+\", \"-\", \"+i\", \"-i\"",
+        "step 0 gate 0 (hadamart): unknown gate hadamart",
+        "step 0 gate 1 (rx-theta): rx-theta needs a value for theta",
+        "step 0 gate 2 (pauli-z-root): invalid root 1/2^x. Use k, 2^k, 1/k or 1/2^k",
+        "step 0 gate 3 (swap): the qubit 2 is mentioned twice in the step",
+        "step 2 gate 0 (pauli-x): the qubit 0 is used after its measurement at step 1",
+    ], problems);
+}
+
+#[test]
+fn definitions_are_checked() {
+    let circuit = r#"{
+        "definitions": [
+            {"name": "bell", "qubits": 2, "parameters": ["theta"], "steps": [
+                {"index": 0, "gates": [{"name": "hadamard", "targets": [0]}, {"name": "rz-theta", "targets": [2], "theta": "theta + phi"}]},
+                {"index": 1, "gates": [{"name": "measure-z", "targets": [1]}]}
+            ]}
+        ],
+        "steps": [
+            {"index": 0, "gates": [{"name": "bell", "targets": [0], "arguments": {"angle": 1}}]}
+        ]
+    }"#;
+
+    let problems = moara::validate(circuit.to_string());
+
+    assert_eq!(vec![
+        "definition bell step 0 gate 1 (rz-theta): the qubit 2 is not one of the 2 qubits of the definition",
+        "definition bell step 0 gate 1 (rz-theta): unknown parameter phi in theta + phi",
+        "definition bell step 1 gate 0 (measure-z): measurements are not supported in definitions",
+        "step 0 gate 0 (bell): the definition acts on 2 qubits. Found 1 targets",
+        "step 0 gate 0 (bell): no argument for the parameter theta",
+        "step 0 gate 0 (bell): the definition has no parameter angle",
+    ], problems);
+}
+
+#[test]
+fn snapshots_need_a_label() {
+    let circuit = r#"{
+        "definitions": [
+            {"name": "prepare", "qubits": 1, "steps": [{"index": 0, "gates": [{"name": "hadamard", "targets": [0]}, {"name": "snapshot"}]}]}
+        ],
+        "steps": [
+            {"index": 0, "gates": [{"name": "prepare", "targets": [0]}]},
+            {"index": 1, "gates": [{"name": "snapshot", "label": "prepared"}, {"name": "hadamard", "targets": [0], "label": "ignored"}]},
+            {"index": 2, "gates": [{"name": "snapshot", "targets": [0]}]}
+        ]
+    }"#;
+
+    let problems = moara::validate(circuit.to_string());
+
+    assert_eq!(vec![
+        "/definitions/0/steps/0/gates/1: missing label",
+        "/steps/2/gates/0: missing label",
+        "step 2 gate 0 (snapshot): snapshots record the whole state and take no qubits",
+    ], problems);
+}
@@ -1,7 +1,10 @@
 from qiskit.providers.models import QasmBackendConfiguration
 from qiskit.providers import BaseBackend
-from .moara_for_qiskit import simulate
+from qiskit.result import Result
+from .moara_for_qiskit import run_qobj
 import json
+import warnings
+import numpy as np
 
 class MoaraBackend(BaseBackend):
     
@@ -21,75 +24,29 @@ class MoaraBackend(BaseBackend):
         'max_shots': MAX_SHOTS,
         'description': 'A simulator',
         'coupling_map': None,
-        'basis_gates': ['cx','id', 'x', 'y', 'z', 'h', 's', 'sdg', 't', 'tdg', 'u3', 'u2', 'u', 'sx', 'rx', 'ry', 'rz', 'swap', 'crx'],
+        'basis_gates': ['u1', 'u2', 'u3', 'u', 'p', 'id', 'x', 'y', 'z', 'h', 's', 'sdg', 't', 'tdg', 'sx', 'sxdg', 'rx', 'ry', 'rz',
+            'cx', 'cy', 'cz', 'ch', 'csx', 'crx', 'cry', 'crz', 'cu1', 'cp', 'cu3', 'cu', 'swap', 'rxx', 'ryy', 'rzz',
+            'ccx', 'cswap', 'c3x', 'c4x', 'c3sqrtx', 'mcx', 'unitary'],
         'gates': []
 }
 
-    def __init__(self, little_endian=False):
+    def __init__(self, little_endian=None):
        super().__init__(QasmBackendConfiguration.from_dict(self.CONFIGURATION), None)
-       self.little_endian = little_endian
+       # the results follow the bit order of Qiskit, so the endianess is no longer needed
+       if little_endian is not None:
+           warnings.warn('The little_endian argument of MoaraBackend is deprecated and has no effect', DeprecationWarning, stacklevel=2)
 
     def run(self, qobj, backend_options=None, noise_model=None, validate=False):
-        if not qobj or not qobj.experiments:
-            return {}
-        experiment = qobj.experiments[0]
-
-        if len(qobj.experiments) > 1:
-            raise Exception('Multiple experiments are not supported yet.')
-
-        circuit = { "steps":[] }
-        index = 0
-        for instruction in experiment.instructions:
-            if not instruction or len(instruction.qubits) == 0:
-                continue
-
-            gate = None
-            if instruction.name == 'id':
-                gate = { 'name': 'identity', 'target':instruction.qubits[0] }
-            elif instruction.name == 'x':
-                gate = { 'name': 'pauli-x', 'target':instruction.qubits[0] }
-            elif instruction.name == 'y':
-                gate = { 'name': 'pauli-y', 'target':instruction.qubits[0] }
-            elif instruction.name == 'z':
-                gate = { 'name': 'pauli-z', 'target':instruction.qubits[0] }
-            elif instruction.name == 'h':   
-                gate = { 'name': 'hadamard', 'target':instruction.qubits[0] }
-            elif instruction.name == 'sx':   
-                gate = { 'name': 'sqrt_not', 'target':instruction.qubits[0] }
-            elif instruction.name == 't':   
-                gate = { 'name': 't', 'target':instruction.qubits[0] }
-            elif instruction.name == 'tdg':   
-                gate = { 'name': 't_dagger', 'target':instruction.qubits[0] }
-            elif instruction.name == 's':   
-                gate = { 'name': 's', 'target':instruction.qubits[0] }
-            elif instruction.name == 'sdg':   
-                gate = { 'name': 's_dagger', 'target':instruction.qubits[0] }
-
-            elif instruction.name == 'u3':
-                gate = { 'name': 'u', 'target':instruction.qubits[0],'theta': instruction.params[0], 'phi': instruction.params[1], 'lambda': instruction.params[2] }
-            elif instruction.name == 'u2':
-                gate = { 'name': 'u-phi-theta', 'target':instruction.qubits[0], 'phi': instruction.params[0], 'theta': instruction.params[1] }
-            elif instruction.name == 'rx':
-                gate = { 'name': 'rx-phi', 'target':instruction.qubits[0], 'phi': instruction.params[0] }
-            elif instruction.name == 'ry':
-                gate = { 'name': 'ry-phi', 'target':instruction.qubits[0], 'phi': instruction.params[0] }
-            elif instruction.name == 'rz':
-                gate = { 'name': 'rz-phi', 'target':instruction.qubits[0], 'phi': instruction.params[0] }
-
-            elif instruction.name == 'cx':
-                gate = { 'name': 'ctrl-pauli-x', 'control':instruction.qubits[0], 'target':instruction.qubits[1] }
-            elif instruction.name == 'crx':
-                gate = { 'name': 'ctrl-rx-phi', 'control':instruction.qubits[0], 'target':instruction.qubits[1], 'phi': instruction.params[0] }
-            elif instruction.name == 'swap':
-                gate = { 'name': 'swap', 'target':instruction.qubits[0], 'target2':instruction.qubits[1] }
-            elif instruction.name == 'measure':
-                gate = { 'name': 'measure-z', 'target':instruction.qubits[0] }
-
-            if gate:
-                step = { 'index':index, 'gates':[gate] }
-                circuit['steps'].append(step)
-                index += 1
-                
-        serializedCircuit = json.dumps(circuit)
-        
-        return simulate(serializedCircuit, qobj.config.shots, experiment.config.n_qubits, self.little_endian)
\ No newline at end of file
+        # numpy arrays and complex numbers (the matrices of unitary instructions) are not JSON serializable
+        def encode(value):
+            if isinstance(value, complex):
+                return [value.real, value.imag]
+            if isinstance(value, np.ndarray):
+                return value.tolist()
+            raise TypeError('Object of type {} is not JSON serializable'.format(type(value).__name__))
+
+        result = json.loads(run_qobj(json.dumps(qobj.to_dict(), default=encode)))
+        result['backend_name'] = self.NAME
+        result['backend_version'] = self.VERSION
+
+        return Result.from_dict(result)
@@ -1,53 +1,20 @@
 use pyo3::prelude::*;
 use pyo3::wrap_pyfunction;
-use std::collections::HashMap;
 
 use moara;
 
 #[pyfunction]
-#[text_signature = "(serialized_circuit, shots, qubit_count)"]
-pub fn simulate(serialized_circuit:String, shots:u32, qubit_count:Option<u8>, little_endian:bool) -> PyResult<HashMap<String, u32>> {
+#[pyo3(text_signature = "(qobj_json)")]
+pub fn run_qobj(qobj_json:String) -> PyResult<String> {
     
-    let result_vector = moara::simulate(serialized_circuit, shots, qubit_count);
+    let result = moara::run_qobj(qobj_json);
 
-    let results = convert_to_hashmap(result_vector, little_endian);
-
-    Ok(results)
+    Ok(result)
 }
 
 #[pymodule]
 fn moara_for_qiskit(_py: Python, m: &PyModule) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(simulate, m)?)?;
+    m.add_function(wrap_pyfunction!(run_qobj, m)?)?;
 
     Ok(())
 }
-
-fn convert_to_hashmap(result_vector:Vec<u32>, little_endian:bool) -> HashMap<String, u32> {
-    let qubit_count = (result_vector.len() as f64).log2() as usize;
-    let mut results = HashMap::new();
-    for (i, count) in result_vector.iter().enumerate() {
-        if *count > 0u32 {
-            let binary = format!("{:b}", i);
-            let mut padded = pad_to_width(binary, qubit_count);
-            if little_endian {
-                padded = padded.chars().rev().collect::<String>()
-            }
-            results.insert(padded, *count);
-        }
-    }
-    results
-}
-
-fn pad_to_width(original:String, width:usize) -> String {
-    if width <= original.len() {
-        return original;
-    }
-    
-    let pad_count = width - original.len();
-    let mut padded = String::with_capacity(width);
-    for _ in 0..pad_count {
-        padded.push_str("0");
-    }
-    padded.push_str(&original);
-    padded
-}
\ No newline at end of file
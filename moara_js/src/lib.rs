@@ -1,18 +1,40 @@
 use js_sys::Array;
 use wasm_bindgen::prelude::*;
 use moara;
+use moara::initial_state::InitialState;
 
 #[wasm_bindgen]
-pub fn simulate(serialized_circuit:String, shots:u32, endianess:Option<String>, qubit_count:Option<u8>) -> Vec<u32> {
-    moara::simulate(serialized_circuit, shots, endianess, qubit_count)
+pub fn simulate(serialized_circuit:String, shots:u32, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<String>) -> Vec<u32> {
+    moara::simulate(serialized_circuit, shots, endianess, qubit_count, None, initial_state.as_deref().map(InitialState::from_text))
 }
 
 #[wasm_bindgen]
-pub fn get_probabilities(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>) -> Vec<f32> {
-    moara::get_probabilities(serialized_circuit, endianess, qubit_count)
+pub fn get_probabilities(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<String>) -> Vec<f32> {
+    moara::get_probabilities(serialized_circuit, endianess, qubit_count, initial_state.as_deref().map(InitialState::from_text))
 }
 
 #[wasm_bindgen]
-pub fn get_statevector(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>) -> Array {
-    moara::get_statevector(serialized_circuit, endianess, qubit_count).into_iter().map(|i| JsValue::from_str(&i.to_string())).collect()
-}
\ No newline at end of file
+pub fn get_statevector(serialized_circuit:String, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<String>) -> Array {
+    moara::get_statevector(serialized_circuit, endianess, qubit_count, initial_state.as_deref().map(InitialState::from_text)).into_iter().map(|i| JsValue::from_str(&i.to_string())).collect()
+}
+
+#[wasm_bindgen]
+pub fn get_amplitudes(serialized_circuit:String, bitstrings:Vec<String>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<String>) -> Array {
+    moara::get_amplitudes(serialized_circuit, bitstrings, endianess, qubit_count, initial_state.as_deref().map(InitialState::from_text)).into_iter().map(|i| JsValue::from_str(&i.to_string())).collect()
+}
+
+#[wasm_bindgen]
+pub fn get_bitstring_probabilities(serialized_circuit:String, bitstrings:Vec<String>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<String>) -> Vec<f32> {
+    moara::get_bitstring_probabilities(serialized_circuit, bitstrings, endianess, qubit_count, initial_state.as_deref().map(InitialState::from_text))
+}
+
+#[wasm_bindgen]
+pub fn get_marginal_probabilities(serialized_circuit:String, qubits:Vec<u8>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<String>) -> Vec<f32> {
+    moara::get_marginal_probabilities(serialized_circuit, qubits, endianess, qubit_count, initial_state.as_deref().map(InitialState::from_text))
+}
+
+// The matrix is flattened row by row
+#[wasm_bindgen]
+pub fn get_reduced_density_matrix(serialized_circuit:String, qubits:Vec<u8>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<String>) -> Array {
+    moara::get_reduced_density_matrix(serialized_circuit, qubits, endianess, qubit_count, initial_state.as_deref().map(InitialState::from_text)).into_iter().map(|i| JsValue::from_str(&i.to_string())).collect()
+}
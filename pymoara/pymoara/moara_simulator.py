@@ -1,7 +1,4 @@
-import json
-from .pymoara import simulate
-from .cirq_parser import CirqParser
-from .pyquil_parser import PyquilParser
+from .pymoara import simulate, convert_quil, convert_cirq
 
 class MoaraSimulator():
 
@@ -9,17 +6,16 @@ class MoaraSimulator():
         if not circuit:
             return {}
         
-        moara_circuit = None
+        serializedCircuit = None
         if hasattr(circuit, '_moments'):
-            moara_circuit, qubits = CirqParser().parse(circuit)
+            import cirq
+            serializedCircuit, qubits = convert_cirq(cirq.to_json(circuit))
         elif hasattr(circuit, '_instructions'):
-            moara_circuit, qubits = PyquilParser().parse(circuit)
+            serializedCircuit, qubits = convert_quil(circuit.out())
         elif hasattr(circuit, '_data'):
             raise Exception("For use with Qiskit use moara_for_qiskit")
 
-        if moara_circuit == None:
+        if serializedCircuit == None:
             raise Exception("Could not detect circuit source")
-
-        serializedCircuit = json.dumps(moara_circuit)
         
         return simulate(serializedCircuit, shots, qubits)
@@ -2,12 +2,93 @@ use pyo3::prelude::*;
 use pyo3::wrap_pyfunction;
 
 use moara as moara_base;
+use moara::initial_state::InitialState;
 
 #[pyfunction]
-#[text_signature = "(serialized_circuit, shots, qubit_count)"]
-pub fn simulate(serialized_circuit:String, shots:u32, qubit_count:Option<u8>) -> PyResult<Vec<u32>> {
+#[pyo3(text_signature = "(serialized_circuit, shots, qubit_count, initial_state)")]
+pub fn simulate(serialized_circuit:String, shots:u32, qubit_count:Option<u8>, initial_state:Option<String>) -> PyResult<Vec<u32>> {
     
-    let result = moara_base::simulate(serialized_circuit, shots, qubit_count);
+    let result = moara_base::simulate(serialized_circuit, shots, None, qubit_count, None, initial_state.as_deref().map(InitialState::from_text));
+
+    Ok(result)
+}
+
+#[pyfunction]
+#[pyo3(text_signature = "(quil_source)")]
+pub fn convert_quil(quil_source:String) -> PyResult<(String, u8)> {
+
+    let result = moara_base::convert_quil(quil_source);
+
+    Ok(result)
+}
+
+#[pyfunction]
+#[pyo3(text_signature = "(cirq_json)")]
+pub fn convert_cirq(cirq_json:String) -> PyResult<(String, u8)> {
+
+    let result = moara_base::convert_cirq(cirq_json);
+
+    Ok(result)
+}
+
+// Returns the (re, im) pair of each bitstring
+#[pyfunction]
+#[pyo3(text_signature = "(serialized_circuit, bitstrings, endianess, qubit_count, initial_state)")]
+pub fn get_amplitudes(serialized_circuit:String, bitstrings:Vec<String>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<String>) -> PyResult<Vec<(f32, f32)>> {
+
+    let amplitudes = moara_base::get_amplitudes(serialized_circuit, bitstrings, endianess, qubit_count, initial_state.as_deref().map(InitialState::from_text));
+    let result = amplitudes.iter().map(|amplitude| (amplitude.re, amplitude.im)).collect();
+
+    Ok(result)
+}
+
+#[pyfunction]
+#[pyo3(text_signature = "(serialized_circuit, bitstrings, endianess, qubit_count, initial_state)")]
+pub fn get_bitstring_probabilities(serialized_circuit:String, bitstrings:Vec<String>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<String>) -> PyResult<Vec<f32>> {
+
+    let result = moara_base::get_bitstring_probabilities(serialized_circuit, bitstrings, endianess, qubit_count, initial_state.as_deref().map(InitialState::from_text));
+
+    Ok(result)
+}
+
+#[pyfunction]
+#[pyo3(text_signature = "(serialized_circuit, qubits, endianess, qubit_count, initial_state)")]
+pub fn get_marginal_probabilities(serialized_circuit:String, qubits:Vec<u8>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<String>) -> PyResult<Vec<f32>> {
+
+    let result = moara_base::get_marginal_probabilities(serialized_circuit, qubits, endianess, qubit_count, initial_state.as_deref().map(InitialState::from_text));
+
+    Ok(result)
+}
+
+// Each row of the matrix is a list of (re, im) pairs
+#[pyfunction]
+#[pyo3(text_signature = "(serialized_circuit, qubits, endianess, qubit_count, initial_state)")]
+pub fn get_reduced_density_matrix(serialized_circuit:String, qubits:Vec<u8>, endianess:Option<String>, qubit_count:Option<u8>, initial_state:Option<String>) -> PyResult<Vec<Vec<(f32, f32)>>> {
+
+    let size = 1 << qubits.len();
+    let matrix = moara_base::get_reduced_density_matrix(serialized_circuit, qubits, endianess, qubit_count, initial_state.as_deref().map(InitialState::from_text));
+    let result = matrix.chunks(size).map(|row| row.iter().map(|value| (value.re, value.im)).collect()).collect();
+
+    Ok(result)
+}
+
+// Returns the Schmidt coefficients, the von Neumann entropy and the Rényi entropy of the given order, in bits
+#[pyfunction]
+#[pyo3(text_signature = "(serialized_circuit, qubits, renyi_order, qubit_count, initial_state)")]
+pub fn get_entanglement(serialized_circuit:String, qubits:Vec<u8>, renyi_order:f64, qubit_count:Option<u8>, initial_state:Option<String>) -> PyResult<(Vec<f64>, f64, f64)> {
+
+    let entanglement = moara_base::get_entanglement(serialized_circuit, qubits, renyi_order, qubit_count, initial_state.as_deref().map(InitialState::from_text));
+
+    Ok((entanglement.schmidt_coefficients, entanglement.von_neumann_entropy, entanglement.renyi_entropy))
+}
+
+// Returns the step index, the Schmidt coefficients and the entropies after each step
+#[pyfunction]
+#[pyo3(text_signature = "(serialized_circuit, qubits, renyi_order, qubit_count, initial_state)")]
+pub fn get_entanglement_profile(serialized_circuit:String, qubits:Vec<u8>, renyi_order:f64, qubit_count:Option<u8>, initial_state:Option<String>) -> PyResult<Vec<(u16, Vec<f64>, f64, f64)>> {
+
+    let profile = moara_base::get_entanglement_profile(serialized_circuit, qubits, renyi_order, qubit_count, initial_state.as_deref().map(InitialState::from_text));
+    let result = profile.into_iter().map(|(step_index, entanglement)| (step_index, entanglement.schmidt_coefficients, entanglement.von_neumann_entropy, entanglement.renyi_entropy)).collect();
 
     Ok(result)
 }
@@ -15,6 +96,14 @@ pub fn simulate(serialized_circuit:String, shots:u32, qubit_count:Option<u8>) ->
 #[pymodule]
 fn pymoara(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(simulate, m)?)?;
+    m.add_function(wrap_pyfunction!(convert_quil, m)?)?;
+    m.add_function(wrap_pyfunction!(convert_cirq, m)?)?;
+    m.add_function(wrap_pyfunction!(get_amplitudes, m)?)?;
+    m.add_function(wrap_pyfunction!(get_bitstring_probabilities, m)?)?;
+    m.add_function(wrap_pyfunction!(get_marginal_probabilities, m)?)?;
+    m.add_function(wrap_pyfunction!(get_reduced_density_matrix, m)?)?;
+    m.add_function(wrap_pyfunction!(get_entanglement, m)?)?;
+    m.add_function(wrap_pyfunction!(get_entanglement_profile, m)?)?;
 
     Ok(())
 }
\ No newline at end of file